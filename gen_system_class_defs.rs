@@ -1,14 +1,31 @@
-use std::ffi::OsStr;
+use std::env;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
-use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::process::Command;
 
 use std::iter;
 
+// Shared with the crate, which exports the settings and has the tests.
+#[path = "src/manifest.rs"]
+#[allow(dead_code)]
+mod manifest;
+
+use manifest::{manifest_xml, ManifestSettings};
+
 fn main() {
+    gen_system_classes();
+
+    if env::var_os("CARGO_FEATURE_MANIFEST").is_some() {
+        embed_manifest(&ManifestSettings::default());
+    }
+}
+
+fn gen_system_classes() {
     let system_classes_file = File::open("system_classes.ini").unwrap();
-    let mut out_file = File::create("src/ffi/system_classes.rs").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let mut out_file = File::create(Path::new(&out_dir).join("system_classes.rs")).unwrap();
 
     let system_classes_file = BufReader::new(system_classes_file);
 
@@ -16,11 +33,68 @@ fn main() {
         let eq = line.find('=').expect("INI file is not in the proper format!");
 
         let ident = &line[..eq];
-        let val: &OsStr = line[eq + 1..].as_ref();
 
-        let val_u16: Vec<u16> = val.encode_wide()
+        // Common controls carry the `ICC_*` flag that has to be passed to
+        // `InitCommonControlsEx()` before the class can be instantiated.
+        let (val, init_flag) = match line[eq + 1..].find(',') {
+            Some(comma) => (&line[eq + 1..eq + 1 + comma], &line[eq + 2 + comma..]),
+            None => (&line[eq + 1..], "0"),
+        };
+
+        let val_u16: Vec<u16> = val.encode_utf16()
             .chain(iter::once(0)).collect();
 
-        writeln!(out_file, "pub const {}: SystemClass = SystemClass(&{:?});", ident, val_u16).unwrap();
+        writeln!(
+            out_file, "pub const {}: SystemClass = SystemClass(&{:?}, {});",
+            ident, val_u16, init_flag
+        ).unwrap();
+    }
+}
+
+/// Compile the manifest into a resource and have it linked into the executables using this
+/// crate. Does nothing when not targeting Windows.
+///
+/// A static library would be bundled into this crate's rlib, where the linker would drop it
+/// as nothing refers to it. Instead the compiled resource is passed to the final link as a
+/// dynamic library, which rustc hands to the linker as it is: `link.exe` recognizes compiled
+/// resources whatever they're called, and GNU `ld` links all of an object file found as
+/// `lib<name>.a`, where it would only take the referenced members of an archive.
+fn embed_manifest(settings: &ManifestSettings) {
+    let target = env::var("TARGET").unwrap();
+
+    if !target.contains("windows") {
+        return;
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir);
+
+    let manifest_path = out_dir.join("wingui.manifest");
+    File::create(&manifest_path).unwrap()
+        .write_all(manifest_xml(settings).as_bytes()).unwrap();
+
+    // 1 is `CREATEPROCESS_MANIFEST_RESOURCE_ID` and 24 is `RT_MANIFEST`.
+    let rc_path = out_dir.join("wingui_manifest.rc");
+    writeln!(
+        File::create(&rc_path).unwrap(), "1 24 \"{}\"",
+        manifest_path.display().to_string().replace('\\', "\\\\")
+    ).unwrap();
+
+    if target.contains("msvc") {
+        run(Command::new("rc.exe").arg("/nologo").arg("/fo")
+            .arg(out_dir.join("wingui_manifest.lib")).arg(&rc_path));
+    } else {
+        run(Command::new("windres").arg(&rc_path).arg("-O").arg("coff")
+            .arg("-o").arg(out_dir.join("libwingui_manifest.a")));
     }
+
+    println!("cargo:rustc-link-search=native={}", out_dir.display());
+    println!("cargo:rustc-link-lib=dylib=wingui_manifest");
+}
+
+fn run(command: &mut Command) {
+    let status = command.status()
+        .unwrap_or_else(|err| panic!("Failed to run {:?}: {}", command, err));
+
+    assert!(status.success(), "{:?} failed with {}", command, status);
 }
@@ -1,8 +1,110 @@
-use ::ffi::{BorrowHandle, WindowHandle, WindowEvents};
+use user32;
+
+use color::{self, Color};
+use dpi::Dpi;
+use focus;
+use layout::{Rect, Size};
+
+use winapi::*;
+
+use std::{mem, ptr};
 
 /// Generic operations supported by all window types.
-pub trait AbsWindow: BorrowHandle<W> where W: WindowEvents {
+pub trait AbsWindow {
     /// Get the backing pointer to this Window.
     #[doc(hidden)]
-    fn ptr(&self) -> WindowPtr; 
+    fn ptr(&self) -> HWND; 
+
+    /// The position and size of this window, relative to the client area of its parent
+    /// (or the screen, for top-level windows), in pixels.
+    fn bounds(&self) -> Rect {
+        unsafe {
+            let mut rect: RECT = mem::zeroed();
+            user32::GetWindowRect(self.ptr(), &mut rect);
+
+            let parent = user32::GetParent(self.ptr());
+
+            if !parent.is_null() {
+                user32::MapWindowPoints(ptr::null_mut(), parent, &mut rect as *mut RECT as *mut POINT, 2);
+            }
+
+            Rect::new(rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top)
+        }
+    }
+
+    /// The size this window would like to be, given `available` space, both in logical
+    /// units (see the `dpi` module).
+    ///
+    /// Controls showing text measure it in their font; the result may be larger than
+    /// `available` if the content doesn't fit. By default this is the current size.
+    fn measure(&self, _available: Size) -> Size {
+        Dpi::of_window(self).unscale_size(self.bounds().size())
+    }
+
+    /// Fill the background of this window with `color`, or go back to the default with
+    /// `None`.
+    ///
+    /// For standard controls this only works if the parent is a window from this crate,
+    /// which reflects `WM_CTLCOLOR*` back to the control.
+    fn set_background_color(&self, color: Option<Color>) {
+        color::set_background(self.ptr(), color);
+    }
+
+    /// Draw the text of this window in `color`, or go back to the default with `None`.
+    fn set_text_color(&self, color: Option<Color>) {
+        color::set_text(self.ptr(), color);
+    }
+
+    /// Give this window the keyboard focus.
+    fn focus(&self) {
+        unsafe {
+            user32::SetFocus(self.ptr());
+        }
+    }
+
+    fn has_focus(&self) -> bool {
+        unsafe { user32::GetFocus() == self.ptr() }
+    }
+
+    /// Called when this window gets the keyboard focus.
+    fn on_focus<F>(&self, on_focus: F) where F: FnMut() + 'static, Self: Sized {
+        focus::set_on_focus(self.ptr(), Box::new(on_focus));
+    }
+
+    /// Called when this window loses the keyboard focus.
+    fn on_blur<F>(&self, on_blur: F) where F: FnMut() + 'static, Self: Sized {
+        focus::set_on_blur(self.ptr(), Box::new(on_blur));
+    }
+
+    /// Put this window at `index` in the order Tab moves the focus between it and its
+    /// siblings. Siblings without an index come after those with one, in the order they
+    /// were created.
+    fn set_tab_index(&self, index: u32) {
+        focus::set_tab_index(self.ptr(), index);
+    }
+
+    fn tab_index(&self) -> Option<u32> {
+        focus::tab_index(self.ptr())
+    }
+
+    /// Start a new group at this window for moving the focus with the arrow keys, which
+    /// stay within the siblings from one window starting a group to the next in Tab order.
+    fn set_group_start(&self, start: bool) {
+        unsafe {
+            let style = user32::GetWindowLongPtrW(self.ptr(), GWL_STYLE) as DWORD;
+            let style = if start { style | WS_GROUP } else { style & !WS_GROUP };
+            user32::SetWindowLongPtrW(self.ptr(), GWL_STYLE, style as LONG_PTR);
+        }
+    }
+
+    /// Move and resize this window, in the same coordinates as `bounds()`.
+    fn set_bounds(&self, bounds: Rect) {
+        unsafe {
+            user32::SetWindowPos(
+                self.ptr(), ptr::null_mut(),
+                bounds.x, bounds.y, bounds.width, bounds.height,
+                SWP_NOZORDER | SWP_NOACTIVATE
+            );
+        }
+    }
 }
@@ -0,0 +1,125 @@
+//! Accelerator tables: the keyboard shortcuts bound to commands on each window.
+//!
+//! `start()` translates keys with the tables of the window a message is for and of its
+//! parents, so a shortcut works while the focus is anywhere inside the window it's bound
+//! to. The command is sent to that window as `WM_COMMAND`.
+
+use user32;
+
+use winapi::*;
+
+use command::CommandId;
+use ffi::user::{TranslateAcceleratorW, FALT, FCONTROL, FSHIFT, FVIRTKEY};
+use input::Accelerator;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr;
+
+struct Table {
+    bindings: Vec<(Accelerator, CommandId)>,
+    haccel: HACCEL,
+}
+
+impl Table {
+    fn new() -> Table {
+        Table { bindings: vec![], haccel: ptr::null_mut() }
+    }
+
+    /// Recreate the Windows table from the bindings, since tables can't be changed.
+    fn rebuild(&mut self) {
+        unsafe {
+            if !self.haccel.is_null() {
+                user32::DestroyAcceleratorTable(self.haccel);
+            }
+
+            let flag = |down, flag| if down { flag } else { 0 };
+
+            let mut accels: Vec<ACCEL> = self.bindings.iter().map(|&(accelerator, CommandId(id))| {
+                let modifiers = accelerator.modifiers;
+
+                ACCEL {
+                    fVirt: FVIRTKEY | flag(modifiers.ctrl, FCONTROL) | flag(modifiers.shift, FSHIFT) |
+                        flag(modifiers.alt, FALT),
+                    key: accelerator.key.vk() as WORD,
+                    cmd: id,
+                }
+            }).collect();
+
+            self.haccel = if accels.is_empty() {
+                ptr::null_mut()
+            } else {
+                user32::CreateAcceleratorTableW(accels.as_mut_ptr(), accels.len() as c_int)
+            };
+        }
+    }
+}
+
+impl Drop for Table {
+    fn drop(&mut self) {
+        if !self.haccel.is_null() {
+            unsafe {
+                user32::DestroyAcceleratorTable(self.haccel);
+            }
+        }
+    }
+}
+
+thread_local!(static TABLES: RefCell<HashMap<HWND, Table>> = RefCell::new(HashMap::new()));
+
+/// Bind `accelerator` to `id` on `hwnd`, replacing what it was bound to before.
+pub fn bind(hwnd: HWND, accelerator: Accelerator, id: CommandId) {
+    TABLES.with(|tables| {
+        let mut tables = tables.borrow_mut();
+        let table = tables.entry(hwnd).or_insert_with(Table::new);
+
+        table.bindings.retain(|&(bound, _)| bound != accelerator);
+        table.bindings.push((accelerator, id));
+        table.rebuild();
+    });
+}
+
+pub fn unbind(hwnd: HWND, accelerator: Accelerator) {
+    TABLES.with(|tables| if let Some(table) = tables.borrow_mut().get_mut(&hwnd) {
+        table.bindings.retain(|&(bound, _)| bound != accelerator);
+        table.rebuild();
+    });
+}
+
+/// The first shortcut bound to `id` on `hwnd`.
+pub fn shortcut(hwnd: HWND, id: CommandId) -> Option<Accelerator> {
+    TABLES.with(|tables| {
+        tables.borrow().get(&hwnd).and_then(|table| {
+            table.bindings.iter().find(|&&(_, bound)| bound == id).map(|&(accelerator, _)| accelerator)
+        })
+    })
+}
+
+/// Translate `msg` if it's a key bound on its window or one of the window's parents.
+/// Returns whether it was, in which case it must not be dispatched.
+pub unsafe fn translate(msg: &mut MSG) -> bool {
+    let mut hwnd = msg.hwnd;
+
+    while !hwnd.is_null() {
+        let haccel = TABLES.with(|tables| tables.borrow().get(&hwnd).map(|table| table.haccel));
+
+        if let Some(haccel) = haccel {
+            if !haccel.is_null() && TranslateAcceleratorW(hwnd, haccel, msg) != 0 {
+                return true;
+            }
+        }
+
+        // Stop at the top-level window rather than going on to its owner.
+        if user32::GetWindowLongPtrW(hwnd, GWL_STYLE) as DWORD & WS_CHILD == 0 {
+            break;
+        }
+
+        hwnd = user32::GetParent(hwnd);
+    }
+
+    false
+}
+
+pub fn forget(hwnd: HWND) {
+    TABLES.with(|tables| tables.borrow_mut().remove(&hwnd));
+}
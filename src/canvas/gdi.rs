@@ -0,0 +1,480 @@
+//! A canvas drawing on a window with GDI.
+
+use {gdi32, user32};
+
+use color::Color;
+use dpi::Dpi;
+use ffi::gdi;
+use font::{self, FontHandle};
+use layout::{Rect, Size};
+use text::metrics::{DcMetrics, FontMetrics};
+
+use super::{Bitmap, Canvas, FillRule, Paint, Path, Point, Shape, StateStack, Stroke, Transform};
+use super::rasterize::Pixels;
+
+use winapi::*;
+
+use std::rc::Rc;
+use std::{cmp, mem, ptr, slice};
+
+// `SetStretchBltMode()` mode that drops pixels when shrinking instead of combining them.
+pub const COLORONCOLOR: c_int = 3;
+
+/// Draws on a device context, usually one from `BeginPaint()`.
+pub struct GdiCanvas {
+    hdc: HDC,
+    state: StateStack,
+    size: Size,
+    /// The `SaveDC()` level to go back to when done, undoing everything done to the DC.
+    saved_dc: c_int,
+    // Keeps the selected font alive.
+    _font: Rc<FontHandle>,
+}
+
+impl GdiCanvas {
+    /// Draw on `hdc` in the client area of `hwnd`, in logical units with the window's font.
+    #[doc(hidden)]
+    pub unsafe fn new(hdc: HDC, hwnd: HWND) -> GdiCanvas {
+        let dpi = Dpi::of_hwnd(hwnd);
+
+        let mut client: RECT = mem::zeroed();
+        user32::GetClientRect(hwnd, &mut client);
+        let pixels = Size::new(client.right - client.left, client.bottom - client.top);
+
+        Self::with_font(hdc, pixels, dpi, font::font_for(hwnd).handle(dpi))
+    }
+
+    /// Draw on `hdc` in an area of `pixels` at `dpi`, without a window.
+    unsafe fn with_font(hdc: HDC, pixels: Size, dpi: Dpi, font: Rc<FontHandle>) -> GdiCanvas {
+        let scale = dpi.scale_factor();
+        let clip = Rect::new(0, 0, pixels.width, pixels.height);
+        let saved_dc = gdi::SaveDC(hdc);
+
+        gdi32::SelectObject(hdc, font.hfont() as HGDIOBJ);
+        gdi::SetBkMode(hdc, TRANSPARENT);
+        gdi::SetStretchBltMode(hdc, COLORONCOLOR);
+
+        GdiCanvas {
+            hdc: hdc,
+            state: StateStack::new(Transform::scale(scale, scale), clip),
+            size: dpi.unscale_size(pixels),
+            saved_dc: saved_dc,
+            _font: font,
+        }
+    }
+
+    fn transform(&self) -> Transform {
+        self.state.current.transform
+    }
+
+    fn transform_points(&self, points: &[Point]) -> Vec<POINT> {
+        let transform = self.transform();
+
+        points.iter().map(|&point| {
+            let point = transform.apply(point);
+            POINT { x: point.x, y: point.y }
+        }).collect()
+    }
+
+    /// Rasterize `shape` into a DIB section holding a copy of the pixels under it, and copy
+    /// the result back.
+    fn render(&mut self, shape: &Shape) {
+        let clip = self.state.current.clip;
+        let area = unwrap_or_ret!(shape.bounds(clip), ());
+
+        unsafe {
+            let info = bitmap_info(area.width, area.height);
+            let mut bits = ptr::null_mut();
+
+            let dib = gdi32::CreateDIBSection(self.hdc, &info, DIB_RGB_COLORS, &mut bits, ptr::null_mut(), 0);
+
+            if dib.is_null() {
+                return;
+            }
+
+            let dib_dc = gdi32::CreateCompatibleDC(self.hdc);
+            let old_bitmap = gdi32::SelectObject(dib_dc, dib as HGDIOBJ);
+
+            // Edges are blended with what was drawn before.
+            gdi32::BitBlt(dib_dc, 0, 0, area.width, area.height, self.hdc, area.x, area.y, SRCCOPY);
+            gdi::GdiFlush();
+
+            {
+                let mut pixels = Pixels {
+                    data: slice::from_raw_parts_mut(bits as *mut u32, (area.width * area.height) as usize),
+                    x: area.x,
+                    y: area.y,
+                    width: area.width,
+                    height: area.height,
+                };
+
+                shape.render(&mut pixels, clip);
+            }
+
+            gdi32::BitBlt(self.hdc, area.x, area.y, area.width, area.height, dib_dc, 0, 0, SRCCOPY);
+
+            gdi32::SelectObject(dib_dc, old_bitmap);
+            gdi32::DeleteDC(dib_dc);
+            gdi32::DeleteObject(dib as HGDIOBJ);
+        }
+    }
+
+    unsafe fn pen(&self, color: Color) -> Selected {
+        let pen = gdi::CreatePen(PS_SOLID, self.state.line_width(), color.colorref());
+        Selected::new(self.hdc, pen as HGDIOBJ, true)
+    }
+
+    unsafe fn brush(&self, color: Color) -> Selected {
+        let brush = gdi32::CreateSolidBrush(color.colorref());
+        Selected::new(self.hdc, brush as HGDIOBJ, true)
+    }
+
+    unsafe fn stock(&self, object: c_int) -> Selected {
+        Selected::new(self.hdc, gdi32::GetStockObject(object), false)
+    }
+}
+
+impl Drop for GdiCanvas {
+    fn drop(&mut self) {
+        unsafe {
+            gdi::RestoreDC(self.hdc, self.saved_dc);
+        }
+    }
+}
+
+/// An off-screen bitmap to draw part of a window in, which is then copied to the window
+/// in one go so that half-finished drawing never shows.
+#[doc(hidden)]
+pub struct BackBuffer {
+    hdc: HDC,
+    bitmap: HBITMAP,
+    old_bitmap: HGDIOBJ,
+    area: RECT,
+}
+
+impl BackBuffer {
+    /// A buffer for `area` of the window `target` draws on, in the same coordinates.
+    pub unsafe fn new(target: HDC, area: RECT) -> BackBuffer {
+        let width = cmp::max(area.right - area.left, 1);
+        let height = cmp::max(area.bottom - area.top, 1);
+
+        let hdc = gdi32::CreateCompatibleDC(target);
+        let bitmap = gdi32::CreateCompatibleBitmap(target, width, height);
+        let old_bitmap = gdi32::SelectObject(hdc, bitmap as HGDIOBJ);
+
+        // The top left corner of the area is the top left pixel of the bitmap.
+        gdi::SetViewportOrgEx(hdc, -area.left, -area.top, ptr::null_mut());
+
+        BackBuffer {
+            hdc: hdc,
+            bitmap: bitmap,
+            old_bitmap: old_bitmap,
+            area: area,
+        }
+    }
+
+    pub fn hdc(&self) -> HDC {
+        self.hdc
+    }
+
+    /// Copy the buffer to its area of `target`.
+    pub unsafe fn copy_to(&self, target: HDC) {
+        let area = self.area;
+
+        gdi32::BitBlt(
+            target, area.left, area.top, area.right - area.left, area.bottom - area.top,
+            self.hdc, area.left, area.top, SRCCOPY
+        );
+    }
+}
+
+impl Drop for BackBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gdi32::SelectObject(self.hdc, self.old_bitmap);
+            gdi32::DeleteObject(self.bitmap as HGDIOBJ);
+            gdi32::DeleteDC(self.hdc);
+        }
+    }
+}
+
+/// A pen or brush selected into a DC for one drawing call, and deleted afterwards unless
+/// it's a stock object.
+struct Selected {
+    hdc: HDC,
+    object: HGDIOBJ,
+    old: HGDIOBJ,
+    owned: bool,
+}
+
+impl Selected {
+    unsafe fn new(hdc: HDC, object: HGDIOBJ, owned: bool) -> Selected {
+        Selected {
+            hdc: hdc,
+            object: object,
+            old: gdi32::SelectObject(hdc, object),
+            owned: owned,
+        }
+    }
+}
+
+impl Drop for Selected {
+    fn drop(&mut self) {
+        unsafe {
+            gdi32::SelectObject(self.hdc, self.old);
+
+            if self.owned {
+                gdi32::DeleteObject(self.object);
+            }
+        }
+    }
+}
+
+impl Canvas for GdiCanvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn save(&mut self) {
+        self.state.save();
+
+        unsafe {
+            gdi::SaveDC(self.hdc);
+        }
+    }
+
+    fn restore(&mut self) {
+        if self.state.restore() {
+            unsafe {
+                gdi::RestoreDC(self.hdc, -1);
+            }
+        }
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32) {
+        self.state.transform(Transform::translate(dx as f64, dy as f64));
+    }
+
+    fn scale(&mut self, scale_x: f64, scale_y: f64) {
+        self.state.transform(Transform::scale(scale_x, scale_y));
+    }
+
+    fn clip(&mut self, rect: Rect) {
+        let rect = self.state.clip(rect);
+
+        unsafe {
+            gdi::IntersectClipRect(self.hdc, rect.x, rect.y, rect.right(), rect.bottom());
+        }
+    }
+
+    fn set_line_width(&mut self, width: i32) {
+        self.state.current.line_width = width;
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, color: Color) {
+        let (from, to) = (self.transform().apply(from), self.transform().apply(to));
+
+        unsafe {
+            let _pen = self.pen(color);
+            gdi32::MoveToEx(self.hdc, from.x, from.y, ptr::null_mut());
+            gdi32::LineTo(self.hdc, to.x, to.y);
+        }
+    }
+
+    fn draw_polygon(&mut self, points: &[Point], color: Color) {
+        let points = self.transform_points(points);
+
+        unsafe {
+            let _pen = self.pen(color);
+            let _brush = self.stock(NULL_BRUSH);
+            gdi32::Polygon(self.hdc, points.as_ptr(), points.len() as c_int);
+        }
+    }
+
+    fn fill_polygon(&mut self, points: &[Point], color: Color) {
+        let points = self.transform_points(points);
+
+        unsafe {
+            let _pen = self.stock(NULL_PEN);
+            let _brush = self.brush(color);
+            gdi32::Polygon(self.hdc, points.as_ptr(), points.len() as c_int);
+        }
+    }
+
+    fn draw_ellipse(&mut self, bounds: Rect, color: Color) {
+        let bounds = self.transform().apply_rect(bounds);
+
+        unsafe {
+            let _pen = self.pen(color);
+            let _brush = self.stock(NULL_BRUSH);
+            gdi32::Ellipse(self.hdc, bounds.x, bounds.y, bounds.right(), bounds.bottom());
+        }
+    }
+
+    fn fill_ellipse(&mut self, bounds: Rect, color: Color) {
+        let bounds = self.transform().apply_rect(bounds);
+
+        unsafe {
+            let _pen = self.stock(NULL_PEN);
+            let _brush = self.brush(color);
+            // Without a pen the ellipse is a pixel smaller than its bounds.
+            gdi32::Ellipse(self.hdc, bounds.x, bounds.y, bounds.right() + 1, bounds.bottom() + 1);
+        }
+    }
+
+    fn fill_path(&mut self, path: &Path, paint: &Paint, rule: FillRule) {
+        let shape = Shape::fill(path, paint, rule, &self.transform());
+        self.render(&shape);
+    }
+
+    fn stroke_path(&mut self, path: &Path, stroke: &Stroke, paint: &Paint) {
+        let shape = Shape::stroke(path, stroke, paint, &self.transform());
+        self.render(&shape);
+    }
+
+    fn draw_text(&mut self, pos: Point, text: &str, color: Color) {
+        let pos = self.transform().apply(pos);
+        let text: Vec<u16> = text.encode_utf16().collect();
+
+        unsafe {
+            gdi32::SetTextColor(self.hdc, color.colorref());
+            gdi32::TextOutW(self.hdc, pos.x, pos.y, text.as_ptr(), text.len() as c_int);
+        }
+    }
+
+    fn text_metrics<'a>(&'a self) -> Box<FontMetrics + 'a> {
+        Box::new(unsafe { DcMetrics::new(self.hdc, self.transform().average_scale()) })
+    }
+
+    fn draw_bitmap(&mut self, bitmap: &Bitmap, dest: Rect) {
+        let dest = self.transform().apply_rect(dest);
+
+        unsafe {
+            let info = bitmap_info(bitmap.width, bitmap.height);
+
+            gdi32::StretchDIBits(
+                self.hdc, dest.x, dest.y, dest.width, dest.height,
+                0, 0, bitmap.width, bitmap.height,
+                bitmap.pixels.as_ptr() as *const VOID, &info, DIB_RGB_COLORS, SRCCOPY
+            );
+        }
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let rect = self.transform().apply_rect(rect);
+        let rect = RECT { left: rect.x, top: rect.y, right: rect.right(), bottom: rect.bottom() };
+
+        unsafe {
+            let brush = gdi32::CreateSolidBrush(color.colorref());
+            user32::FillRect(self.hdc, &rect, brush);
+            gdi32::DeleteObject(brush as HGDIOBJ);
+        }
+    }
+}
+
+/// The header of a top-down 32-bit DIB, the layout of `Bitmap`.
+pub fn bitmap_info(width: i32, height: i32) -> BITMAPINFO {
+    let mut info: BITMAPINFO = unsafe { mem::zeroed() };
+
+    info.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as DWORD;
+    info.bmiHeader.biWidth = width;
+    // Negative for rows from the top down.
+    info.bmiHeader.biHeight = -cmp::max(height, 0);
+    info.bmiHeader.biPlanes = 1;
+    info.bmiHeader.biBitCount = 32;
+    info.bmiHeader.biCompression = BI_RGB;
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use color::Color;
+    use dpi::Dpi;
+    use font::Font;
+    use layout::Size;
+
+    use canvas::{Bitmap, Canvas, FillRule, GradientStop, LineJoin, Paint, Path, PointF};
+    use canvas::{RasterCanvas, Stroke};
+
+    use super::*;
+
+    const WHITE: Color = Color { r: 255, g: 255, b: 255 };
+
+    /// Draw with `draw` on a DIB section through GDI, and the pixels it ends up with.
+    fn draw_with_gdi<F: Fn(&mut Canvas)>(size: Size, dpi: Dpi, draw: F) -> Bitmap {
+        let pixels = dpi.scale_size(size);
+        let mut bitmap = Bitmap::new(pixels.width, pixels.height, WHITE);
+
+        unsafe {
+            let hdc = gdi32::CreateCompatibleDC(ptr::null_mut());
+            let info = bitmap_info(pixels.width, pixels.height);
+            let mut bits = ptr::null_mut();
+            let dib = gdi32::CreateDIBSection(
+                hdc, &info, DIB_RGB_COLORS, &mut bits, ptr::null_mut(), 0
+            );
+            assert!(!dib.is_null());
+
+            let old_bitmap = gdi32::SelectObject(hdc, dib as HGDIOBJ);
+            let data = slice::from_raw_parts_mut(bits as *mut u32, bitmap.pixels.len());
+            data.copy_from_slice(&bitmap.pixels);
+
+            draw(&mut GdiCanvas::with_font(hdc, pixels, dpi, Font::message_font().handle(dpi)));
+            gdi::GdiFlush();
+
+            // GDI leaves the alpha byte alone, or clears it.
+            for (pixel, &drawn) in bitmap.pixels.iter_mut().zip(data.iter()) {
+                *pixel = 0xff000000 | drawn;
+            }
+
+            gdi32::SelectObject(hdc, old_bitmap);
+            gdi32::DeleteObject(dib as HGDIOBJ);
+            gdi32::DeleteDC(hdc);
+        }
+
+        bitmap
+    }
+
+    fn assert_same<F: Fn(&mut Canvas)>(size: Size, dpi: Dpi, draw: F) {
+        let mut raster = RasterCanvas::with_dpi(size, dpi, WHITE);
+        draw(&mut raster);
+
+        let gdi = draw_with_gdi(size, dpi, draw);
+        let raster = raster.into_bitmap();
+
+        for (i, (&drawn, &expected)) in gdi.pixels.iter().zip(&raster.pixels).enumerate() {
+            let (x, y) = (i as i32 % gdi.width, i as i32 / gdi.width);
+            assert!(drawn == expected, "({}, {}) is {:08x} with GDI but {:08x} in memory",
+                    x, y, drawn, expected);
+        }
+    }
+
+    fn draw_paths(canvas: &mut Canvas) {
+        let black = Color { r: 0, g: 0, b: 0 };
+        let red = Color { r: 255, g: 0, b: 0 };
+
+        canvas.fill_path(&Path::ellipse(12.0, 12.0, 9.5, 7.25), &Paint::from(black), FillRule::NonZero);
+
+        let triangle = Path::new().move_to(20.0, 4.0).line_to(38.0, 30.0).line_to(4.0, 26.0).close();
+        canvas.stroke_path(&triangle, &Stroke::new(3.0).join(LineJoin::Round).dashes(vec![5.0], 1.5),
+                           &Paint::from(red));
+
+        let gradient = Paint::Linear {
+            start: PointF::new(24.0, 0.0),
+            end: PointF::new(40.0, 0.0),
+            stops: vec![GradientStop::new(0.0, black), GradientStop::new(1.0, red)],
+        };
+        canvas.fill_path(&Path::new().move_to(24.0, 2.0).quad_to(40.0, 2.0, 38.0, 16.0).close(),
+                         &gradient, FillRule::EvenOdd);
+    }
+
+    #[test]
+    fn paths_are_the_same_as_in_memory() {
+        assert_same(Size::new(40, 32), Dpi::default(), draw_paths);
+    }
+
+    #[test]
+    fn scaled_paths_are_the_same_as_in_memory() {
+        assert_same(Size::new(40, 32), Dpi(144), draw_paths);
+    }
+}
@@ -0,0 +1,432 @@
+//! Custom drawing with `Window::on_paint()`.
+//!
+//! Paint code draws on a `Canvas`, which is either a window being painted with GDI or a
+//! `RasterCanvas` drawing into a `Bitmap` in memory. The raster canvas doesn't use any
+//! Windows APIs, so the output of paint code can be compared against reference images on
+//! any platform.
+//!
+//! Coordinates are in logical units, like layouts: a window canvas starts out scaled to the
+//! DPI of the window. `save()` and `restore()` keep a stack of the transform, clipping
+//! rectangle and line width.
+//!
+//! The basic shapes are drawn by GDI on windows, without anti-aliasing. Paths, drawn with
+//! `fill_path()` and `stroke_path()`, are anti-aliased by the rasterizer in this module
+//! instead, so they come out the same on every canvas.
+
+use color::Color;
+use layout::{Rect, Size};
+use text::metrics::FontMetrics;
+
+use std::cmp;
+
+#[cfg(windows)] mod gdi;
+mod paint;
+mod path;
+mod raster;
+mod rasterize;
+mod surface;
+
+#[cfg(windows)] pub use self::gdi::{BackBuffer, GdiCanvas};
+pub use self::paint::{GradientStop, Paint, Rgba};
+pub use self::path::{LineCap, LineJoin, Path, PathElement, PointF, Polyline, Stroke};
+pub use self::raster::RasterCanvas;
+pub use self::rasterize::FillRule;
+pub use self::surface::{scaled_rect, Frame, PixelSurface, ScaleMode};
+
+#[cfg(windows)]
+#[doc(hidden)]
+pub use self::gdi::bitmap_info;
+
+/// A point in the coordinates of a canvas.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x: x, y: y }
+    }
+}
+
+/// A scale followed by a translation, mapping canvas coordinates to pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub dx: f64,
+    pub dy: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform::scale(1.0, 1.0)
+    }
+
+    pub fn scale(scale_x: f64, scale_y: f64) -> Transform {
+        Transform { scale_x: scale_x, scale_y: scale_y, dx: 0.0, dy: 0.0 }
+    }
+
+    pub fn translate(dx: f64, dy: f64) -> Transform {
+        Transform { scale_x: 1.0, scale_y: 1.0, dx: dx, dy: dy }
+    }
+
+    /// Apply `inner` first, then this transform.
+    pub fn then(&self, inner: Transform) -> Transform {
+        Transform {
+            scale_x: self.scale_x * inner.scale_x,
+            scale_y: self.scale_y * inner.scale_y,
+            dx: self.dx + self.scale_x * inner.dx,
+            dy: self.dy + self.scale_y * inner.dy,
+        }
+    }
+
+    pub fn apply_f64(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.scale_x + self.dx, y * self.scale_y + self.dy)
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        let (x, y) = self.apply_f64(point.x as f64, point.y as f64);
+        Point::new(x.round() as i32, y.round() as i32)
+    }
+
+    /// Transform the edges of `rect`, so that rectangles which touch before still touch
+    /// afterwards. Negative scales are flipped back to a positive width and height.
+    pub fn apply_rect(&self, rect: Rect) -> Rect {
+        let a = self.apply(Point::new(rect.x, rect.y));
+        let b = self.apply(Point::new(rect.right(), rect.bottom()));
+
+        let (left, right) = (cmp::min(a.x, b.x), cmp::max(a.x, b.x));
+        let (top, bottom) = (cmp::min(a.y, b.y), cmp::max(a.y, b.y));
+
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    /// The scale of lengths that aren't horizontal or vertical, like line widths.
+    pub fn average_scale(&self) -> f64 {
+        (self.scale_x.abs() + self.scale_y.abs()) / 2.0
+    }
+
+    /// The width in pixels of a line `width` units wide, at least 1.
+    pub fn apply_width(&self, width: i32) -> i32 {
+        cmp::max((width as f64 * self.average_scale()).round() as i32, 1)
+    }
+}
+
+/// An image in memory, as rows of `0xAARRGGBB` pixels from the top down. This is the
+/// layout of a 32-bit DIB, so it can be drawn without conversion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+    pub width: i32,
+    pub height: i32,
+    pub pixels: Vec<u32>,
+}
+
+impl Bitmap {
+    /// A bitmap filled with `color`.
+    pub fn new(width: i32, height: i32, color: Color) -> Bitmap {
+        let len = cmp::max(width, 0) as usize * cmp::max(height, 0) as usize;
+
+        Bitmap {
+            width: width,
+            height: height,
+            pixels: vec![argb(color); len],
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// The pixel at `(x, y)`, or `None` if it's outside the bitmap.
+    pub fn pixel(&self, x: i32, y: i32) -> Option<u32> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.pixels[(y * self.width + x) as usize])
+    }
+}
+
+/// The opaque `0xAARRGGBB` pixel of `color`.
+pub fn argb(color: Color) -> u32 {
+    0xff000000 | (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32
+}
+
+/// A surface to draw on.
+///
+/// Outlines are drawn with the current line width. Rectangles and ellipses are given by
+/// their bounds; the right and bottom edges are outside the shape.
+pub trait Canvas {
+    /// The size of the surface, in the units it started out with.
+    fn size(&self) -> Size;
+
+    /// Push the transform, clipping rectangle and line width on a stack.
+    fn save(&mut self);
+
+    /// Go back to the state of the matching `save()`. Does nothing if nothing was saved.
+    fn restore(&mut self);
+
+    /// Move the origin to `(dx, dy)` in the current coordinates.
+    fn translate(&mut self, dx: i32, dy: i32);
+
+    /// Scale what's drawn from now on around the current origin.
+    fn scale(&mut self, scale_x: f64, scale_y: f64);
+
+    /// Limit drawing to the part of `rect` inside the current clipping rectangle.
+    fn clip(&mut self, rect: Rect);
+
+    fn set_line_width(&mut self, width: i32);
+
+    fn draw_line(&mut self, from: Point, to: Point, color: Color);
+
+    fn draw_polygon(&mut self, points: &[Point], color: Color);
+
+    fn fill_polygon(&mut self, points: &[Point], color: Color);
+
+    fn draw_ellipse(&mut self, bounds: Rect, color: Color);
+
+    fn fill_ellipse(&mut self, bounds: Rect, color: Color);
+
+    /// Draw a single line of `text` with its top left corner at `pos`, in the font of the
+    /// window.
+    fn draw_text(&mut self, pos: Point, text: &str, color: Color);
+
+    /// Metrics of the font `draw_text()` uses, in the current units, e.g. for a
+    /// `TextLayout`.
+    fn text_metrics<'a>(&'a self) -> Box<FontMetrics + 'a>;
+
+    /// Draw `bitmap` stretched to `dest`.
+    fn draw_bitmap(&mut self, bitmap: &Bitmap, dest: Rect);
+
+    /// Fill the inside of `path`, anti-aliased.
+    fn fill_path(&mut self, path: &Path, paint: &Paint, rule: FillRule);
+
+    /// Draw the outline of `path`, anti-aliased.
+    fn stroke_path(&mut self, path: &Path, stroke: &Stroke, paint: &Paint);
+
+    fn draw_rect(&mut self, rect: Rect, color: Color) {
+        self.draw_polygon(&rect_points(rect), color);
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.fill_polygon(&rect_points(rect), color);
+    }
+}
+
+fn rect_points(rect: Rect) -> [Point; 4] {
+    [
+        Point::new(rect.x, rect.y),
+        Point::new(rect.right(), rect.y),
+        Point::new(rect.right(), rect.bottom()),
+        Point::new(rect.x, rect.bottom()),
+    ]
+}
+
+/// A path turned into polygons in pixels, ready to be rasterized.
+struct Shape {
+    polygons: Vec<Vec<PointF>>,
+    rule: FillRule,
+    paint: Paint,
+}
+
+impl Shape {
+    fn fill(path: &Path, paint: &Paint, rule: FillRule, transform: &Transform) -> Shape {
+        Shape {
+            polygons: path.flatten(transform).into_iter().map(|polyline| polyline.points).collect(),
+            rule: rule,
+            paint: paint.transform(transform),
+        }
+    }
+
+    fn stroke(path: &Path, stroke: &Stroke, paint: &Paint, transform: &Transform) -> Shape {
+        Shape {
+            polygons: stroke.outline(&path.flatten(transform), transform.average_scale()),
+            rule: FillRule::NonZero,
+            paint: paint.transform(transform),
+        }
+    }
+
+    /// The pixels the shape touches inside `clip`.
+    #[cfg(windows)]
+    fn bounds(&self, clip: Rect) -> Option<Rect> {
+        rasterize::bounds(&self.polygons)
+            .map(|bounds| intersect(bounds, clip))
+            .and_then(|bounds| if bounds.width > 0 && bounds.height > 0 { Some(bounds) } else { None })
+    }
+
+    fn render(&self, pixels: &mut rasterize::Pixels, clip: Rect) {
+        rasterize::fill(pixels, clip, &self.polygons, self.rule, &self.paint);
+    }
+}
+
+/// What `save()` and `restore()` keep.
+#[derive(Copy, Clone, Debug)]
+struct State {
+    transform: Transform,
+    /// In pixels.
+    clip: Rect,
+    line_width: i32,
+}
+
+/// The state stack shared by the canvas implementations.
+struct StateStack {
+    current: State,
+    saved: Vec<State>,
+}
+
+impl StateStack {
+    fn new(transform: Transform, clip: Rect) -> StateStack {
+        StateStack {
+            current: State {
+                transform: transform,
+                clip: clip,
+                line_width: 1,
+            },
+            saved: vec![],
+        }
+    }
+
+    fn save(&mut self) {
+        self.saved.push(self.current);
+    }
+
+    fn restore(&mut self) -> bool {
+        match self.saved.pop() {
+            Some(state) => {
+                self.current = state;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn transform(&mut self, inner: Transform) {
+        self.current.transform = self.current.transform.then(inner);
+    }
+
+    /// Intersect the clipping rectangle with `rect`, returning the pixels of `rect`.
+    fn clip(&mut self, rect: Rect) -> Rect {
+        let rect = self.current.transform.apply_rect(rect);
+        self.current.clip = intersect(self.current.clip, rect);
+        rect
+    }
+
+    fn line_width(&self) -> i32 {
+        self.current.transform.apply_width(self.current.line_width)
+    }
+}
+
+/// The overlap of two rectangles.
+///
+/// If they don't overlap horizontally (or vertically) the result has zero width (or
+/// height), starting at the larger of the two left (or top) edges.
+fn intersect(a: Rect, b: Rect) -> Rect {
+    let left = cmp::max(a.x, b.x);
+    let top = cmp::max(a.y, b.y);
+    let right = cmp::max(cmp::min(a.right(), b.right()), left);
+    let bottom = cmp::max(cmp::min(a.bottom(), b.bottom()), top);
+
+    Rect::new(left, top, right - left, bottom - top)
+}
+
+#[cfg(test)]
+mod tests {
+    use color::Color;
+    use layout::Rect;
+
+    use super::*;
+
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color { r: 255, g: 255, b: 255 };
+    pub const RED: Color = Color { r: 255, g: 0, b: 0 };
+
+    /// Draw `bitmap` as text, a line per row: `.` for white, `#` for black, `r` for red and
+    /// `+` for anything else, like the blended edges of paths.
+    pub fn snapshot(bitmap: &Bitmap) -> String {
+        let mut text = String::new();
+
+        for row in bitmap.pixels.chunks(bitmap.width as usize) {
+            for &pixel in row {
+                text.push(match pixel {
+                    pixel if pixel == argb(WHITE) => '.',
+                    pixel if pixel == argb(BLACK) => '#',
+                    pixel if pixel == argb(RED) => 'r',
+                    _ => '+',
+                });
+            }
+
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// The rows of a snapshot written one per line, indented.
+    pub fn rows(rows: &str) -> String {
+        rows.lines().map(str::trim).filter(|row| !row.is_empty())
+            .fold(String::new(), |text, row| text + row + "\n")
+    }
+
+    #[test]
+    fn transform_then() {
+        let transform = Transform::translate(10.0, 20.0).then(Transform::scale(2.0, 3.0));
+
+        assert_eq!(transform.apply(Point::new(1, 1)), Point::new(12, 23));
+        let transform = Transform::scale(2.0, 2.0).then(Transform::translate(1.0, 1.0));
+        assert_eq!(transform.apply(Point::new(0, 0)), Point::new(2, 2));
+    }
+
+    #[test]
+    fn transform_rect_edges() {
+        let transform = Transform::scale(1.5, -1.0);
+
+        // The edges are rounded separately, so the width depends on the position.
+        assert_eq!(transform.apply_rect(Rect::new(1, 1, 1, 2)), Rect::new(2, -3, 1, 2));
+        assert_eq!(transform.apply_rect(Rect::new(2, 1, 1, 2)), Rect::new(3, -3, 2, 2));
+    }
+
+    #[test]
+    fn line_width_at_least_one() {
+        assert_eq!(Transform::scale(0.25, 0.25).apply_width(2), 1);
+        assert_eq!(Transform::scale(1.5, 2.5).apply_width(3), 6);
+    }
+
+    #[test]
+    fn bitmap_pixel() {
+        let mut bitmap = Bitmap::new(2, 2, WHITE);
+        bitmap.pixels[3] = argb(RED);
+
+        assert_eq!(bitmap.pixel(1, 1), Some(0xffff0000));
+        assert_eq!(bitmap.pixel(0, 0), Some(0xffffffff));
+        assert_eq!(bitmap.pixel(2, 0), None);
+        assert_eq!(bitmap.pixel(0, -1), None);
+    }
+
+    #[test]
+    fn state_stack() {
+        let mut state = StateStack::new(Transform::identity(), Rect::new(0, 0, 100, 100));
+
+        state.save();
+        state.transform(Transform::translate(10.0, 10.0));
+        assert_eq!(state.clip(Rect::new(80, 80, 50, 50)), Rect::new(90, 90, 50, 50));
+        assert_eq!(state.current.clip, Rect::new(90, 90, 10, 10));
+
+        assert!(state.restore());
+        assert_eq!(state.current.clip, Rect::new(0, 0, 100, 100));
+        assert!(!state.restore());
+    }
+
+    #[test]
+    fn intersect_without_overlap() {
+        assert_eq!(intersect(Rect::new(0, 0, 10, 10), Rect::new(5, 5, 10, 10)), Rect::new(5, 5, 5, 5));
+        assert_eq!(intersect(Rect::new(0, 0, 10, 10), Rect::new(20, 0, 5, 5)), Rect::new(20, 0, 0, 5));
+    }
+}
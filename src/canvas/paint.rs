@@ -0,0 +1,131 @@
+//! What shapes are filled with: translucent colors and gradients.
+
+use color::Color;
+
+use super::Transform;
+use super::path::PointF;
+
+/// A color with an alpha channel, where 0 is transparent and 255 opaque.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Rgba {
+        Rgba { r: r, g: g, b: b, a: a }
+    }
+
+    fn lerp(self, other: Rgba, t: f64) -> Rgba {
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+        Rgba::new(
+            channel(self.r, other.r), channel(self.g, other.g),
+            channel(self.b, other.b), channel(self.a, other.a)
+        )
+    }
+}
+
+impl From<Color> for Rgba {
+    fn from(color: Color) -> Rgba {
+        Rgba::new(color.r, color.g, color.b, 255)
+    }
+}
+
+/// A color at `offset` along a gradient, from 0 at the start to 1 at the end.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GradientStop {
+    pub offset: f64,
+    pub color: Rgba,
+}
+
+impl GradientStop {
+    pub fn new<C: Into<Rgba>>(offset: f64, color: C) -> GradientStop {
+        GradientStop { offset: offset, color: color.into() }
+    }
+}
+
+/// How to color the inside of a shape. Gradients use the color of the nearest stop beyond
+/// their ends.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    Solid(Rgba),
+    /// Changing along the line from `start` to `end`, constant across it.
+    Linear { start: PointF, end: PointF, stops: Vec<GradientStop> },
+    /// Changing from `center` out to `radius`.
+    Radial { center: PointF, radius: f64, stops: Vec<GradientStop> },
+}
+
+impl From<Color> for Paint {
+    fn from(color: Color) -> Paint {
+        Paint::Solid(color.into())
+    }
+}
+
+impl From<Rgba> for Paint {
+    fn from(color: Rgba) -> Paint {
+        Paint::Solid(color)
+    }
+}
+
+impl Paint {
+    /// The same paint in pixels. Radial gradients stay circular, with the average scale.
+    pub fn transform(&self, transform: &Transform) -> Paint {
+        let point = |point: PointF| {
+            let (x, y) = transform.apply_f64(point.x, point.y);
+            PointF::new(x, y)
+        };
+
+        match *self {
+            Paint::Solid(color) => Paint::Solid(color),
+            Paint::Linear { start, end, ref stops } =>
+                Paint::Linear { start: point(start), end: point(end), stops: stops.clone() },
+            Paint::Radial { center, radius, ref stops } => {
+                let radius = radius * transform.average_scale();
+                Paint::Radial { center: point(center), radius: radius, stops: stops.clone() }
+            },
+        }
+    }
+
+    /// The color at `(x, y)`.
+    pub fn color_at(&self, x: f64, y: f64) -> Rgba {
+        match *self {
+            Paint::Solid(color) => color,
+            Paint::Linear { start, end, ref stops } => {
+                let (dx, dy) = (end.x - start.x, end.y - start.y);
+                let len_sq = dx * dx + dy * dy;
+
+                // How far along the line the projection of the point is.
+                let t = if len_sq == 0.0 { 0.0 } else { ((x - start.x) * dx + (y - start.y) * dy) / len_sq };
+                gradient_color(stops, t)
+            },
+            Paint::Radial { center, radius, ref stops } => {
+                let distance = ((x - center.x) * (x - center.x) + (y - center.y) * (y - center.y)).sqrt();
+                let t = if radius == 0.0 { 1.0 } else { distance / radius };
+                gradient_color(stops, t)
+            },
+        }
+    }
+}
+
+fn gradient_color(stops: &[GradientStop], t: f64) -> Rgba {
+    let first = unwrap_or_ret!(stops.first(), Rgba::new(0, 0, 0, 0));
+
+    if t <= first.offset {
+        return first.color;
+    }
+
+    for pair in stops.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+
+        if t <= b.offset {
+            let span = b.offset - a.offset;
+            return if span <= 0.0 { b.color } else { a.color.lerp(b.color, (t - a.offset) / span) };
+        }
+    }
+
+    stops[stops.len() - 1].color
+}
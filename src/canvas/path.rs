@@ -0,0 +1,592 @@
+//! Paths made of lines and Bezier curves, and the outlines of their strokes.
+
+use layout::Rect;
+
+use super::Transform;
+
+use std::f64::consts::PI;
+use std::mem;
+
+/// A point with fractional coordinates, for paths.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PointF {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl PointF {
+    pub fn new(x: f64, y: f64) -> PointF {
+        PointF { x: x, y: y }
+    }
+
+    fn transform(self, transform: &Transform) -> PointF {
+        let (x, y) = transform.apply_f64(self.x, self.y);
+        PointF::new(x, y)
+    }
+
+    fn lerp(self, other: PointF, t: f64) -> PointF {
+        PointF::new(self.x + (other.x - self.x) * t, self.y + (other.y - self.y) * t)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathElement {
+    MoveTo(PointF),
+    LineTo(PointF),
+    /// A quadratic Bezier curve with one control point.
+    QuadTo(PointF, PointF),
+    /// A cubic Bezier curve with two control points.
+    CubicTo(PointF, PointF, PointF),
+    /// Join the end of the current subpath to its start.
+    Close,
+}
+
+/// The outline of a shape, made of subpaths which each start with `move_to()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    elements: Vec<PathElement>,
+}
+
+/// How far the lines a curve is approximated with may be from it, in pixels.
+const TOLERANCE: f64 = 0.2;
+
+// The distance of the control points of a cubic Bezier quarter circle from its ends, as a
+// fraction of the radius.
+const KAPPA: f64 = 0.5522847498;
+
+impl Path {
+    pub fn new() -> Path {
+        Path::default()
+    }
+
+    pub fn move_to(mut self, x: f64, y: f64) -> Self {
+        self.elements.push(PathElement::MoveTo(PointF::new(x, y)));
+        self
+    }
+
+    pub fn line_to(mut self, x: f64, y: f64) -> Self {
+        self.elements.push(PathElement::LineTo(PointF::new(x, y)));
+        self
+    }
+
+    pub fn quad_to(mut self, cx: f64, cy: f64, x: f64, y: f64) -> Self {
+        self.elements.push(PathElement::QuadTo(PointF::new(cx, cy), PointF::new(x, y)));
+        self
+    }
+
+    pub fn cubic_to(mut self, c1x: f64, c1y: f64, c2x: f64, c2y: f64, x: f64, y: f64) -> Self {
+        self.elements.push(PathElement::CubicTo(
+            PointF::new(c1x, c1y), PointF::new(c2x, c2y), PointF::new(x, y)
+        ));
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.elements.push(PathElement::Close);
+        self
+    }
+
+    /// A closed path around `rect`.
+    pub fn rect(rect: Rect) -> Path {
+        let (left, top) = (rect.x as f64, rect.y as f64);
+        let (right, bottom) = (rect.right() as f64, rect.bottom() as f64);
+
+        Path::new().move_to(left, top).line_to(right, top).line_to(right, bottom)
+            .line_to(left, bottom).close()
+    }
+
+    /// A closed path around the ellipse centered at `(cx, cy)`, made of four cubic curves.
+    pub fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> Path {
+        let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+        Path::new()
+            .move_to(cx + rx, cy)
+            .cubic_to(cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry)
+            .cubic_to(cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy)
+            .cubic_to(cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry)
+            .cubic_to(cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy)
+            .close()
+    }
+
+    pub fn elements(&self) -> &[PathElement] {
+        &self.elements
+    }
+
+    /// Transform the path to pixels and approximate its curves with lines.
+    pub fn flatten(&self, transform: &Transform) -> Vec<Polyline> {
+        let mut polylines = vec![];
+        let mut current = Polyline::default();
+        let mut last = PointF::default();
+
+        for element in &self.elements {
+            match *element {
+                PathElement::MoveTo(point) => {
+                    let point = point.transform(transform);
+                    finish(&mut polylines, &mut current);
+                    current.points.push(point);
+                    last = point;
+                },
+                PathElement::LineTo(point) => {
+                    let point = point.transform(transform);
+                    start_if_empty(&mut current, last);
+                    current.points.push(point);
+                    last = point;
+                },
+                PathElement::QuadTo(control, end) => {
+                    let (control, end) = (control.transform(transform), end.transform(transform));
+                    start_if_empty(&mut current, last);
+
+                    let dd = second_difference(last, control, end);
+                    let segments = segment_count(dd * 0.25);
+
+                    for i in 1..segments + 1 {
+                        let t = i as f64 / segments as f64;
+                        current.points.push(last.lerp(control, t).lerp(control.lerp(end, t), t));
+                    }
+
+                    last = end;
+                },
+                PathElement::CubicTo(c1, c2, end) => {
+                    let (c1, c2, end) = (c1.transform(transform), c2.transform(transform), end.transform(transform));
+                    start_if_empty(&mut current, last);
+
+                    let dd = second_difference(last, c1, c2).max(second_difference(c1, c2, end));
+                    let segments = segment_count(dd * 0.75);
+
+                    for i in 1..segments + 1 {
+                        let t = i as f64 / segments as f64;
+                        let (a, b, c) = (last.lerp(c1, t), c1.lerp(c2, t), c2.lerp(end, t));
+                        current.points.push(a.lerp(b, t).lerp(b.lerp(c, t), t));
+                    }
+
+                    last = end;
+                },
+                PathElement::Close => {
+                    if !current.points.is_empty() {
+                        let start = current.points[0];
+                        current.closed = true;
+                        finish(&mut polylines, &mut current);
+
+                        // Drawing continues from the start of the closed subpath.
+                        last = start;
+                    }
+                },
+            }
+        }
+
+        finish(&mut polylines, &mut current);
+        polylines
+    }
+}
+
+/// The number of lines to approximate a curve with, where `deviation` bounds how far the
+/// curve is from a straight line.
+fn segment_count(deviation: f64) -> usize {
+    let count = (deviation / TOLERANCE).sqrt().ceil();
+    if count.is_finite() { count.max(1.0).min(1000.0) as usize } else { 1 }
+}
+
+/// How much the middle of three control points bends away from the other two.
+fn second_difference(a: PointF, b: PointF, c: PointF) -> f64 {
+    distance(PointF::new(a.x - 2.0 * b.x + c.x, a.y - 2.0 * b.y + c.y), PointF::default())
+}
+
+fn start_if_empty(current: &mut Polyline, last: PointF) {
+    if current.points.is_empty() {
+        current.points.push(last);
+    }
+}
+
+fn finish(polylines: &mut Vec<Polyline>, current: &mut Polyline) {
+    if !current.points.is_empty() {
+        polylines.push(mem::replace(current, Polyline::default()));
+    }
+}
+
+fn distance(a: PointF, b: PointF) -> f64 {
+    ((a.x - b.x) * (a.x - b.x) + (a.y - b.y) * (a.y - b.y)).sqrt()
+}
+
+/// A flattened subpath, in pixels.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<PointF>,
+    pub closed: bool,
+}
+
+/// The shape of the corners where the segments of a stroke meet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Extend the outer edges until they meet, or bevel if that's beyond the miter limit.
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// The shape of the ends of an open stroke and of each dash.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    /// Extend the end by half the width of the line.
+    Square,
+}
+
+/// How to draw the outline of a path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stroke {
+    pub width: f64,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// The longest a miter may be, as a multiple of the width.
+    pub miter_limit: f64,
+    /// Alternating lengths of dashes and gaps, or empty for a solid line. An odd number of
+    /// lengths is repeated, as in SVG.
+    pub dashes: Vec<f64>,
+    /// How far into the dash pattern the stroke starts.
+    pub dash_offset: f64,
+}
+
+impl Stroke {
+    pub fn new(width: f64) -> Stroke {
+        Stroke {
+            width: width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+            dashes: vec![],
+            dash_offset: 0.0,
+        }
+    }
+
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn miter_limit(mut self, miter_limit: f64) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    pub fn dashes(mut self, dashes: Vec<f64>, offset: f64) -> Self {
+        self.dashes = dashes;
+        self.dash_offset = offset;
+        self
+    }
+
+    /// The outline of the stroke of `polylines`, as polygons in pixels to be filled with
+    /// the non-zero rule. `scale` is the number of pixels per unit of width.
+    pub fn outline(&self, polylines: &[Polyline], scale: f64) -> Vec<Vec<PointF>> {
+        let half_width = self.width * scale / 2.0;
+        let mut polygons = vec![];
+
+        if half_width <= 0.0 {
+            return polygons;
+        }
+
+        let dashes: Vec<f64> = self.dashes.iter().map(|&dash| dash * scale).collect();
+        let dashed = dashes.iter().any(|&dash| dash > 0.0) && dashes.iter().all(|&dash| dash >= 0.0);
+
+        for polyline in polylines {
+            let mut points = polyline.points.clone();
+            points.dedup();
+
+            // A closed path that already returned to its start doesn't need another segment.
+            if polyline.closed && points.len() > 1 && points[0] == points[points.len() - 1] {
+                points.pop();
+            }
+
+            if !dashed {
+                self.outline_polyline(&points, polyline.closed, half_width, &mut polygons);
+                continue;
+            }
+
+            if polyline.closed && points.len() > 1 {
+                let start = points[0];
+                points.push(start);
+            }
+
+            for dash in split_dashes(&points, &dashes, self.dash_offset * scale) {
+                self.outline_polyline(&dash, false, half_width, &mut polygons);
+            }
+        }
+
+        for polygon in &mut polygons {
+            // Overlapping pieces only add up to a solid stroke if they wind the same way.
+            if signed_area(polygon) < 0.0 {
+                polygon.reverse();
+            }
+        }
+
+        polygons
+    }
+
+    fn outline_polyline(&self, points: &[PointF], closed: bool, half_width: f64, polygons: &mut Vec<Vec<PointF>>) {
+        if points.is_empty() {
+            return;
+        }
+
+        if points.len() == 1 {
+            // A dot, which only has caps.
+            match self.cap {
+                LineCap::Butt => {},
+                LineCap::Round => polygons.push(circle(points[0], half_width)),
+                LineCap::Square => {
+                    let p = points[0];
+                    polygons.push(vec![
+                        PointF::new(p.x - half_width, p.y - half_width),
+                        PointF::new(p.x + half_width, p.y - half_width),
+                        PointF::new(p.x + half_width, p.y + half_width),
+                        PointF::new(p.x - half_width, p.y + half_width),
+                    ]);
+                },
+            }
+
+            return;
+        }
+
+        let segments = if closed { points.len() } else { points.len() - 1 };
+
+        for i in 0..segments {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            let n = normal(a, b, half_width);
+
+            polygons.push(vec![
+                PointF::new(a.x + n.x, a.y + n.y),
+                PointF::new(b.x + n.x, b.y + n.y),
+                PointF::new(b.x - n.x, b.y - n.y),
+                PointF::new(a.x - n.x, a.y - n.y),
+            ]);
+        }
+
+        let len = points.len();
+        let joins = if closed { 0..len } else { 1..len - 1 };
+
+        for i in joins {
+            let prev = points[(i + len - 1) % len];
+            let next = points[(i + 1) % len];
+            self.join_polygon(prev, points[i], next, half_width, polygons);
+        }
+
+        if !closed {
+            self.cap_polygon(points[1], points[0], half_width, polygons);
+            self.cap_polygon(points[len - 2], points[len - 1], half_width, polygons);
+        }
+    }
+
+    fn join_polygon(&self, prev: PointF, p: PointF, next: PointF, half_width: f64, polygons: &mut Vec<Vec<PointF>>) {
+        let n0 = normal(prev, p, half_width);
+        let n1 = normal(p, next, half_width);
+
+        let cross = (p.x - prev.x) * (next.y - p.y) - (p.y - prev.y) * (next.x - p.x);
+
+        if cross == 0.0 {
+            return;
+        }
+
+        // The corner sticks out on the side away from the turn.
+        let side = if cross > 0.0 { -1.0 } else { 1.0 };
+        let a = PointF::new(p.x + side * n0.x, p.y + side * n0.y);
+        let b = PointF::new(p.x + side * n1.x, p.y + side * n1.y);
+
+        match self.join {
+            LineJoin::Round => polygons.push(circle(p, half_width)),
+            LineJoin::Bevel => polygons.push(vec![p, a, b]),
+            LineJoin::Miter => {
+                let (mx, my) = (n0.x + n1.x, n0.y + n1.y);
+                let m_len = (mx * mx + my * my).sqrt();
+
+                // The cosine of half the angle between the segments.
+                let cos = m_len / (2.0 * half_width);
+
+                if cos > 0.0 && 1.0 / cos <= self.miter_limit {
+                    let length = half_width / cos;
+                    let tip = PointF::new(p.x + side * mx / m_len * length, p.y + side * my / m_len * length);
+                    polygons.push(vec![p, a, tip, b]);
+                } else {
+                    polygons.push(vec![p, a, b]);
+                }
+            },
+        }
+    }
+
+    /// The cap at `end` of a line coming from `from`.
+    fn cap_polygon(&self, from: PointF, end: PointF, half_width: f64, polygons: &mut Vec<Vec<PointF>>) {
+        match self.cap {
+            LineCap::Butt => {},
+            LineCap::Round => polygons.push(circle(end, half_width)),
+            LineCap::Square => {
+                let n = normal(from, end, half_width);
+                // Along the line, the normal of the normal.
+                let d = PointF::new(n.y, -n.x);
+
+                polygons.push(vec![
+                    PointF::new(end.x + n.x, end.y + n.y),
+                    PointF::new(end.x + n.x + d.x, end.y + n.y + d.y),
+                    PointF::new(end.x - n.x + d.x, end.y - n.y + d.y),
+                    PointF::new(end.x - n.x, end.y - n.y),
+                ]);
+            },
+        }
+    }
+}
+
+/// The normal of the line from `a` to `b`, `length` long.
+fn normal(a: PointF, b: PointF, length: f64) -> PointF {
+    let len = distance(a, b);
+    PointF::new(-(b.y - a.y) / len * length, (b.x - a.x) / len * length)
+}
+
+fn circle(center: PointF, radius: f64) -> Vec<PointF> {
+    let segments = segment_count(radius).max(8) * 4;
+
+    (0..segments).map(|i| {
+        let angle = i as f64 * 2.0 * PI / segments as f64;
+        PointF::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+    }).collect()
+}
+
+fn signed_area(polygon: &[PointF]) -> f64 {
+    let mut area = 0.0;
+
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+
+    area / 2.0
+}
+
+/// Cut `points` into the dashes of `pattern`, starting `offset` into it.
+fn split_dashes(points: &[PointF], pattern: &[f64], offset: f64) -> Vec<Vec<PointF>> {
+    // Like SVG, an odd number of lengths is repeated to make an even one, so that `[2]` is
+    // a dash of 2 then a gap of 2.
+    let pattern: Vec<f64> = if pattern.len() % 2 == 0 {
+        pattern.to_vec()
+    } else {
+        pattern.iter().chain(pattern).cloned().collect()
+    };
+
+    let total: f64 = pattern.iter().fold(0.0, |sum, &dash| sum + dash);
+
+    // Find where in the pattern the line starts.
+    let mut index = 0;
+    let mut remaining = pattern[0];
+    let mut offset = ((offset % total) + total) % total;
+
+    while offset > 0.0 {
+        if offset < remaining {
+            remaining -= offset;
+            break;
+        }
+
+        offset -= remaining;
+        index = (index + 1) % pattern.len();
+        remaining = pattern[index];
+    }
+
+    let mut dashes = vec![];
+    let mut current = vec![];
+
+    if index % 2 == 0 {
+        current.push(points[0]);
+    }
+
+    for window in points.windows(2) {
+        let (mut a, b) = (window[0], window[1]);
+        let mut length = distance(a, b);
+
+        while length > remaining {
+            let end = a.lerp(b, remaining / length);
+            length -= remaining;
+            a = end;
+
+            // Dashes are the even entries of the pattern, gaps the odd ones.
+            if index % 2 == 0 {
+                current.push(end);
+                dashes.push(mem::replace(&mut current, vec![]));
+            } else {
+                current.push(end);
+            }
+
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+        }
+
+        remaining -= length;
+
+        if index % 2 == 0 {
+            current.push(b);
+        }
+    }
+
+    if index % 2 == 0 && current.len() > 1 {
+        dashes.push(current);
+    }
+
+    dashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(length: f64) -> Vec<PointF> {
+        vec![PointF::new(0.0, 0.0), PointF::new(length, 0.0)]
+    }
+
+    fn xs(dashes: &[Vec<PointF>]) -> Vec<Vec<f64>> {
+        dashes.iter().map(|dash| dash.iter().map(|point| point.x).collect()).collect()
+    }
+
+    #[test]
+    fn dashes_and_gaps() {
+        assert_eq!(xs(&split_dashes(&line(10.0), &[3.0, 1.0], 0.0)),
+                   vec![vec![0.0, 3.0], vec![4.0, 7.0], vec![8.0, 10.0]]);
+    }
+
+    #[test]
+    fn odd_pattern_is_repeated() {
+        assert_eq!(xs(&split_dashes(&line(10.0), &[2.0], 0.0)),
+                   vec![vec![0.0, 2.0], vec![4.0, 6.0], vec![8.0, 10.0]]);
+        // 3, 1, 1 becomes 3, 1, 1, 3, 1, 1, so the second 3 is a gap.
+        assert_eq!(xs(&split_dashes(&line(10.0), &[3.0, 1.0, 1.0], 0.0)),
+                   vec![vec![0.0, 3.0], vec![4.0, 5.0], vec![8.0, 9.0]]);
+    }
+
+    #[test]
+    fn dash_offset() {
+        assert_eq!(xs(&split_dashes(&line(10.0), &[3.0, 1.0], 2.0)),
+                   vec![vec![0.0, 1.0], vec![2.0, 5.0], vec![6.0, 9.0]]);
+        // Negative offsets count back from the end of the pattern.
+        assert_eq!(xs(&split_dashes(&line(6.0), &[3.0, 1.0], -1.0)),
+                   vec![vec![1.0, 4.0], vec![5.0, 6.0]]);
+    }
+
+    #[test]
+    fn dashes_around_corners() {
+        let points = vec![PointF::new(0.0, 0.0), PointF::new(2.0, 0.0), PointF::new(2.0, 4.0)];
+        let dashes = split_dashes(&points, &[3.0, 1.0], 0.0);
+
+        assert_eq!(dashes, vec![
+            vec![PointF::new(0.0, 0.0), PointF::new(2.0, 0.0), PointF::new(2.0, 1.0)],
+            vec![PointF::new(2.0, 2.0), PointF::new(2.0, 4.0)],
+        ]);
+    }
+
+    #[test]
+    fn dashed_stroke() {
+        let path = Path::new().move_to(0.0, 1.0).line_to(10.0, 1.0);
+        let polylines = path.flatten(&Transform::identity());
+        let outline = Stroke::new(2.0).dashes(vec![2.0], 0.0).outline(&polylines, 1.0);
+
+        assert_eq!(outline.len(), 3);
+        assert_eq!(outline[1].iter().fold(f64::INFINITY, |min, point| min.min(point.x)), 4.0);
+    }
+}
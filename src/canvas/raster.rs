@@ -0,0 +1,496 @@
+//! A canvas drawing into a bitmap in memory, for testing paint code.
+
+use color::Color;
+use dpi::Dpi;
+use layout::{Rect, Size};
+use text::metrics::{FixedMetrics, FontMetrics, FIXED_CHAR_WIDTH, FIXED_LINE_HEIGHT};
+
+use super::{argb, intersect, Bitmap, Canvas, FillRule, Paint, Path, Point, Shape, StateStack, Stroke, Transform};
+use super::rasterize::Pixels;
+
+use std::{cmp, f64};
+
+/// Draws into a `Bitmap` without any Windows APIs, so the result is the same everywhere.
+///
+/// Shapes are filled without anti-aliasing, with a pixel inside if its center is, and
+/// polygons use the even-odd rule like GDI. Text is drawn with `FixedMetrics`, with a
+/// solid box for each character that isn't whitespace, so that only its position and
+/// length show up in snapshots.
+pub struct RasterCanvas {
+    bitmap: Bitmap,
+    state: StateStack,
+    size: Size,
+}
+
+impl RasterCanvas {
+    /// A canvas `size` pixels large, filled with `background`.
+    pub fn new(size: Size, background: Color) -> RasterCanvas {
+        Self::with_dpi(size, Dpi::default(), background)
+    }
+
+    /// A canvas of `size` logical units scaled to `dpi`, like a window on a monitor with
+    /// that DPI.
+    pub fn with_dpi(size: Size, dpi: Dpi, background: Color) -> RasterCanvas {
+        let pixels = dpi.scale_size(size);
+        let scale = dpi.scale_factor();
+
+        RasterCanvas {
+            bitmap: Bitmap::new(pixels.width, pixels.height, background),
+            state: StateStack::new(
+                Transform::scale(scale, scale), Rect::new(0, 0, pixels.width, pixels.height)
+            ),
+            size: size,
+        }
+    }
+
+    pub fn bitmap(&self) -> &Bitmap {
+        &self.bitmap
+    }
+
+    pub fn into_bitmap(self) -> Bitmap {
+        self.bitmap
+    }
+
+    fn transform(&self) -> Transform {
+        self.state.current.transform
+    }
+
+    fn render(&mut self, shape: &Shape) {
+        let mut pixels = Pixels {
+            x: 0,
+            y: 0,
+            width: self.bitmap.width,
+            height: self.bitmap.height,
+            data: &mut self.bitmap.pixels,
+        };
+
+        shape.render(&mut pixels, self.state.current.clip);
+    }
+
+    /// Fill the pixels from `left` up to `right` on row `y`, inside the clipping rectangle.
+    fn fill_span(&mut self, y: i32, left: i32, right: i32, pixel: u32) {
+        let clip = self.state.current.clip;
+
+        if y < clip.y || y >= clip.bottom() {
+            return;
+        }
+
+        let left = cmp::max(left, clip.x);
+        let right = cmp::min(right, clip.right());
+
+        for x in left..right {
+            let index = (y * self.bitmap.width + x) as usize;
+            self.bitmap.pixels[index] = pixel;
+        }
+    }
+
+    fn plot(&mut self, x: i32, y: i32, pixel: u32) {
+        self.fill_span(y, x, x + 1, pixel);
+    }
+
+    /// Fill a polygon given in pixels with the even-odd rule.
+    fn fill_pixels(&mut self, points: &[(f64, f64)], pixel: u32) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let min_y = points.iter().fold(f64::INFINITY, |min, &(_, y)| min.min(y));
+        let max_y = points.iter().fold(f64::NEG_INFINITY, |max, &(_, y)| max.max(y));
+
+        let clip = self.state.current.clip;
+        let top = cmp::max(min_y.floor() as i32, clip.y);
+        let bottom = cmp::min(max_y.ceil() as i32, clip.bottom());
+
+        let mut crossings = Vec::new();
+
+        for y in top..bottom {
+            let center = y as f64 + 0.5;
+            crossings.clear();
+
+            for (i, &(x0, y0)) in points.iter().enumerate() {
+                let (x1, y1) = points[(i + 1) % points.len()];
+
+                if (y0 <= center) != (y1 <= center) {
+                    crossings.push(x0 + (center - y0) * (x1 - x0) / (y1 - y0));
+                }
+            }
+
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            for pair in crossings.chunks(2) {
+                if pair.len() == 2 {
+                    // Pixels whose centers are between the crossings.
+                    let left = (pair[0] - 0.5).ceil() as i32;
+                    let right = (pair[1] - 0.5).ceil() as i32;
+                    self.fill_span(y, left, right, pixel);
+                }
+            }
+        }
+    }
+
+    /// Draw a line between two points in pixels, not including the last pixel for thin
+    /// lines, like GDI.
+    fn line_pixels(&mut self, from: (f64, f64), to: (f64, f64), pixel: u32) {
+        let width = self.state.line_width();
+
+        if width > 1 {
+            let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+            let len = (dx * dx + dy * dy).sqrt();
+
+            if len == 0.0 {
+                return;
+            }
+
+            // Offset both ends by half the width on either side of the line.
+            let half = width as f64 / 2.0;
+            let (nx, ny) = (-dy / len * half, dx / len * half);
+
+            self.fill_pixels(&[
+                (from.0 + nx, from.1 + ny),
+                (to.0 + nx, to.1 + ny),
+                (to.0 - nx, to.1 - ny),
+                (from.0 - nx, from.1 - ny),
+            ], pixel);
+
+            return;
+        }
+
+        let (mut x, mut y) = (from.0.round() as i32, from.1.round() as i32);
+        let (x1, y1) = (to.0.round() as i32, to.1.round() as i32);
+
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+        let step_x = if x < x1 { 1 } else { -1 };
+        let step_y = if y < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        while (x, y) != (x1, y1) {
+            self.plot(x, y, pixel);
+
+            let err2 = 2 * err;
+
+            if err2 >= dy {
+                err += dy;
+                x += step_x;
+            }
+
+            if err2 <= dx {
+                err += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    fn outline_pixels(&mut self, points: &[(f64, f64)], pixel: u32) {
+        for (i, &from) in points.iter().enumerate() {
+            let to = points[(i + 1) % points.len()];
+            self.line_pixels(from, to, pixel);
+        }
+    }
+
+    fn transform_points(&self, points: &[Point]) -> Vec<(f64, f64)> {
+        let transform = self.transform();
+        points.iter().map(|point| transform.apply_f64(point.x as f64, point.y as f64)).collect()
+    }
+
+    /// The outline of the ellipse in `bounds` as a polygon in pixels.
+    fn ellipse_pixels(&self, bounds: Rect) -> Vec<(f64, f64)> {
+        let bounds = self.transform().apply_rect(bounds);
+
+        let (rx, ry) = (bounds.width as f64 / 2.0, bounds.height as f64 / 2.0);
+        let (cx, cy) = (bounds.x as f64 + rx, bounds.y as f64 + ry);
+
+        // Enough segments that the corners don't show.
+        let segments = cmp::min(cmp::max((rx + ry) as usize, 16), 256);
+
+        (0..segments).map(|i| {
+            let angle = i as f64 * 2.0 * f64::consts::PI / segments as f64;
+            (cx + rx * angle.cos(), cy + ry * angle.sin())
+        }).collect()
+    }
+}
+
+impl Canvas for RasterCanvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn save(&mut self) {
+        self.state.save();
+    }
+
+    fn restore(&mut self) {
+        self.state.restore();
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32) {
+        self.state.transform(Transform::translate(dx as f64, dy as f64));
+    }
+
+    fn scale(&mut self, scale_x: f64, scale_y: f64) {
+        self.state.transform(Transform::scale(scale_x, scale_y));
+    }
+
+    fn clip(&mut self, rect: Rect) {
+        self.state.clip(rect);
+    }
+
+    fn set_line_width(&mut self, width: i32) {
+        self.state.current.line_width = width;
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, color: Color) {
+        let points = self.transform_points(&[from, to]);
+        self.line_pixels(points[0], points[1], argb(color));
+    }
+
+    fn draw_polygon(&mut self, points: &[Point], color: Color) {
+        let points = self.transform_points(points);
+        self.outline_pixels(&points, argb(color));
+    }
+
+    fn fill_polygon(&mut self, points: &[Point], color: Color) {
+        let points = self.transform_points(points);
+        self.fill_pixels(&points, argb(color));
+    }
+
+    fn draw_ellipse(&mut self, bounds: Rect, color: Color) {
+        let points = self.ellipse_pixels(bounds);
+        self.outline_pixels(&points, argb(color));
+    }
+
+    fn fill_ellipse(&mut self, bounds: Rect, color: Color) {
+        let points = self.ellipse_pixels(bounds);
+        self.fill_pixels(&points, argb(color));
+    }
+
+    fn fill_path(&mut self, path: &Path, paint: &Paint, rule: FillRule) {
+        let shape = Shape::fill(path, paint, rule, &self.transform());
+        self.render(&shape);
+    }
+
+    fn stroke_path(&mut self, path: &Path, stroke: &Stroke, paint: &Paint) {
+        let shape = Shape::stroke(path, stroke, paint, &self.transform());
+        self.render(&shape);
+    }
+
+    fn draw_text(&mut self, pos: Point, text: &str, color: Color) {
+        for (i, c) in text.chars().enumerate() {
+            if c.is_whitespace() {
+                continue;
+            }
+
+            let x = pos.x + i as i32 * FIXED_CHAR_WIDTH;
+            self.fill_rect(Rect::new(x + 1, pos.y + 3, FIXED_CHAR_WIDTH - 2, FIXED_LINE_HEIGHT - 6), color);
+        }
+    }
+
+    fn text_metrics<'a>(&'a self) -> Box<FontMetrics + 'a> {
+        Box::new(FixedMetrics)
+    }
+
+    fn draw_bitmap(&mut self, bitmap: &Bitmap, dest: Rect) {
+        let dest = self.transform().apply_rect(dest);
+        let visible = intersect(dest, self.state.current.clip);
+
+        if dest.width == 0 || dest.height == 0 {
+            return;
+        }
+
+        // Nearest neighbour.
+        for y in visible.y..visible.bottom() {
+            let src_y = ((y - dest.y) as i64 * bitmap.height as i64 / dest.height as i64) as i32;
+
+            for x in visible.x..visible.right() {
+                let src_x = ((x - dest.x) as i64 * bitmap.width as i64 / dest.width as i64) as i32;
+
+                if let Some(pixel) = bitmap.pixel(src_x, src_y) {
+                    self.bitmap.pixels[(y * self.bitmap.width + x) as usize] = pixel;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use canvas::{Bitmap, Canvas, FillRule, Paint, Path, Point};
+    use canvas::tests::{rows, snapshot, BLACK, RED, WHITE};
+    use dpi::Dpi;
+    use layout::{Rect, Size};
+
+    use super::*;
+
+    fn canvas(width: i32, height: i32) -> RasterCanvas {
+        RasterCanvas::new(Size::new(width, height), WHITE)
+    }
+
+    #[test]
+    fn rects() {
+        let mut canvas = canvas(8, 6);
+        canvas.fill_rect(Rect::new(1, 1, 3, 2), BLACK);
+        // Like `Polygon`, the outline includes the right and bottom edges.
+        canvas.draw_rect(Rect::new(4, 2, 3, 3), RED);
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            ........
+            .###....
+            .###rrrr
+            ....r..r
+            ....r..r
+            ....rrrr
+        "));
+    }
+
+    #[test]
+    fn lines_skip_last_pixel() {
+        let mut canvas = canvas(8, 8);
+        canvas.draw_line(Point::new(0, 0), Point::new(7, 7), BLACK);
+        canvas.draw_line(Point::new(0, 7), Point::new(5, 7), RED);
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            #.......
+            .#......
+            ..#.....
+            ...#....
+            ....#...
+            .....#..
+            ......#.
+            rrrrr...
+        "));
+    }
+
+    #[test]
+    fn ellipse() {
+        let mut canvas = canvas(9, 7);
+        canvas.fill_ellipse(Rect::new(1, 1, 7, 5), BLACK);
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            .........
+            ...###...
+            .#######.
+            .#######.
+            .#######.
+            ...###...
+            .........
+        "));
+    }
+
+    #[test]
+    fn clip_is_restored() {
+        let mut canvas = canvas(8, 6);
+        canvas.save();
+        canvas.translate(2, 1);
+        canvas.clip(Rect::new(0, 0, 3, 3));
+        canvas.fill_rect(Rect::new(-5, -5, 20, 20), BLACK);
+        canvas.restore();
+        canvas.fill_rect(Rect::new(6, 4, 2, 2), RED);
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            ........
+            ..###...
+            ..###...
+            ..###...
+            ......rr
+            ......rr
+        "));
+    }
+
+    #[test]
+    fn text_boxes() {
+        let mut canvas = canvas(24, 16);
+        canvas.draw_text(Point::new(0, 0), "a b", BLACK);
+
+        let text = snapshot(canvas.bitmap());
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[..3], ["........................"; 3]);
+        assert_eq!(lines[3..13], [".######..........######."; 10]);
+        assert_eq!(lines[13..], ["........................"; 3]);
+    }
+
+    #[test]
+    fn scaled_to_dpi() {
+        let mut canvas = RasterCanvas::with_dpi(Size::new(4, 3), Dpi(192), WHITE);
+        canvas.fill_rect(Rect::new(1, 1, 2, 1), BLACK);
+
+        assert_eq!(canvas.size(), Size::new(4, 3));
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            ........
+            ........
+            ..####..
+            ..####..
+            ........
+            ........
+        "));
+    }
+
+    #[test]
+    fn bitmap_scaled() {
+        let mut bitmap = Bitmap::new(2, 2, WHITE);
+        bitmap.pixels[0] = argb(BLACK);
+        bitmap.pixels[3] = argb(RED);
+
+        let mut canvas = canvas(6, 5);
+        canvas.draw_bitmap(&bitmap, Rect::new(1, 1, 4, 4));
+        canvas.draw_bitmap(&bitmap, Rect::new(5, 0, 0, 3));
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            ......
+            .##...
+            .##...
+            ...rr.
+            ...rr.
+        "));
+    }
+
+    #[test]
+    fn path_edges_are_blended() {
+        let mut canvas = canvas(8, 8);
+        canvas.fill_path(&Path::ellipse(4.0, 4.0, 3.0, 3.0), &Paint::from(BLACK), FillRule::NonZero);
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            ........
+            ..++++..
+            .+####+.
+            .+####+.
+            .+####+.
+            .+####+.
+            ..++++..
+            ........
+        "));
+    }
+
+    #[test]
+    fn fill_rules() {
+        let path = Path::rect(Rect::new(1, 1, 6, 6))
+            .move_to(2.0, 2.0).line_to(6.0, 2.0).line_to(6.0, 6.0).line_to(2.0, 6.0).close();
+
+        let mut canvas = canvas(8, 8);
+        canvas.fill_path(&path, &Paint::from(BLACK), FillRule::EvenOdd);
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            ........
+            .######.
+            .#....#.
+            .#....#.
+            .#....#.
+            .#....#.
+            .######.
+            ........
+        "));
+
+        canvas.fill_path(&path, &Paint::from(RED), FillRule::NonZero);
+
+        assert_eq!(snapshot(canvas.bitmap()), rows("
+            ........
+            .rrrrrr.
+            .rrrrrr.
+            .rrrrrr.
+            .rrrrrr.
+            .rrrrrr.
+            .rrrrrr.
+            ........
+        "));
+    }
+}
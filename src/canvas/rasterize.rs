@@ -0,0 +1,183 @@
+//! Anti-aliased filling of polygons, in pure Rust so that the result is the same on every
+//! backend.
+
+use layout::Rect;
+
+use super::intersect;
+use super::paint::{Paint, Rgba};
+use super::path::PointF;
+
+use std::{cmp, f64};
+
+/// Which parts of overlapping or self-intersecting polygons are inside.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside if the outlines around a point wind around it more times one way than the
+    /// other.
+    NonZero,
+    /// Inside if a ray from a point crosses an odd number of outlines.
+    EvenOdd,
+}
+
+impl FillRule {
+    fn is_inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// A rectangle of `0xAARRGGBB` pixels with its top left corner at `(x, y)` in the pixels of
+/// a canvas, which is treated as opaque.
+pub struct Pixels<'a> {
+    pub data: &'a mut [u32],
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// The number of rows sampled per pixel. Coverage within each row is exact.
+const SAMPLES: usize = 4;
+
+struct Edge {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    /// 1 if the edge goes down, -1 if it goes up.
+    winding: i32,
+}
+
+/// The pixels `polygons` touch.
+pub fn bounds(polygons: &[Vec<PointF>]) -> Option<Rect> {
+    let points = polygons.iter().flat_map(|polygon| polygon.iter());
+
+    let (min_x, min_y, max_x, max_y) = points.fold(
+        (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        |(min_x, min_y, max_x, max_y), point| {
+            (min_x.min(point.x), min_y.min(point.y), max_x.max(point.x), max_y.max(point.y))
+        }
+    );
+
+    if min_x > max_x {
+        return None;
+    }
+
+    let (left, top) = (min_x.floor() as i32, min_y.floor() as i32);
+    Some(Rect::new(left, top, max_x.ceil() as i32 - left, max_y.ceil() as i32 - top))
+}
+
+/// Fill `polygons`, given in pixels, with `paint` where they're inside `clip`, blending
+/// partly covered pixels at the edges.
+pub fn fill(pixels: &mut Pixels, clip: Rect, polygons: &[Vec<PointF>], rule: FillRule, paint: &Paint) {
+    let bounds = unwrap_or_ret!(bounds(polygons), ());
+    let area = intersect(intersect(bounds, clip), Rect::new(pixels.x, pixels.y, pixels.width, pixels.height));
+
+    if area.width == 0 || area.height == 0 {
+        return;
+    }
+
+    let mut edges = vec![];
+
+    for polygon in polygons {
+        for (i, &a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+
+            if a.y < b.y {
+                edges.push(Edge { x0: a.x, y0: a.y, x1: b.x, y1: b.y, winding: 1 });
+            } else if a.y > b.y {
+                edges.push(Edge { x0: b.x, y0: b.y, x1: a.x, y1: a.y, winding: -1 });
+            }
+        }
+    }
+
+    let mut coverage = vec![0.0; area.width as usize];
+    let mut crossings = vec![];
+
+    for y in area.y..area.bottom() {
+        for cell in &mut coverage {
+            *cell = 0.0;
+        }
+
+        for sample in 0..SAMPLES {
+            let sample_y = y as f64 + (sample as f64 + 0.5) / SAMPLES as f64;
+            crossings.clear();
+
+            for edge in &edges {
+                if edge.y0 <= sample_y && sample_y < edge.y1 {
+                    let x = edge.x0 + (sample_y - edge.y0) * (edge.x1 - edge.x0) / (edge.y1 - edge.y0);
+                    crossings.push((x, edge.winding));
+                }
+            }
+
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(cmp::Ordering::Equal));
+
+            let mut winding = 0;
+            let mut start = 0.0;
+
+            for &(x, edge_winding) in &crossings {
+                let was_inside = rule.is_inside(winding);
+                winding += edge_winding;
+
+                match (was_inside, rule.is_inside(winding)) {
+                    (false, true) => start = x,
+                    (true, false) => add_span(&mut coverage, area, start, x, 1.0 / SAMPLES as f64),
+                    _ => {},
+                }
+            }
+        }
+
+        for (i, &cover) in coverage.iter().enumerate() {
+            if cover <= 0.0 {
+                continue;
+            }
+
+            let x = area.x + i as i32;
+            let color = paint.color_at(x as f64 + 0.5, y as f64 + 0.5);
+            let index = ((y - pixels.y) * pixels.width + x - pixels.x) as usize;
+
+            pixels.data[index] = blend(pixels.data[index], color, cover.min(1.0));
+        }
+    }
+}
+
+/// Add `weight` times the part of each pixel between `start` and `end` to its coverage.
+fn add_span(coverage: &mut [f64], area: Rect, start: f64, end: f64, weight: f64) {
+    let start = start.max(area.x as f64) - area.x as f64;
+    let end = end.min(area.right() as f64) - area.x as f64;
+
+    if end <= start {
+        return;
+    }
+
+    let (first, last) = (start.floor() as usize, end.floor() as usize);
+
+    if first == last {
+        coverage[first] += (end - start) * weight;
+        return;
+    }
+
+    coverage[first] += (first as f64 + 1.0 - start) * weight;
+
+    for cell in &mut coverage[first + 1..last] {
+        *cell += weight;
+    }
+
+    if last < coverage.len() {
+        coverage[last] += (end - last as f64) * weight;
+    }
+}
+
+/// Draw `color` over `pixel` with `coverage` of it showing.
+fn blend(pixel: u32, color: Rgba, coverage: f64) -> u32 {
+    let alpha = color.a as f64 / 255.0 * coverage;
+
+    let channel = |shift: u32, value: u8| {
+        let below = ((pixel >> shift) & 0xff) as f64;
+        ((value as f64 * alpha + below * (1.0 - alpha)).round() as u32) << shift
+    };
+
+    0xff000000 | channel(16, color.r) | channel(8, color.g) | channel(0, color.b)
+}
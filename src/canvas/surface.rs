@@ -0,0 +1,347 @@
+//! Showing frames rendered in memory, for emulators, image viewers and the like.
+
+#[cfg(windows)] use {gdi32, user32};
+
+use color::Color;
+#[cfg(windows)] use ffi::gdi;
+use layout::{Rect, Size};
+
+use super::Bitmap;
+#[cfg(windows)] use super::gdi::{bitmap_info, COLORONCOLOR};
+
+#[cfg(windows)] use winapi::*;
+
+use std::{cmp, mem};
+
+/// How a frame is fitted into the client area when their sizes differ. The frame is
+/// centered and the rest of the client area is black.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Scale by the largest whole number that fits, at least 1, so pixels stay square and
+    /// the same size.
+    Integer,
+    /// Scale as large as fits while keeping the aspect ratio.
+    Fit,
+    /// Fill the whole client area.
+    Stretch,
+}
+
+/// Where a frame of size `frame` goes in a client area of size `area` with `mode`.
+pub fn scaled_rect(frame: Size, area: Size, mode: ScaleMode) -> Rect {
+    if frame.width <= 0 || frame.height <= 0 {
+        return Rect::new(0, 0, 0, 0);
+    }
+
+    let fit = (area.width as f64 / frame.width as f64).min(area.height as f64 / frame.height as f64);
+
+    let size = match mode {
+        ScaleMode::Stretch => return Rect::new(0, 0, area.width, area.height),
+        ScaleMode::Fit => Size::new(
+            (frame.width as f64 * fit).round() as i32, (frame.height as f64 * fit).round() as i32
+        ),
+        ScaleMode::Integer => {
+            let scale = cmp::max(fit.floor() as i32, 1);
+            Size::new(frame.width * scale, frame.height * scale)
+        },
+    };
+
+    Rect::new((area.width - size.width) / 2, (area.height - size.height) / 2, size.width, size.height)
+}
+
+/// A frame as it was presented, kept by surfaces that record their frames.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// The pixels as they were shown, converted to the `0xAARRGGBB` layout of `Bitmap`.
+    pub bitmap: Bitmap,
+    /// Where in the client area it was shown.
+    pub dest: Rect,
+}
+
+/// A buffer of pixels shown in the client area of a window, scaled with a `ScaleMode`.
+///
+/// Pixels are RGBA, packed as `0xRRGGBBAA`; alpha is ignored since the frame covers
+/// the window. Attach the surface to a window with `Window::set_pixel_surface()`, or
+/// turn on `set_recording()` to keep the presented frames, e.g. in tests.
+pub struct PixelSurface {
+    /// The buffer the caller draws into, in RGBA.
+    buffer: Vec<u32>,
+    /// The last presented buffer, in the layout GDI expects.
+    frame: Bitmap,
+    mode: ScaleMode,
+    resize_with_client: bool,
+    /// The size of the client area in pixels.
+    client_size: Size,
+    #[cfg(windows)]
+    hwnd: Option<HWND>,
+    recording: bool,
+    frames: Vec<Frame>,
+}
+
+/// The `0xAARRGGBB` pixel of the RGBA pixel `pixel`, made opaque.
+fn rgba_to_argb(pixel: u32) -> u32 {
+    0xff000000 | (pixel >> 8)
+}
+
+impl PixelSurface {
+    /// A black surface of `width` by `height` pixels, not attached to a window.
+    pub fn new(width: i32, height: i32, mode: ScaleMode) -> PixelSurface {
+        let frame = Bitmap::new(width, height, Color::rgb(0, 0, 0));
+
+        PixelSurface {
+            buffer: vec![0x000000ff; frame.pixels.len()],
+            frame: frame,
+            mode: mode,
+            resize_with_client: false,
+            client_size: Size::new(width, height),
+            #[cfg(windows)]
+            hwnd: None,
+            recording: false,
+            frames: vec![],
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.frame.size()
+    }
+
+    pub fn scale_mode(&self) -> ScaleMode {
+        self.mode
+    }
+
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        self.mode = mode;
+    }
+
+    /// Keep the buffer the size of the client area, so that frames are shown without
+    /// scaling. The buffer is cleared whenever its size changes.
+    pub fn set_resize_with_client(&mut self, resize: bool) {
+        self.resize_with_client = resize;
+
+        if resize {
+            let client_size = self.client_size;
+            self.resize(client_size.width, client_size.height);
+        }
+    }
+
+    /// Change the size of the buffer, clearing it to black.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        if self.frame.size() != Size::new(width, height) {
+            self.frame = Bitmap::new(width, height, Color::rgb(0, 0, 0));
+            self.buffer = vec![0x000000ff; self.frame.pixels.len()];
+        }
+    }
+
+    /// The buffer, as rows of RGBA pixels from the top down.
+    pub fn pixels(&self) -> &[u32] {
+        &self.buffer
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        &mut self.buffer
+    }
+
+    /// Show the buffer.
+    pub fn present(&mut self) {
+        for (shown, &pixel) in self.frame.pixels.iter_mut().zip(&self.buffer) {
+            *shown = rgba_to_argb(pixel);
+        }
+
+        self.paint_window();
+
+        if self.recording {
+            let dest = scaled_rect(self.frame.size(), self.client_size, self.mode);
+            self.frames.push(Frame { bitmap: self.frame.clone(), dest: dest });
+        }
+    }
+
+    /// Paint the window the surface is attached to, if any.
+    #[cfg(windows)]
+    fn paint_window(&self) {
+        if let Some(hwnd) = self.hwnd {
+            unsafe {
+                let hdc = user32::GetDC(hwnd);
+                self.paint(hdc);
+                user32::ReleaseDC(hwnd, hdc);
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn paint_window(&self) {}
+
+    /// Copy `frame`, which must be the size of the buffer, into it and show it.
+    ///
+    /// ## Panics
+    /// If `frame` is a different size than the buffer.
+    pub fn present_frame(&mut self, frame: &[u32]) {
+        self.buffer.copy_from_slice(frame);
+        self.present();
+    }
+
+    /// Keep a copy of every frame presented from now on, for `frames()`. Off by default.
+    /// Turning it off drops the frames kept so far.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+
+        if !recording {
+            self.frames.clear();
+        }
+    }
+
+    /// The frames presented while recording.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Take the frames presented so far, so that only new ones are kept.
+    pub fn take_frames(&mut self) -> Vec<Frame> {
+        mem::replace(&mut self.frames, vec![])
+    }
+
+    /// Tell the surface the size of the client area it's shown in, in pixels. Windows do
+    /// this for the surface attached to them.
+    pub fn set_client_size(&mut self, size: Size) {
+        self.client_size = size;
+
+        if self.resize_with_client {
+            self.resize(size.width, size.height);
+        }
+    }
+
+    #[cfg(windows)]
+    #[doc(hidden)]
+    pub fn attach(&mut self, hwnd: HWND) {
+        self.hwnd = Some(hwnd);
+
+        unsafe {
+            let mut client: RECT = mem::zeroed();
+            user32::GetClientRect(hwnd, &mut client);
+            self.set_client_size(Size::new(client.right - client.left, client.bottom - client.top));
+        }
+    }
+
+    /// Draw the buffer and the black bars around it on `hdc`.
+    #[cfg(windows)]
+    #[doc(hidden)]
+    pub unsafe fn paint(&self, hdc: HDC) {
+        let frame = &self.frame;
+        let dest = scaled_rect(frame.size(), self.client_size, self.mode);
+        let info = bitmap_info(frame.width, frame.height);
+        let bits = frame.pixels.as_ptr() as *const VOID;
+
+        if dest.size() == frame.size() {
+            gdi::SetDIBitsToDevice(
+                hdc, dest.x, dest.y, frame.width as DWORD, frame.height as DWORD, 0, 0,
+                0, frame.height as UINT, bits, &info, DIB_RGB_COLORS
+            );
+        } else {
+            gdi::SetStretchBltMode(hdc, COLORONCOLOR);
+            gdi32::StretchDIBits(
+                hdc, dest.x, dest.y, dest.width, dest.height, 0, 0, frame.width, frame.height,
+                bits, &info, DIB_RGB_COLORS, SRCCOPY
+            );
+        }
+
+        // Fill the bars by excluding the frame from the clip region.
+        let saved = gdi::SaveDC(hdc);
+        gdi::ExcludeClipRect(hdc, dest.x, dest.y, dest.right(), dest.bottom());
+
+        let client = RECT { left: 0, top: 0, right: self.client_size.width, bottom: self.client_size.height };
+        user32::FillRect(hdc, &client, gdi32::GetStockObject(BLACK_BRUSH) as HBRUSH);
+
+        gdi::RestoreDC(hdc, saved);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_scale() {
+        let scaled = |width, height| {
+            scaled_rect(Size::new(160, 144), Size::new(width, height), ScaleMode::Integer)
+        };
+
+        assert_eq!(scaled(500, 500), Rect::new(10, 34, 480, 432));
+        assert_eq!(scaled(160, 144), Rect::new(0, 0, 160, 144));
+        // Never smaller than the frame, even if it doesn't fit.
+        assert_eq!(scaled(100, 100), Rect::new(-30, -22, 160, 144));
+    }
+
+    #[test]
+    fn fit_and_stretch() {
+        let frame = Size::new(4, 3);
+        let area = Size::new(100, 50);
+
+        assert_eq!(scaled_rect(frame, area, ScaleMode::Fit), Rect::new(16, 0, 67, 50));
+        assert_eq!(scaled_rect(frame, Size::new(2, 10), ScaleMode::Fit), Rect::new(0, 4, 2, 2));
+        assert_eq!(scaled_rect(frame, area, ScaleMode::Stretch), Rect::new(0, 0, 100, 50));
+    }
+
+    #[test]
+    fn empty_frame() {
+        for &mode in &[ScaleMode::Integer, ScaleMode::Fit, ScaleMode::Stretch] {
+            assert_eq!(scaled_rect(Size::new(0, 3), Size::new(10, 10), mode), Rect::default());
+        }
+    }
+
+    #[test]
+    fn frames_are_recorded() {
+        let mut surface = PixelSurface::new(2, 1, ScaleMode::Integer);
+        surface.set_recording(true);
+        surface.set_client_size(Size::new(7, 3));
+
+        surface.pixels_mut()[0] = 0xff000080;
+        surface.present();
+        surface.present_frame(&[0x00ff00ff, 0x0000ff00]);
+
+        let frames = surface.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].bitmap.pixels, [0xffff0000, 0xff000000]);
+        assert_eq!(frames[0].dest, Rect::new(0, 0, 6, 3));
+        assert_eq!(frames[1].bitmap.pixels, [0xff00ff00, 0xff0000ff]);
+    }
+
+    #[test]
+    fn recording_is_opt_in() {
+        let mut surface = PixelSurface::new(1, 1, ScaleMode::Stretch);
+        surface.present();
+        assert!(surface.frames().is_empty());
+
+        surface.set_recording(true);
+        surface.present();
+        surface.present();
+        assert_eq!(surface.take_frames().len(), 2);
+        assert!(surface.frames().is_empty());
+
+        surface.present();
+        surface.set_recording(false);
+        surface.present();
+        assert!(surface.frames().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn frame_of_wrong_size() {
+        PixelSurface::new(2, 2, ScaleMode::Fit).present_frame(&[0; 3]);
+    }
+
+    #[test]
+    fn resize_with_client() {
+        let mut surface = PixelSurface::new(2, 2, ScaleMode::Integer);
+        surface.set_recording(true);
+        surface.pixels_mut()[0] = 0xffffffff;
+
+        surface.set_client_size(Size::new(3, 2));
+        assert_eq!(surface.size(), Size::new(2, 2));
+
+        surface.set_resize_with_client(true);
+        assert_eq!(surface.size(), Size::new(3, 2));
+        assert!(surface.pixels().iter().all(|&pixel| pixel == 0x000000ff));
+
+        surface.set_client_size(Size::new(5, 4));
+        surface.present();
+        assert_eq!(surface.frames()[0].dest, Rect::new(0, 0, 5, 4));
+    }
+}
@@ -0,0 +1,269 @@
+//! Custom background and text colors for controls.
+//!
+//! Standard controls ask their parent for colors with `WM_CTLCOLOR*`, which is reflected
+//! back to the control and answered here from the colors set on it. Brushes are shared by
+//! all controls with the same background color and deleted when the last of them is
+//! destroyed.
+//!
+//! Controls without colors of their own get the defaults of the current theme, if it has
+//! any.
+
+#[cfg(windows)] use {gdi32, user32};
+
+#[cfg(windows)] use winapi::*;
+
+#[cfg(windows)] use std::cell::RefCell;
+#[cfg(windows)] use std::collections::HashMap;
+#[cfg(windows)] use std::rc::{Rc, Weak};
+#[cfg(windows)] use std::mem;
+
+/// An opaque RGB color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r: r, g: g, b: b }
+    }
+
+    #[cfg(windows)]
+    #[doc(hidden)]
+    pub fn colorref(self) -> COLORREF {
+        self.r as COLORREF | (self.g as COLORREF) << 8 | (self.b as COLORREF) << 16
+    }
+}
+
+#[cfg(windows)]
+struct Brush(HBRUSH);
+
+#[cfg(windows)]
+impl Drop for Brush {
+    fn drop(&mut self) {
+        unsafe {
+            gdi32::DeleteObject(self.0 as HGDIOBJ);
+        }
+    }
+}
+
+#[cfg(windows)]
+#[derive(Default)]
+struct Colors {
+    background: Option<(Color, Rc<Brush>)>,
+    text: Option<Color>,
+}
+
+#[cfg(windows)]
+thread_local! {
+    static BRUSHES: RefCell<HashMap<Color, Weak<Brush>>> = RefCell::new(HashMap::new());
+    static COLORS: RefCell<HashMap<HWND, Colors>> = RefCell::new(HashMap::new());
+    // Used for whatever a window doesn't set itself.
+    static DEFAULTS: RefCell<Colors> = RefCell::new(Colors::default());
+}
+
+#[cfg(windows)]
+fn brush(color: Color) -> Rc<Brush> {
+    BRUSHES.with(|brushes| {
+        let mut brushes = brushes.borrow_mut();
+
+        if let Some(brush) = brushes.get(&color).and_then(Weak::upgrade) {
+            return brush;
+        }
+
+        let brush = Rc::new(Brush(unsafe { gdi32::CreateSolidBrush(color.colorref()) }));
+        brushes.insert(color, Rc::downgrade(&brush));
+        brush
+    })
+}
+
+#[cfg(windows)]
+fn update<F: FnOnce(&mut Colors)>(hwnd: HWND, f: F) {
+    COLORS.with(|colors| {
+        let mut colors = colors.borrow_mut();
+        let remove = {
+            let entry = colors.entry(hwnd).or_insert_with(Colors::default);
+            f(entry);
+            entry.background.is_none() && entry.text.is_none()
+        };
+
+        if remove {
+            colors.remove(&hwnd);
+        }
+    });
+
+    prune_brushes();
+
+    unsafe {
+        user32::InvalidateRect(hwnd, ::std::ptr::null(), TRUE);
+    }
+}
+
+#[cfg(windows)]
+#[doc(hidden)]
+pub fn set_background(hwnd: HWND, color: Option<Color>) {
+    update(hwnd, |colors| colors.background = color.map(|color| (color, brush(color))));
+}
+
+#[cfg(windows)]
+#[doc(hidden)]
+pub fn set_text(hwnd: HWND, color: Option<Color>) {
+    update(hwnd, |colors| colors.text = color);
+}
+
+/// Set the colors used by windows that don't have their own. Windows have to be redrawn to
+/// pick them up.
+#[cfg(windows)]
+#[doc(hidden)]
+pub fn set_defaults(background: Option<Color>, text: Option<Color>) {
+    DEFAULTS.with(|defaults| {
+        let mut defaults = defaults.borrow_mut();
+        defaults.background = background.map(|color| (color, brush(color)));
+        defaults.text = text;
+    });
+
+    prune_brushes();
+}
+
+/// Forget the colors of a window being destroyed, freeing its brush if no other window
+/// uses it.
+#[cfg(windows)]
+#[doc(hidden)]
+pub fn forget(hwnd: HWND) {
+    let had_colors = COLORS.with(|colors| colors.borrow_mut().remove(&hwnd).is_some());
+
+    if had_colors {
+        prune_brushes();
+    }
+}
+
+/// Dropping the last reference to a brush deletes it, but leaves its entry in the cache.
+#[cfg(windows)]
+fn prune_brushes() {
+    BRUSHES.with(|brushes| {
+        let mut brushes = brushes.borrow_mut();
+
+        let deleted: Vec<Color> = brushes.iter()
+            .filter(|&(_, brush)| brush.upgrade().is_none())
+            .map(|(&color, _)| color)
+            .collect();
+
+        for color in deleted {
+            brushes.remove(&color);
+        }
+    });
+}
+
+#[cfg(windows)]
+fn resolve(colors: &Colors) -> (Option<(Color, HBRUSH)>, Option<Color>) {
+    (colors.background.as_ref().map(|&(color, ref brush)| (color, brush.0)), colors.text)
+}
+
+/// The colors of `hwnd`, falling back to the defaults for those it doesn't set.
+#[cfg(windows)]
+fn colors_for(hwnd: HWND) -> Option<(Option<(Color, HBRUSH)>, Option<Color>)> {
+    let (background, text) = COLORS.with(|colors| colors.borrow().get(&hwnd).map(resolve))
+        .unwrap_or((None, None));
+    let (default_background, default_text) = DEFAULTS.with(|defaults| resolve(&defaults.borrow()));
+
+    match (background.or(default_background), text.or(default_text)) {
+        (None, None) => None,
+        colors => Some(colors),
+    }
+}
+
+/// Answer a `WM_CTLCOLOR*` message reflected to `hwnd`, if it has custom colors.
+#[cfg(windows)]
+#[doc(hidden)]
+pub unsafe fn handle_ctl_color(hwnd: HWND, msg: UINT, hdc: HDC) -> Option<LRESULT> {
+    let (background, text) = unwrap_or_ret!(colors_for(hwnd), None);
+
+    // Start from the default colors, so that only what was set changes.
+    let parent = user32::GetParent(hwnd);
+    let mut res = user32::DefWindowProcW(parent, msg, hdc as WPARAM, hwnd as LPARAM);
+
+    if let Some(text) = text {
+        gdi32::SetTextColor(hdc, text.colorref());
+    }
+
+    if let Some((color, brush)) = background {
+        gdi32::SetBkColor(hdc, color.colorref());
+        res = brush as LRESULT;
+    }
+
+    Some(res)
+}
+
+/// Fill the client area of `hwnd` with its background color, if it has one.
+#[cfg(windows)]
+#[doc(hidden)]
+pub unsafe fn erase_background(hwnd: HWND, hdc: HDC) -> bool {
+    match colors_for(hwnd).and_then(|(background, _)| background) {
+        Some((_, brush)) => {
+            let mut rect: RECT = mem::zeroed();
+            user32::GetClientRect(hwnd, &mut rect);
+            user32::FillRect(hdc, &rect, brush);
+            true
+        },
+        None => false,
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use abs_window::AbsWindow;
+    use window::Window;
+
+    use super::*;
+
+    fn has_brush(color: Color) -> bool {
+        BRUSHES.with(|brushes| brushes.borrow().contains_key(&color))
+    }
+
+    fn has_colors(hwnd: HWND) -> bool {
+        COLORS.with(|colors| colors.borrow().contains_key(&hwnd))
+    }
+
+    #[test]
+    fn brushes_are_shared_and_released_with_windows() {
+        let color = Color::rgb(12, 34, 56);
+
+        let window = Window::new("Colors");
+        let child = Window::new_child(&window);
+        let (hwnd, child_hwnd) = (window.ptr(), child.ptr());
+
+        window.set_background_color(Some(color));
+        child.set_background_color(Some(color));
+
+        let shared = COLORS.with(|colors| {
+            let colors = colors.borrow();
+            let brush = |hwnd: HWND| colors[&hwnd].background.as_ref().map(|&(_, ref brush)| brush.0);
+            brush(hwnd) == brush(child_hwnd)
+        });
+        assert!(shared, "windows with the same color have separate brushes");
+
+        // Dropping a window destroys it, which has to forget its colors.
+        drop(child);
+        assert!(!has_colors(child_hwnd));
+        assert!(has_brush(color));
+
+        drop(window);
+        assert!(!has_colors(hwnd));
+        assert!(!has_brush(color));
+    }
+
+    #[test]
+    fn clearing_the_color_releases_the_brush() {
+        let color = Color::rgb(65, 43, 21);
+        let window = Window::new("Colors");
+
+        window.set_background_color(Some(color));
+        assert!(has_brush(color));
+
+        window.set_background_color(None);
+        assert!(!has_colors(window.ptr()));
+        assert!(!has_brush(color));
+    }
+}
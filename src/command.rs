@@ -0,0 +1,24 @@
+//! Commands triggered by toolbar buttons, menu items and keyboard shortcuts.
+
+/// A user-chosen identifier for an action, e.g. `const SAVE: CommandId = CommandId(1);`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CommandId(pub u16);
+
+/// Where a command was triggered from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandSource {
+    ToolBar,
+    /// The window's menu bar, see `Window::set_menu()`.
+    Menu,
+    /// A menu shown with `Window::show_context_menu()`.
+    ContextMenu,
+    /// A shortcut bound with `Window::add_accelerator()`.
+    Accelerator,
+}
+
+/// An action requested by the user, delivered to `Window::on_command()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub id: CommandId,
+    pub source: CommandSource,
+}
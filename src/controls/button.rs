@@ -0,0 +1,203 @@
+use user32;
+
+use abs_window::AbsWindow;
+use ffi::{WindowHandle, OCM_BASE};
+use ffi::class::system as system_classes;
+use ffi::traits::{WindowData, WindowEvents};
+use layout::Size;
+use text::TextWindow;
+use text::metrics;
+use winstr::WinString;
+
+use winapi::*;
+
+use std::cmp;
+
+// Missing from `winapi`.
+const BM_GETCHECK: UINT = 0x00F0;
+const BM_SETCHECK: UINT = 0x00F1;
+const BST_UNCHECKED: WPARAM = 0;
+const BST_CHECKED: WPARAM = 1;
+
+/// Space around the text of a push button.
+const BUTTON_PADDING: Size = Size { width: 20, height: 10 };
+/// The smallest push button, as in the Windows guidelines.
+const BUTTON_MIN: Size = Size { width: 75, height: 23 };
+/// The size of the box of a check box, and the gap between it and the text.
+const CHECK_SIZE: i32 = 13;
+const CHECK_GAP: i32 = 4;
+
+/// A push button.
+pub struct Button {
+    hand: WindowHandle<Self>,
+}
+
+impl Button {
+    pub fn new<P: AbsWindow, T: AsRef<str>>(parent: &P, text: T) -> Button {
+        let data = ButtonData::new(parent.ptr(), text, BS_PUSHBUTTON);
+        let hand = WindowHandle::create_instance(system_classes::BUTTON, data).unwrap();
+
+        Button {
+            hand: hand
+        }
+    }
+
+    /// Called when the button is clicked.
+    pub fn on_click<F>(&mut self, on_click: F) -> &mut Self where F: FnMut(&mut Button) + 'static {
+        unsafe {
+            self.hand.data_mut().on_click = Some(Box::new(on_click));
+        }
+
+        self
+    }
+}
+
+impl AbsWindow for Button {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+
+    fn measure(&self, _available: Size) -> Size {
+        let text = self.get_text();
+        let text = metrics::with_metrics(self, |metrics| metrics::text_size(metrics, &text));
+
+        Size::new(
+            cmp::max(text.width + BUTTON_PADDING.width, BUTTON_MIN.width),
+            cmp::max(text.height + BUTTON_PADDING.height, BUTTON_MIN.height),
+        )
+    }
+}
+
+unsafe impl TextWindow for Button {}
+
+impl WindowEvents for Button {
+    type Data = ButtonData<Button>;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, wparam: WPARAM, _: LPARAM) -> Option<LRESULT> {
+        handle_click(hnd, msg, wparam, |hand| Button { hand: hand })
+    }
+}
+
+/// A box that is checked and unchecked by clicking it, with a label.
+pub struct CheckBox {
+    hand: WindowHandle<Self>,
+}
+
+impl CheckBox {
+    pub fn new<P: AbsWindow, T: AsRef<str>>(parent: &P, text: T) -> CheckBox {
+        let data = ButtonData::new(parent.ptr(), text, BS_AUTOCHECKBOX);
+        let hand = WindowHandle::create_instance(system_classes::BUTTON, data).unwrap();
+
+        CheckBox {
+            hand: hand
+        }
+    }
+
+    pub fn is_checked(&self) -> bool {
+        unsafe {
+            user32::SendMessageW(self.ptr(), BM_GETCHECK, 0, 0) as WPARAM == BST_CHECKED
+        }
+    }
+
+    /// Check or uncheck the box. This does not trigger `on_toggled`.
+    pub fn set_checked(&mut self, checked: bool) {
+        let state = if checked { BST_CHECKED } else { BST_UNCHECKED };
+
+        unsafe {
+            user32::SendMessageW(self.ptr(), BM_SETCHECK, state, 0);
+        }
+    }
+
+    /// Called with the new state when the user checks or unchecks the box.
+    pub fn on_toggled<F>(&mut self, on_toggled: F) -> &mut Self
+    where F: FnMut(&mut CheckBox, bool) + 'static {
+        unsafe {
+            self.hand.data_mut().on_click = Some(Box::new(move |check_box: &mut CheckBox| {
+                let checked = check_box.is_checked();
+                on_toggled(check_box, checked)
+            }));
+        }
+
+        self
+    }
+}
+
+impl AbsWindow for CheckBox {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+
+    fn measure(&self, _available: Size) -> Size {
+        let text = self.get_text();
+        let text = metrics::with_metrics(self, |metrics| metrics::text_size(metrics, &text));
+
+        Size::new(CHECK_SIZE + CHECK_GAP + text.width, cmp::max(CHECK_SIZE, text.height))
+    }
+}
+
+unsafe impl TextWindow for CheckBox {}
+
+impl WindowEvents for CheckBox {
+    type Data = ButtonData<CheckBox>;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, wparam: WPARAM, _: LPARAM) -> Option<LRESULT> {
+        handle_click(hnd, msg, wparam, |hand| CheckBox { hand: hand })
+    }
+}
+
+/// Call `on_click` for a reflected `BN_CLICKED`, with the control made by `wrap`.
+fn handle_click<B, F>(hnd: &WindowHandle<B>, msg: UINT, wparam: WPARAM, wrap: F) -> Option<LRESULT>
+where B: WindowEvents<Data = ButtonData<B>>, F: FnOnce(WindowHandle<B>) -> B {
+    if msg != OCM_BASE + WM_COMMAND || HIWORD(wparam as DWORD) != BN_CLICKED {
+        return None;
+    }
+
+    let cb = unsafe {
+        hnd.data_mut().on_click.take()
+    };
+
+    if let Some(mut on_click) = cb {
+        on_click(&mut wrap(hnd.clone()));
+
+        unsafe {
+            hnd.data_mut().on_click = Some(on_click);
+        }
+    }
+
+    Some(0)
+}
+
+/// The data of the `BUTTON` class controls, which are all clickable.
+pub struct ButtonData<B> {
+    parent: HWND,
+    text: WinString,
+    style: DWORD,
+    on_click: Option<Box<FnMut(&mut B)>>,
+}
+
+impl<B> ButtonData<B> {
+    fn new<T: AsRef<str>>(parent: HWND, text: T, style: DWORD) -> ButtonData<B> {
+        ButtonData {
+            parent: parent,
+            text: WinString::from_str(text),
+            style: style,
+            on_click: None,
+        }
+    }
+}
+
+impl<B> WindowData for ButtonData<B> {
+    fn name(&self) -> Option<&WinString> {
+        Some(&self.text)
+    }
+
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE | WS_TABSTOP | self.style
+    }
+
+    fn ex_style(&self) -> DWORD { 0 }
+}
@@ -0,0 +1,45 @@
+use ffi::comctl;
+
+use winapi::*;
+
+/// A set of same-sized images, used for toolbar buttons.
+pub struct ImageList {
+    himl: HIMAGELIST,
+}
+
+impl ImageList {
+    /// Create an empty list of `width` x `height` images with an alpha channel.
+    pub fn new(width: i32, height: i32) -> ImageList {
+        let himl = unsafe {
+            comctl::ImageList_Create(width, height, ILC_COLOR32 | ILC_MASK, 0, 4)
+        };
+
+        assert!(!himl.is_null(), "Failed to create an image list");
+
+        ImageList {
+            himl: himl
+        }
+    }
+
+    /// Add a copy of `icon`, returning its index.
+    pub unsafe fn add_icon(&mut self, icon: HICON) -> usize {
+        comctl::ImageList_ReplaceIcon(self.himl, -1, icon) as usize
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { comctl::ImageList_GetImageCount(self.himl) as usize }
+    }
+
+    #[doc(hidden)]
+    pub fn himl(&self) -> HIMAGELIST {
+        self.himl
+    }
+}
+
+impl Drop for ImageList {
+    fn drop(&mut self) {
+        unsafe {
+            comctl::ImageList_Destroy(self.himl);
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use user32;
+
+use abs_window::AbsWindow;
+use ffi::{WindowHandle, OCM_BASE};
+use ffi::class::system as system_classes;
+use ffi::msg::{self, Notify};
+use ffi::traits::{WindowData, WindowEvents};
+use winstr::WinString;
+
+use winapi::*;
+
+use std::{mem, ptr, slice};
+
+/// How the rows of a `ListView` are displayed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ListViewMode {
+    Icon,
+    SmallIcon,
+    List,
+    /// A table with one column per cell and a header.
+    Report,
+}
+
+impl ListViewMode {
+    fn style(self) -> DWORD {
+        match self {
+            ListViewMode::Icon => LVS_ICON,
+            ListViewMode::SmallIcon => LVS_SMALLICON,
+            ListViewMode::List => LVS_LIST,
+            ListViewMode::Report => LVS_REPORT,
+        }
+    }
+}
+
+/// Supplies the contents of a virtual `ListView` on demand.
+pub trait TableSource {
+    /// The total number of rows.
+    fn row_count(&self) -> usize;
+
+    /// The text of the given cell. Column 0 is the label of the row.
+    fn cell_text(&mut self, row: usize, column: usize) -> String;
+
+    /// Hint that the rows `from ... to` (inclusive) are about to be displayed.
+    fn prefetch(&mut self, _from: usize, _to: usize) {}
+
+    /// Reorder the rows by `column`. Return `false` if sorting by that column isn't supported.
+    fn sort(&mut self, _column: usize, _ascending: bool) -> bool { false }
+}
+
+/// A list of rows with optional columns (`SysListView32`).
+pub struct ListView {
+    hand: WindowHandle<Self>,
+}
+
+impl ListView {
+    pub fn new<P: AbsWindow>(parent: &P, mode: ListViewMode) -> ListView {
+        Self::create(parent.ptr(), mode, None)
+    }
+
+    /// Create a list view that doesn't store any rows itself, but asks `source` for the
+    /// text of each cell as it is displayed (`LVS_OWNERDATA`).
+    pub fn virtual_list<P, T>(parent: &P, mode: ListViewMode, source: T) -> ListView
+    where P: AbsWindow, T: TableSource + 'static {
+        let mut list = Self::create(parent.ptr(), mode, Some(Box::new(source)));
+        list.refresh();
+        list
+    }
+
+    fn create(parent: HWND, mode: ListViewMode, source: Option<Box<TableSource>>) -> ListView {
+        let data = ListViewData::new(parent, mode, source);
+        let hand = WindowHandle::create_instance(system_classes::LIST_VIEW, data).unwrap();
+
+        unsafe {
+            user32::SendMessageW(
+                hand.hwnd(), LVM_SETEXTENDEDLISTVIEWSTYLE,
+                LVS_EX_FULLROWSELECT as WPARAM, LVS_EX_FULLROWSELECT as LPARAM
+            );
+        }
+
+        ListView {
+            hand: hand
+        }
+    }
+
+    pub fn is_virtual(&self) -> bool {
+        unsafe { self.hand.data_mut().source.is_some() }
+    }
+
+    pub fn set_mode(&mut self, mode: ListViewMode) {
+        unsafe {
+            let style = user32::GetWindowLongPtrW(self.ptr(), GWL_STYLE) as DWORD;
+            let style = (style & !LVS_TYPEMASK) | mode.style();
+
+            user32::SetWindowLongPtrW(self.ptr(), GWL_STYLE, style as LONG_PTR);
+            self.hand.data_mut().mode = mode;
+        }
+    }
+
+    pub fn mode(&self) -> ListViewMode {
+        unsafe { self.hand.data_mut().mode }
+    }
+
+    /// Append a column, returning its index. Columns are only displayed in `Report` mode.
+    pub fn add_column<T: AsRef<str>>(&mut self, title: T, width: i32) -> usize {
+        let title = WinString::from_str(title);
+        let column = self.column_count();
+
+        unsafe {
+            let mut col: LVCOLUMNW = mem::zeroed();
+            col.mask = LVCF_FMT | LVCF_TEXT | LVCF_WIDTH | LVCF_SUBITEM;
+            col.fmt = LVCFMT_LEFT;
+            col.cx = width;
+            col.pszText = title.as_ptr() as LPWSTR;
+            col.iSubItem = column as c_int;
+
+            user32::SendMessageW(
+                self.ptr(), LVM_INSERTCOLUMNW, column as WPARAM, &col as *const _ as LPARAM
+            );
+        }
+
+        column
+    }
+
+    pub fn remove_column(&mut self, column: usize) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), LVM_DELETECOLUMN, column as WPARAM, 0);
+        }
+    }
+
+    pub fn column_count(&self) -> usize {
+        unsafe {
+            let header = user32::SendMessageW(self.ptr(), LVM_GETHEADER, 0, 0) as HWND;
+            user32::SendMessageW(header, HDM_GETITEMCOUNT, 0, 0) as usize
+        }
+    }
+
+    pub fn set_column_width(&mut self, column: usize, width: i32) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), LVM_SETCOLUMNWIDTH, column as WPARAM, width as LPARAM);
+        }
+    }
+
+    /// Append a row, returning its index.
+    ///
+    /// ## Panics
+    /// If this is a virtual list view; its rows come from the `TableSource`.
+    pub fn add_row<T: AsRef<str>>(&mut self, cells: &[T]) -> usize {
+        assert!(!self.is_virtual(), "The rows of a virtual ListView come from its TableSource");
+
+        let row = self.row_count();
+        let label = WinString::from_str(cells.first().map_or("", |cell| cell.as_ref()));
+
+        unsafe {
+            let mut item: LVITEMW = mem::zeroed();
+            item.mask = LVIF_TEXT;
+            item.iItem = row as c_int;
+            item.pszText = label.as_ptr() as LPWSTR;
+
+            user32::SendMessageW(self.ptr(), LVM_INSERTITEMW, 0, &item as *const _ as LPARAM);
+        }
+
+        for (column, text) in cells.iter().enumerate().skip(1) {
+            self.set_cell(row, column, text);
+        }
+
+        row
+    }
+
+    /// Set the text of a cell in a non-virtual list view.
+    pub fn set_cell<T: AsRef<str>>(&mut self, row: usize, column: usize, text: T) {
+        let text = WinString::from_str(text);
+
+        unsafe {
+            let mut item: LVITEMW = mem::zeroed();
+            item.iSubItem = column as c_int;
+            item.pszText = text.as_ptr() as LPWSTR;
+
+            user32::SendMessageW(
+                self.ptr(), LVM_SETITEMTEXTW, row as WPARAM, &item as *const _ as LPARAM
+            );
+        }
+    }
+
+    pub fn cell_text(&self, row: usize, column: usize) -> String {
+        unsafe { item_text(self.ptr(), row, column) }
+    }
+
+    pub fn remove_row(&mut self, row: usize) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), LVM_DELETEITEM, row as WPARAM, 0);
+        }
+    }
+
+    /// Remove all rows. Virtual list views are refreshed instead.
+    pub fn clear(&mut self) {
+        if self.is_virtual() {
+            self.refresh();
+        } else {
+            unsafe {
+                user32::SendMessageW(self.ptr(), LVM_DELETEALLITEMS, 0, 0);
+            }
+        }
+    }
+
+    pub fn row_count(&self) -> usize {
+        unsafe {
+            user32::SendMessageW(self.ptr(), LVM_GETITEMCOUNT, 0, 0) as usize
+        }
+    }
+
+    /// Re-read the row count from the `TableSource` and redraw all rows.
+    ///
+    /// Call this whenever the data behind a virtual list view changes.
+    pub fn refresh(&mut self) {
+        unsafe {
+            let count = self.hand.data_mut().source.as_ref().map_or(0, |source| source.row_count());
+
+            user32::SendMessageW(self.ptr(), LVM_SETITEMCOUNT, count as WPARAM, 0);
+            user32::InvalidateRect(self.ptr(), ptr::null(), TRUE);
+        }
+    }
+
+    pub fn selected_rows(&self) -> Vec<usize> {
+        let mut rows = Vec::new();
+        let mut next = -1;
+
+        loop {
+            next = unsafe {
+                user32::SendMessageW(self.ptr(), LVM_GETNEXTITEM, next as WPARAM, LVNI_SELECTED)
+            };
+
+            match msg::index(next as c_int) {
+                Some(row) => rows.push(row),
+                None => return rows,
+            }
+        }
+    }
+
+    pub fn ensure_visible(&mut self, row: usize) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), LVM_ENSUREVISIBLE, row as WPARAM, FALSE as LPARAM);
+        }
+    }
+
+    /// Sort the rows by the text of `column` and show the sort direction in its header.
+    ///
+    /// Virtual list views are sorted by `TableSource::sort()` instead.
+    pub fn sort_by(&mut self, column: usize, ascending: bool) {
+        if self.is_virtual() {
+            let sorted = unsafe {
+                self.hand.data_mut().source.as_mut().unwrap().sort(column, ascending)
+            };
+
+            if !sorted {
+                return;
+            }
+
+            self.refresh();
+        } else {
+            // Rows can't be read while the list view is moving them around, so compare a
+            // copy of the column, indexed by the original row number stored in each row.
+            let texts = (0 .. self.row_count()).map(|row| unsafe {
+                set_row_param(self.ptr(), row, row as LPARAM);
+                item_text(self.ptr(), row, column)
+            }).collect();
+
+            let ctxt = SortContext {
+                texts: texts,
+                ascending: ascending,
+            };
+
+            unsafe {
+                user32::SendMessageW(
+                    self.ptr(), LVM_SORTITEMS, &ctxt as *const _ as WPARAM, compare_rows as LPARAM
+                );
+            }
+        }
+
+        unsafe {
+            set_sort_arrow(self.ptr(), column, ascending);
+            self.hand.data_mut().sort = Some((column, ascending));
+        }
+    }
+
+    /// The column and direction of the last call to `sort_by()`.
+    pub fn sorted_by(&self) -> Option<(usize, bool)> {
+        unsafe { self.hand.data_mut().sort }
+    }
+
+    /// Sort by a column when its header is clicked, reversing the direction if it is
+    /// clicked again.
+    pub fn set_sort_on_click(&mut self, sort_on_click: bool) {
+        unsafe {
+            self.hand.data_mut().sort_on_click = sort_on_click;
+        }
+    }
+
+    pub fn on_column_click<F>(&mut self, on_column_click: F) -> &mut Self
+    where F: FnMut(&mut ListView, usize) + 'static {
+        unsafe {
+            self.hand.data_mut().on_column_click = Some(Box::new(on_column_click));
+        }
+
+        self
+    }
+
+    pub fn on_selection_changed<F>(&mut self, on_selection_changed: F) -> &mut Self
+    where F: FnMut(&mut ListView) + 'static {
+        unsafe {
+            self.hand.data_mut().on_selection_changed = Some(Box::new(on_selection_changed));
+        }
+
+        self
+    }
+}
+
+impl AbsWindow for ListView {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+}
+
+impl WindowEvents for ListView {
+    type Data = ListViewData;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, _: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        if msg != OCM_BASE + WM_NOTIFY {
+            return None;
+        }
+
+        let data = unsafe { hnd.data_mut() };
+        let mut list = ListView { hand: hnd.clone() };
+
+        match unsafe { Notify::from_lparam(lparam) } {
+            Notify::ListViewDispInfo(item) => {
+                if let (Some(source), true) = (data.source.as_mut(), item.mask & LVIF_TEXT != 0) {
+                    let text = source.cell_text(item.iItem as usize, item.iSubItem as usize);
+                    let text = WinString::from_str(text);
+
+                    let buf = unsafe {
+                        slice::from_raw_parts_mut(item.pszText, item.cchTextMax as usize)
+                    };
+
+                    msg::copy_text(text.as_wide(), buf);
+                }
+            },
+            Notify::ListViewCacheHint { from, to } => {
+                data.source.as_mut().map(|source| source.prefetch(from, to));
+            },
+            Notify::ListViewColumnClick { column } => {
+                if data.sort_on_click {
+                    let ascending = match data.sort {
+                        Some((sorted, ascending)) if sorted == column => !ascending,
+                        _ => true,
+                    };
+
+                    list.sort_by(column, ascending);
+                }
+
+                if let Some(mut on_column_click) = data.on_column_click.take() {
+                    on_column_click(&mut list, column);
+                    data.on_column_click = Some(on_column_click);
+                }
+            },
+            Notify::ListViewItemChanged { old_state, new_state, .. } => {
+                if (old_state ^ new_state) & LVIS_SELECTED != 0 {
+                    if let Some(mut on_selection_changed) = data.on_selection_changed.take() {
+                        on_selection_changed(&mut list);
+                        data.on_selection_changed = Some(on_selection_changed);
+                    }
+                }
+            },
+            _ => return None,
+        }
+
+        Some(0)
+    }
+}
+
+pub struct ListViewData {
+    parent: HWND,
+    mode: ListViewMode,
+    source: Option<Box<TableSource>>,
+    sort: Option<(usize, bool)>,
+    sort_on_click: bool,
+    on_column_click: Option<Box<FnMut(&mut ListView, usize)>>,
+    on_selection_changed: Option<Box<FnMut(&mut ListView)>>,
+}
+
+impl ListViewData {
+    fn new(parent: HWND, mode: ListViewMode, source: Option<Box<TableSource>>) -> ListViewData {
+        ListViewData {
+            parent: parent,
+            mode: mode,
+            source: source,
+            sort: None,
+            sort_on_click: false,
+            on_column_click: None,
+            on_selection_changed: None,
+        }
+    }
+}
+
+impl WindowData for ListViewData {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        let owner_data = if self.source.is_some() { LVS_OWNERDATA } else { 0 };
+
+        WS_CHILD | WS_VISIBLE | WS_TABSTOP | LVS_SHOWSELALWAYS | owner_data | self.mode.style()
+    }
+}
+
+struct SortContext {
+    texts: Vec<String>,
+    ascending: bool,
+}
+
+/// `LVM_SORTITEMS` callback; the first two arguments are the rows' `lParam`s, set to their
+/// index in `SortContext::texts` before sorting.
+unsafe extern "system" fn compare_rows(row1: LPARAM, row2: LPARAM, ctxt: LPARAM) -> c_int {
+    let ctxt = &*(ctxt as *const SortContext);
+
+    let ord = ctxt.texts[row1 as usize].cmp(&ctxt.texts[row2 as usize]);
+
+    if ctxt.ascending { ord as c_int } else { ord.reverse() as c_int }
+}
+
+unsafe fn set_row_param(hwnd: HWND, row: usize, param: LPARAM) {
+    let mut item: LVITEMW = mem::zeroed();
+    item.mask = LVIF_PARAM;
+    item.iItem = row as c_int;
+    item.lParam = param;
+
+    user32::SendMessageW(hwnd, LVM_SETITEMW, 0, &item as *const _ as LPARAM);
+}
+
+unsafe fn item_text(hwnd: HWND, row: usize, column: usize) -> String {
+    let mut buf = vec![0u16; 64];
+
+    loop {
+        let mut item: LVITEMW = mem::zeroed();
+        item.iSubItem = column as c_int;
+        item.pszText = buf.as_mut_ptr();
+        item.cchTextMax = buf.len() as c_int;
+
+        let len = user32::SendMessageW(
+            hwnd, LVM_GETITEMTEXTW, row as WPARAM, &mut item as *mut _ as LPARAM
+        ) as usize;
+
+        // The text was truncated if it filled the buffer
+        if len + 1 < buf.len() {
+            return String::from_utf16_lossy(&buf[..len]);
+        }
+
+        let new_len = buf.len() * 2;
+        buf.resize(new_len, 0);
+    }
+}
+
+unsafe fn set_sort_arrow(hwnd: HWND, column: usize, ascending: bool) {
+    let header = user32::SendMessageW(hwnd, LVM_GETHEADER, 0, 0) as HWND;
+    let count = user32::SendMessageW(header, HDM_GETITEMCOUNT, 0, 0) as usize;
+
+    for i in 0 .. count {
+        let mut item: HDITEMW = mem::zeroed();
+        item.mask = HDI_FORMAT;
+
+        user32::SendMessageW(header, HDM_GETITEMW, i as WPARAM, &mut item as *mut _ as LPARAM);
+
+        item.fmt &= !(HDF_SORTUP | HDF_SORTDOWN);
+
+        if i == column {
+            item.fmt |= if ascending { HDF_SORTUP } else { HDF_SORTDOWN };
+        }
+
+        user32::SendMessageW(header, HDM_SETITEMW, i as WPARAM, &item as *const _ as LPARAM);
+    }
+}
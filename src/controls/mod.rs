@@ -0,0 +1,39 @@
+//! Wrappers for the controls provided by the Common Controls library (`comctl32.dll`).
+//!
+//! `InitCommonControlsEx()` is called automatically the first time each kind of control
+//! is created.
+
+#[cfg(windows)] pub mod button;
+#[cfg(windows)] pub mod image_list;
+#[cfg(windows)] pub mod list_view;
+#[cfg(windows)] pub mod progress;
+#[cfg(windows)] pub mod slider;
+#[cfg(windows)] pub mod spin_box;
+pub mod status_bar;
+#[cfg(windows)] pub mod tab_view;
+#[cfg(windows)] pub mod tool_bar;
+pub mod tree_view;
+
+#[cfg(windows)] pub use self::button::{Button, CheckBox};
+#[cfg(windows)] pub use self::image_list::ImageList;
+#[cfg(windows)] pub use self::list_view::{ListView, ListViewMode, TableSource};
+#[cfg(windows)] pub use self::progress::ProgressBar;
+#[cfg(windows)] pub use self::slider::Slider;
+#[cfg(windows)] pub use self::spin_box::SpinBox;
+#[cfg(windows)] pub use self::status_bar::StatusBar;
+#[cfg(windows)] pub use self::tab_view::TabView;
+#[cfg(windows)] pub use self::tool_bar::{ToolBar, ToolButton};
+#[cfg(windows)] pub use self::tree_view::{TreeModel, TreeView};
+
+/// The direction a control is laid out in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation::Horizontal
+    }
+}
@@ -0,0 +1,120 @@
+use user32;
+
+use abs_window::AbsWindow;
+use ffi::WindowHandle;
+use ffi::class::system as system_classes;
+use ffi::traits::{WindowData, WindowEvents};
+
+use winapi::*;
+
+/// A bar which fills up as an operation progresses.
+pub struct ProgressBar {
+    hand: WindowHandle<Self>,
+}
+
+impl ProgressBar {
+    pub fn new<P: AbsWindow>(parent: &P) -> ProgressBar {
+        let data = ProgressData { parent: parent.ptr() };
+        let hand = WindowHandle::create_instance(system_classes::PROGRESS, data).unwrap();
+
+        ProgressBar {
+            hand: hand
+        }
+    }
+
+    /// Set the values corresponding to an empty and a full bar. Defaults to 0 and 100.
+    pub fn set_range(&mut self, min: i32, max: i32) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), PBM_SETRANGE32, min as WPARAM, max as LPARAM);
+        }
+    }
+
+    /// The values corresponding to an empty and a full bar.
+    pub fn range(&self) -> (i32, i32) {
+        let mut range = PBRANGE { iLow: 0, iHigh: 0 };
+
+        unsafe {
+            user32::SendMessageW(self.ptr(), PBM_GETRANGE, 0, &mut range as *mut _ as LPARAM);
+        }
+
+        (range.iLow, range.iHigh)
+    }
+
+    pub fn set_value(&mut self, value: i32) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), PBM_SETPOS, value as WPARAM, 0);
+        }
+    }
+
+    pub fn value(&self) -> i32 {
+        unsafe {
+            user32::SendMessageW(self.ptr(), PBM_GETPOS, 0, 0) as i32
+        }
+    }
+
+    /// Set the amount `step()` advances the bar by. Defaults to 10.
+    pub fn set_step(&mut self, step: i32) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), PBM_SETSTEP, step as WPARAM, 0);
+        }
+    }
+
+    pub fn step(&mut self) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), PBM_STEPIT, 0, 0);
+        }
+    }
+
+    /// Switch to a continuously scrolling bar, for operations of unknown length.
+    ///
+    /// `interval_ms` is the time between animation updates; 0 uses the default of 30ms.
+    pub fn set_marquee(&mut self, marquee: bool, interval_ms: u32) {
+        unsafe {
+            let style = user32::GetWindowLongPtrW(self.ptr(), GWL_STYLE) as DWORD;
+
+            if marquee {
+                user32::SetWindowLongPtrW(self.ptr(), GWL_STYLE, (style | PBS_MARQUEE) as LONG_PTR);
+            }
+
+            user32::SendMessageW(
+                self.ptr(), PBM_SETMARQUEE, marquee as WPARAM, interval_ms as LPARAM
+            );
+
+            if !marquee {
+                user32::SetWindowLongPtrW(self.ptr(), GWL_STYLE, (style & !PBS_MARQUEE) as LONG_PTR);
+            }
+        }
+    }
+
+    pub fn is_marquee(&self) -> bool {
+        unsafe {
+            user32::GetWindowLongPtrW(self.ptr(), GWL_STYLE) as DWORD & PBS_MARQUEE != 0
+        }
+    }
+}
+
+impl AbsWindow for ProgressBar {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+}
+
+impl WindowEvents for ProgressBar {
+    type Data = ProgressData;
+}
+
+pub struct ProgressData {
+    parent: HWND,
+}
+
+impl WindowData for ProgressData {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE
+    }
+
+    fn ex_style(&self) -> DWORD { 0 }
+}
@@ -0,0 +1,174 @@
+use user32;
+
+use abs_window::AbsWindow;
+use ffi::{WindowHandle, OCM_BASE};
+use ffi::class::system as system_classes;
+use ffi::traits::{WindowData, WindowEvents};
+
+use super::Orientation;
+
+use winapi::*;
+
+/// A thumb that can be dragged along a track to select a value (a trackbar).
+pub struct Slider {
+    hand: WindowHandle<Self>,
+}
+
+impl Slider {
+    pub fn new<P: AbsWindow>(parent: &P, orientation: Orientation) -> Slider {
+        let data = SliderData::new(parent.ptr(), orientation);
+        let hand = WindowHandle::create_instance(system_classes::TRACKBAR, data).unwrap();
+
+        Slider {
+            hand: hand
+        }
+    }
+
+    /// Set the minimum and maximum values, both of which can be selected. Defaults to 0 and
+    /// 100.
+    pub fn set_range(&mut self, min: i32, max: i32) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TBM_SETRANGEMIN, FALSE as WPARAM, min as LPARAM);
+            user32::SendMessageW(self.ptr(), TBM_SETRANGEMAX, TRUE as WPARAM, max as LPARAM);
+        }
+    }
+
+    /// The minimum and maximum values, inclusive.
+    pub fn range(&self) -> (i32, i32) {
+        unsafe {
+            let min = user32::SendMessageW(self.ptr(), TBM_GETRANGEMIN, 0, 0) as i32;
+            let max = user32::SendMessageW(self.ptr(), TBM_GETRANGEMAX, 0, 0) as i32;
+            (min, max)
+        }
+    }
+
+    /// Move the thumb. This does not trigger `on_value_changed`.
+    pub fn set_value(&mut self, value: i32) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TBM_SETPOS, TRUE as WPARAM, value as LPARAM);
+            self.hand.data_mut().last_value = Some(value);
+        }
+    }
+
+    pub fn value(&self) -> i32 {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TBM_GETPOS, 0, 0) as i32
+        }
+    }
+
+    /// Draw a tick mark every `freq` values. 0 hides the ticks.
+    pub fn set_tick_frequency(&mut self, freq: u32) {
+        unsafe {
+            let style = user32::GetWindowLongPtrW(self.ptr(), GWL_STYLE) as DWORD;
+            let style = if freq == 0 { style | TBS_NOTICKS } else { style & !TBS_NOTICKS };
+
+            user32::SetWindowLongPtrW(self.ptr(), GWL_STYLE, style as LONG_PTR);
+            user32::SendMessageW(self.ptr(), TBM_SETTICFREQ, freq as WPARAM, 0);
+        }
+    }
+
+    /// Set the amount the value changes by for the arrow and Page Up/Down keys.
+    pub fn set_step(&mut self, line: i32, page: i32) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TBM_SETLINESIZE, 0, line as LPARAM);
+            user32::SendMessageW(self.ptr(), TBM_SETPAGESIZE, 0, page as LPARAM);
+        }
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        unsafe { self.hand.data_mut().orientation }
+    }
+
+    /// Called with the new value whenever the user moves the thumb, including while it's
+    /// being dragged.
+    pub fn on_value_changed<F>(&mut self, on_value_changed: F) -> &mut Self
+    where F: FnMut(&mut Slider, i32) + 'static {
+        unsafe {
+            self.hand.data_mut().on_value_changed = Some(Box::new(on_value_changed));
+        }
+
+        self
+    }
+}
+
+impl AbsWindow for Slider {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+}
+
+impl WindowEvents for Slider {
+    type Data = SliderData;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, wparam: WPARAM, _: LPARAM) -> Option<LRESULT> {
+        if msg != OCM_BASE + WM_HSCROLL && msg != OCM_BASE + WM_VSCROLL {
+            return None;
+        }
+
+        // Sent after every change, with the value already reported.
+        if LOWORD(wparam as DWORD) as WPARAM == TB_ENDTRACK {
+            return Some(0);
+        }
+
+        let mut slider = Slider { hand: hnd.clone() };
+        let value = slider.value();
+
+        // The end of a drag repeats the last position it moved to.
+        let cb = unsafe {
+            let data = hnd.data_mut();
+
+            if data.last_value == Some(value) {
+                return Some(0);
+            }
+
+            data.last_value = Some(value);
+            data.on_value_changed.take()
+        };
+
+        if let Some(mut on_value_changed) = cb {
+            on_value_changed(&mut slider, value);
+
+            unsafe {
+                hnd.data_mut().on_value_changed = Some(on_value_changed);
+            }
+        }
+
+        Some(0)
+    }
+}
+
+pub struct SliderData {
+    parent: HWND,
+    orientation: Orientation,
+    on_value_changed: Option<Box<FnMut(&mut Slider, i32)>>,
+    /// The value last reported or set, to report each one once.
+    last_value: Option<i32>,
+}
+
+impl SliderData {
+    fn new(parent: HWND, orientation: Orientation) -> SliderData {
+        SliderData {
+            parent: parent,
+            orientation: orientation,
+            on_value_changed: None,
+            last_value: None,
+        }
+    }
+}
+
+impl WindowData for SliderData {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        let orientation = match self.orientation {
+            Orientation::Horizontal => TBS_HORZ,
+            Orientation::Vertical => TBS_VERT,
+        };
+
+        WS_CHILD | WS_VISIBLE | WS_TABSTOP | TBS_AUTOTICKS | orientation
+    }
+
+    fn ex_style(&self) -> DWORD { 0 }
+}
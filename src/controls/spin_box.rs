@@ -0,0 +1,233 @@
+use user32;
+
+use abs_window::AbsWindow;
+use ffi::{WindowHandle, OCM_BASE};
+use ffi::class::system as system_classes;
+use ffi::traits::{WindowData, WindowEvents};
+use text::Edit;
+
+use winapi::*;
+
+use std::cmp;
+
+/// An `Edit` box holding an integer, with up/down arrows to change it.
+pub struct SpinBox {
+    edit: Edit,
+    hand: WindowHandle<UpDown>,
+}
+
+impl SpinBox {
+    pub fn new<P: AbsWindow>(parent: &P) -> SpinBox {
+        let mut edit = Edit::new(parent, "0");
+        edit.set_numeric(true);
+
+        let data = UpDownData::new(parent.ptr());
+        let hand = WindowHandle::create_instance(system_classes::UP_DOWN, data).unwrap();
+
+        unsafe {
+            user32::SendMessageW(hand.hwnd(), UDM_SETBUDDY, edit.ptr() as WPARAM, 0);
+        }
+
+        // Typing into the box doesn't notify the arrows, so pass the edit's changes on.
+        let up_down = hand.hwnd();
+
+        edit.on_text_changed(move |edit| unsafe {
+            let wparam = (EN_CHANGE as WPARAM) << 16;
+            user32::SendMessageW(up_down, OCM_BASE + WM_COMMAND, wparam, edit.ptr() as LPARAM);
+        });
+
+        let mut spin_box = SpinBox {
+            edit: edit,
+            hand: hand,
+        };
+
+        spin_box.set_range(0, 100);
+        spin_box
+    }
+
+    /// The text box displaying the value.
+    pub fn edit(&self) -> &Edit {
+        &self.edit
+    }
+
+    /// Set the minimum and maximum values, both of which the spinner can reach. Defaults to
+    /// 0 and 100.
+    pub fn set_range(&mut self, min: i32, max: i32) {
+        unsafe {
+            user32::SendMessageW(self.hand.hwnd(), UDM_SETRANGE32, min as WPARAM, max as LPARAM);
+        }
+    }
+
+    /// The minimum and maximum values, inclusive.
+    pub fn range(&self) -> (i32, i32) {
+        range(self.hand.hwnd())
+    }
+
+    /// Change the value. This does not trigger `on_value_changed`.
+    pub fn set_value(&mut self, value: i32) {
+        unsafe {
+            self.hand.data_mut().last_value = Some(value);
+            user32::SendMessageW(self.hand.hwnd(), UDM_SETPOS32, 0, value as LPARAM);
+        }
+    }
+
+    /// Get the current value, clamped to the range if the user typed something outside it.
+    pub fn value(&self) -> i32 {
+        unsafe {
+            user32::SendMessageW(self.hand.hwnd(), UDM_GETPOS32, 0, 0) as i32
+        }
+    }
+
+    /// Wrap around to the other end of the range instead of stopping at it.
+    pub fn set_wrap(&mut self, wrap: bool) {
+        unsafe {
+            let hwnd = self.hand.hwnd();
+            let style = user32::GetWindowLongPtrW(hwnd, GWL_STYLE) as DWORD;
+            let style = if wrap { style | UDS_WRAP } else { style & !UDS_WRAP };
+
+            user32::SetWindowLongPtrW(hwnd, GWL_STYLE, style as LONG_PTR);
+        }
+    }
+
+    /// Called with the new value whenever the user clicks the arrows, presses the arrow keys
+    /// or types a number.
+    pub fn on_value_changed<F>(&mut self, on_value_changed: F) -> &mut Self
+    where F: FnMut(i32) + 'static {
+        unsafe {
+            self.hand.data_mut().on_value_changed = Some(Box::new(on_value_changed));
+        }
+
+        self
+    }
+}
+
+impl AbsWindow for SpinBox {
+    fn ptr(&self) -> HWND {
+        self.edit.ptr()
+    }
+}
+
+/// The arrow half of a `SpinBox`.
+pub struct UpDown;
+
+impl WindowEvents for UpDown {
+    type Data = UpDownData;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let hwnd = hnd.hwnd();
+
+        let value = match msg {
+            // Sent before the arrows change the value, with the amount they'll change it by.
+            _ if msg == OCM_BASE + WM_NOTIFY => unsafe {
+                let nmud = &*(lparam as *const NMUPDOWN);
+
+                if nmud.hdr.code != UDN_DELTAPOS {
+                    return None;
+                }
+
+                let style = user32::GetWindowLongPtrW(hwnd, GWL_STYLE) as DWORD;
+                let (min, max) = range(hwnd);
+
+                step(nmud.iPos, nmud.iDelta, min, max, style & UDS_WRAP != 0)
+            },
+            _ if msg == OCM_BASE + WM_VSCROLL => unsafe {
+                user32::SendMessageW(hwnd, UDM_GETPOS32, 0, 0) as i32
+            },
+            // Forwarded from the edit box by `SpinBox::new()`.
+            _ if msg == OCM_BASE + WM_COMMAND && HIWORD(wparam as DWORD) == EN_CHANGE => unsafe {
+                let mut error: BOOL = FALSE;
+                let value = user32::SendMessageW(
+                    hwnd, UDM_GETPOS32, 0, &mut error as *mut BOOL as LPARAM
+                );
+
+                // Not a number in the range, e.g. empty or only a minus sign so far.
+                if error != FALSE {
+                    return Some(0);
+                }
+
+                value as i32
+            },
+            _ => return None,
+        };
+
+        // The same change usually arrives more than once, e.g. as `UDN_DELTAPOS`, then
+        // `EN_CHANGE` as the new value is written into the box, then `WM_VSCROLL`.
+        let cb = unsafe {
+            let data = hnd.data_mut();
+
+            if data.last_value == Some(value) {
+                return Some(0);
+            }
+
+            data.last_value = Some(value);
+            data.on_value_changed.take()
+        };
+
+        if let Some(mut on_value_changed) = cb {
+            on_value_changed(value);
+
+            unsafe {
+                hnd.data_mut().on_value_changed = Some(on_value_changed);
+            }
+        }
+
+        Some(0)
+    }
+}
+
+/// The inclusive range of the up-down control `hwnd`.
+fn range(hwnd: HWND) -> (i32, i32) {
+    let (mut min, mut max): (c_int, c_int) = (0, 0);
+
+    unsafe {
+        user32::SendMessageW(
+            hwnd, UDM_GETRANGE32,
+            &mut min as *mut c_int as WPARAM, &mut max as *mut c_int as LPARAM
+        );
+    }
+
+    (min, max)
+}
+
+/// The value the arrows will move to from `pos`, the way the up-down control does it:
+/// stopping at the ends of the range, or jumping to the other end if `wrap` is set.
+fn step(pos: i32, delta: i32, min: i32, max: i32, wrap: bool) -> i32 {
+    // The minimum can be the larger of the two, which reverses the arrows.
+    let (low, high) = (cmp::min(min, max), cmp::max(min, max));
+    let value = pos.saturating_add(delta);
+
+    match (value < low, value > high) {
+        (true, _) => if wrap { high } else { low },
+        (_, true) => if wrap { low } else { high },
+        _ => value,
+    }
+}
+
+pub struct UpDownData {
+    parent: HWND,
+    on_value_changed: Option<Box<FnMut(i32)>>,
+    /// The value last reported or set, to report each one once.
+    last_value: Option<i32>,
+}
+
+impl UpDownData {
+    fn new(parent: HWND) -> UpDownData {
+        UpDownData {
+            parent: parent,
+            on_value_changed: None,
+            last_value: None,
+        }
+    }
+}
+
+impl WindowData for UpDownData {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE | UDS_SETBUDDYINT | UDS_ALIGNRIGHT | UDS_ARROWKEYS | UDS_NOTHOUSANDS
+    }
+
+    fn ex_style(&self) -> DWORD { 0 }
+}
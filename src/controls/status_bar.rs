@@ -0,0 +1,202 @@
+#[cfg(windows)] use user32;
+
+#[cfg(windows)] use abs_window::AbsWindow;
+use dpi::Dpi;
+#[cfg(windows)] use ffi::WindowHandle;
+#[cfg(windows)] use ffi::class::system as system_classes;
+#[cfg(windows)] use ffi::traits::{WindowData, WindowEvents};
+#[cfg(windows)] use winstr::WinString;
+
+#[cfg(windows)] use winapi::*;
+
+use std::cmp;
+#[cfg(windows)] use std::mem;
+
+/// A bar along the bottom of a window, divided into parts that each show some text.
+///
+/// Attach one with `WindowBuilder::status_bar()` so it follows the window's size.
+#[cfg(windows)]
+pub struct StatusBar {
+    hand: WindowHandle<Self>,
+}
+
+#[cfg(windows)]
+impl StatusBar {
+    pub fn new<P: AbsWindow>(parent: &P) -> StatusBar {
+        let data = StatusBarData::new(parent.ptr());
+        let hand = WindowHandle::create_instance(system_classes::STATUS_BAR, data).unwrap();
+
+        StatusBar {
+            hand: hand
+        }
+    }
+
+    /// Divide the bar into parts with the given widths, in logical units.
+    ///
+    /// A width of -1 makes that part take up any remaining space.
+    pub fn set_parts(&mut self, widths: &[i32]) {
+        unsafe {
+            self.hand.data_mut().widths = widths.to_vec();
+        }
+
+        self.update_parts();
+    }
+
+    pub fn part_count(&self) -> usize {
+        unsafe { self.hand.data_mut().widths.len() }
+    }
+
+    pub fn set_text<T: AsRef<str>>(&mut self, part: usize, text: T) {
+        let text = WinString::from_str(text);
+
+        unsafe {
+            user32::SendMessageW(self.ptr(), SB_SETTEXTW, part as WPARAM, text.as_ptr() as LPARAM);
+        }
+    }
+
+    pub fn text(&self, part: usize) -> String {
+        let mut text = WinString::empty();
+
+        unsafe {
+            let len = user32::SendMessageW(self.ptr(), SB_GETTEXTLENGTHW, part as WPARAM, 0);
+            let buf = text.as_mut_ptr(LOWORD(len as DWORD) as usize);
+
+            user32::SendMessageW(self.ptr(), SB_GETTEXTW, part as WPARAM, buf as LPARAM);
+        }
+
+        text.to_string()
+    }
+
+    /// The height of the bar, in pixels.
+    pub fn height(&self) -> i32 {
+        unsafe {
+            let mut rect: RECT = mem::zeroed();
+            user32::GetWindowRect(self.ptr(), &mut rect);
+            rect.bottom - rect.top
+        }
+    }
+
+    /// Fit the bar to the bottom of its parent. Called when the parent is resized.
+    #[doc(hidden)]
+    pub fn parent_resized(&mut self) {
+        unsafe {
+            // The status bar positions itself when it receives `WM_SIZE`.
+            user32::SendMessageW(self.ptr(), WM_SIZE, 0, 0);
+        }
+
+        self.update_parts();
+    }
+
+    fn update_parts(&mut self) {
+        unsafe {
+            let mut rect: RECT = mem::zeroed();
+            user32::GetClientRect(self.ptr(), &mut rect);
+
+            let widths = &self.hand.data_mut().widths;
+            let edges = part_edges(widths, rect.right - rect.left, Dpi::of_window(self));
+
+            user32::SendMessageW(
+                self.ptr(), SB_SETPARTS, edges.len() as WPARAM, edges.as_ptr() as LPARAM
+            );
+        }
+    }
+}
+
+/// Convert part widths in logical units into the right edge of each part in pixels, as
+/// `SB_SETPARTS` expects. `total` is the width of the bar in pixels.
+///
+/// Parts with a width of -1 share the space left over by the others.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn part_edges(widths: &[i32], total: i32, dpi: Dpi) -> Vec<i32> {
+    let widths: Vec<i32> = widths.iter()
+        .map(|&width| if width < 0 { width } else { dpi.scale(width) })
+        .collect();
+
+    let fixed = widths.iter().filter(|&&width| width >= 0).fold(0, |sum, &width| sum + width);
+    let stretch = widths.iter().filter(|&&width| width < 0).count() as i32;
+
+    let stretch_width = if stretch > 0 {
+        cmp::max(total - fixed, 0) / stretch
+    } else {
+        0
+    };
+
+    let mut right = 0;
+
+    widths.iter().map(|&width| {
+        right += if width < 0 { stretch_width } else { width };
+        right
+    }).collect()
+}
+
+#[cfg(windows)]
+impl AbsWindow for StatusBar {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+}
+
+#[cfg(windows)]
+impl WindowEvents for StatusBar {
+    type Data = StatusBarData;
+}
+
+#[cfg(windows)]
+pub struct StatusBarData {
+    parent: HWND,
+    widths: Vec<i32>,
+}
+
+#[cfg(windows)]
+impl StatusBarData {
+    fn new(parent: HWND) -> StatusBarData {
+        StatusBarData {
+            parent: parent,
+            widths: vec![-1],
+        }
+    }
+}
+
+#[cfg(windows)]
+impl WindowData for StatusBarData {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE | SBARS_SIZEGRIP
+    }
+
+    fn ex_style(&self) -> DWORD { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_parts() {
+        assert_eq!(part_edges(&[100, 50, 80], 500, Dpi(96)), vec![100, 150, 230]);
+        assert_eq!(part_edges(&[], 500, Dpi(96)), vec![]);
+    }
+
+    #[test]
+    fn trailing_part_fills_the_rest() {
+        assert_eq!(part_edges(&[100, 50, -1], 500, Dpi(96)), vec![100, 150, 500]);
+        assert_eq!(part_edges(&[-1], 320, Dpi(96)), vec![320]);
+    }
+
+    #[test]
+    fn stretched_parts_share_the_rest() {
+        assert_eq!(part_edges(&[-1, 100, -1], 500, Dpi(96)), vec![200, 300, 500]);
+
+        // Nothing left over for them.
+        assert_eq!(part_edges(&[300, -1, 300], 500, Dpi(96)), vec![300, 300, 600]);
+    }
+
+    #[test]
+    fn scale_fixed_parts() {
+        assert_eq!(part_edges(&[100, 50, -1], 1000, Dpi(192)), vec![200, 300, 1000]);
+        assert_eq!(part_edges(&[100, -1], 400, Dpi(144)), vec![150, 400]);
+    }
+}
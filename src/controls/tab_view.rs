@@ -0,0 +1,267 @@
+use user32;
+
+use abs_window::AbsWindow;
+use ffi::{WindowHandle, OCM_BASE};
+use ffi::class::system as system_classes;
+use ffi::msg::{self, Notify};
+use ffi::traits::{WindowData, WindowEvents};
+use window::Window;
+use winstr::WinString;
+
+use winapi::*;
+
+use std::mem;
+
+/// A set of tabs (`SysTabControl32`), each with its own page to put controls in.
+///
+/// Only the page of the selected tab is shown, and all pages are resized to fill the area
+/// below the tabs.
+pub struct TabView {
+    hand: WindowHandle<Self>,
+}
+
+impl TabView {
+    pub fn new<P: AbsWindow>(parent: &P) -> TabView {
+        let data = TabViewData::new(parent.ptr());
+        let hand = WindowHandle::create_instance(system_classes::TAB_CONTROL, data).unwrap();
+
+        TabView {
+            hand: hand
+        }
+    }
+
+    /// Append a tab, returning its page.
+    pub fn add_tab<T: AsRef<str>>(&mut self, title: T) -> Window {
+        let index = self.tab_count();
+        self.insert_tab(index, title)
+    }
+
+    /// Insert a tab at `index`, returning its page.
+    pub fn insert_tab<T: AsRef<str>>(&mut self, index: usize, title: T) -> Window {
+        let page = Window::new_child(self);
+        let handle = page.clone();
+
+        // The tab view keeps the owning handle, so the page lives as long as the tab.
+        self.insert_page(index, title.as_ref(), page);
+        handle
+    }
+
+    /// Remove the tab at `index` and destroy its page.
+    pub fn remove_tab(&mut self, index: usize) {
+        let selected = self.selected();
+
+        unsafe {
+            user32::SendMessageW(self.ptr(), TCM_DELETEITEM, index as WPARAM, 0);
+            self.hand.data_mut().pages.remove(index);
+        }
+
+        let count = self.tab_count();
+
+        // The tab control doesn't select another tab by itself
+        if selected == Some(index) && count > 0 {
+            self.select(if index < count { index } else { count - 1 });
+        }
+    }
+
+    /// Move the tab at `from` so that it is at `to`.
+    pub fn move_tab(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+
+        let title = self.title(from);
+        let was_selected = self.selected() == Some(from);
+
+        let page = unsafe {
+            user32::SendMessageW(self.ptr(), TCM_DELETEITEM, from as WPARAM, 0);
+            self.hand.data_mut().pages.remove(from)
+        };
+
+        self.insert_page(to, &title, page);
+
+        if was_selected {
+            self.select(to);
+        }
+    }
+
+    pub fn tab_count(&self) -> usize {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TCM_GETITEMCOUNT, 0, 0) as usize
+        }
+    }
+
+    /// Get the page of the tab at `index`.
+    pub fn page(&self, index: usize) -> Option<Window> {
+        unsafe { self.hand.data_mut().pages.get(index).cloned() }
+    }
+
+    pub fn title(&self, index: usize) -> String {
+        let mut buf = [0u16; 256];
+
+        unsafe {
+            let mut item: TCITEMW = mem::zeroed();
+            item.mask = TCIF_TEXT;
+            item.pszText = buf.as_mut_ptr();
+            item.cchTextMax = buf.len() as c_int;
+
+            user32::SendMessageW(
+                self.ptr(), TCM_GETITEMW, index as WPARAM, &mut item as *mut _ as LPARAM
+            );
+
+            msg::wide_to_string(item.pszText).unwrap_or_else(String::new)
+        }
+    }
+
+    pub fn set_title<T: AsRef<str>>(&mut self, index: usize, title: T) {
+        let title = WinString::from_str(title);
+
+        unsafe {
+            let mut item: TCITEMW = mem::zeroed();
+            item.mask = TCIF_TEXT;
+            item.pszText = title.as_ptr() as LPWSTR;
+
+            user32::SendMessageW(
+                self.ptr(), TCM_SETITEMW, index as WPARAM, &item as *const _ as LPARAM
+            );
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        let selected = unsafe {
+            user32::SendMessageW(self.ptr(), TCM_GETCURSEL, 0, 0)
+        };
+
+        msg::index(selected as c_int)
+    }
+
+    /// Select the tab at `index` and show its page. This does not trigger `on_tab_changed`.
+    pub fn select(&mut self, index: usize) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TCM_SETCURSEL, index as WPARAM, 0);
+        }
+
+        self.update_pages();
+    }
+
+    /// Called with the index of the newly selected tab when the user switches tabs.
+    pub fn on_tab_changed<F>(&mut self, on_tab_changed: F) -> &mut Self
+    where F: FnMut(&mut TabView, usize) + 'static {
+        unsafe {
+            self.hand.data_mut().on_tab_changed = Some(Box::new(on_tab_changed));
+        }
+
+        self
+    }
+
+    fn insert_page(&mut self, index: usize, title: &str, page: Window) {
+        let title = WinString::from_str(title);
+
+        unsafe {
+            let mut item: TCITEMW = mem::zeroed();
+            item.mask = TCIF_TEXT;
+            item.pszText = title.as_ptr() as LPWSTR;
+
+            user32::SendMessageW(
+                self.ptr(), TCM_INSERTITEMW, index as WPARAM, &item as *const _ as LPARAM
+            );
+
+            self.hand.data_mut().pages.insert(index, page);
+        }
+
+        if self.selected().is_none() {
+            self.select(index);
+        } else {
+            self.update_pages();
+        }
+    }
+
+    /// Show only the selected page, and fit all pages to the display area.
+    fn update_pages(&mut self) {
+        let selected = self.selected();
+
+        unsafe {
+            let mut rect = mem::zeroed();
+            user32::GetClientRect(self.ptr(), &mut rect);
+            user32::SendMessageW(self.ptr(), TCM_ADJUSTRECT, FALSE as WPARAM, &mut rect as *mut RECT as LPARAM);
+
+            for (i, page) in self.hand.data_mut().pages.iter().enumerate() {
+                let page = page.ptr();
+
+                user32::MoveWindow(
+                    page, rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top, TRUE
+                );
+
+                user32::ShowWindow(page, if selected == Some(i) { SW_SHOW } else { SW_HIDE });
+            }
+        }
+    }
+}
+
+impl AbsWindow for TabView {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+}
+
+impl WindowEvents for TabView {
+    type Data = TabViewData;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, _: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let mut tabs = TabView { hand: hnd.clone() };
+
+        match msg {
+            WM_SIZE => {
+                tabs.update_pages();
+                None
+            },
+            _ if msg == OCM_BASE + WM_NOTIFY => match unsafe { Notify::from_lparam(lparam) } {
+                Notify::TabSelChange => {
+                    tabs.update_pages();
+
+                    let data = unsafe { hnd.data_mut() };
+                    let selected = tabs.selected();
+
+                    if let (Some(selected), Some(mut on_tab_changed)) =
+                        (selected, data.on_tab_changed.take()) {
+                        on_tab_changed(&mut tabs, selected);
+                        data.on_tab_changed = Some(on_tab_changed);
+                    }
+
+                    Some(0)
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+pub struct TabViewData {
+    parent: HWND,
+    pages: Vec<Window>,
+    on_tab_changed: Option<Box<FnMut(&mut TabView, usize)>>,
+}
+
+impl TabViewData {
+    fn new(parent: HWND) -> TabViewData {
+        TabViewData {
+            parent: parent,
+            pages: Vec::new(),
+            on_tab_changed: None,
+        }
+    }
+}
+
+impl WindowData for TabViewData {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE | WS_TABSTOP | WS_CLIPCHILDREN | WS_CLIPSIBLINGS | TCS_TABS
+    }
+
+    fn ex_style(&self) -> DWORD {
+        WS_EX_CONTROLPARENT
+    }
+}
@@ -0,0 +1,268 @@
+use user32;
+
+use abs_window::AbsWindow;
+use command::CommandId;
+use ffi::WindowHandle;
+use ffi::class::system as system_classes;
+use ffi::traits::{WindowData, WindowEvents};
+use winstr::WinString;
+
+use super::ImageList;
+
+use winapi::*;
+
+use std::{mem, ptr};
+
+/// A row of buttons along the top of a window.
+///
+/// Clicking a button delivers its `CommandId` to `Window::on_command()`. Attach one with
+/// `WindowBuilder::tool_bar()` so it follows the window's size.
+pub struct ToolBar {
+    hand: WindowHandle<Self>,
+}
+
+impl ToolBar {
+    pub fn new<P: AbsWindow>(parent: &P) -> ToolBar {
+        let data = ToolBarData::new(parent.ptr());
+        let hand = WindowHandle::create_instance(system_classes::TOOL_BAR, data).unwrap();
+
+        unsafe {
+            let hwnd = hand.hwnd();
+
+            user32::SendMessageW(hwnd, TB_BUTTONSTRUCTSIZE, mem::size_of::<TBBUTTON>() as WPARAM, 0);
+            // Buttons without `BTNS_SHOWTEXT` display their text as a tooltip instead.
+            user32::SendMessageW(hwnd, TB_SETEXTENDEDSTYLE, 0, TBSTYLE_EX_MIXEDBUTTONS as LPARAM);
+        }
+
+        ToolBar {
+            hand: hand
+        }
+    }
+
+    /// Use the images provided by Windows (`IDB_STD_SMALL_COLOR`), indexed by the `STD_*`
+    /// constants in `winapi`.
+    pub fn use_standard_images(&mut self) {
+        unsafe {
+            user32::SendMessageW(
+                self.ptr(), TB_LOADIMAGES, IDB_STD_SMALL_COLOR, HINST_COMMCTRL as LPARAM
+            );
+        }
+    }
+
+    /// Use the images in `images` for the buttons.
+    pub fn set_images(&mut self, images: ImageList) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TB_SETIMAGELIST, 0, images.himl() as LPARAM);
+            self.hand.data_mut().images = Some(images);
+        }
+    }
+
+    pub fn add_button(&mut self, button: ToolButton) {
+        let text = button.text.as_ref().map(WinString::from_str);
+
+        let mut style = BTNS_AUTOSIZE;
+
+        if button.toggle {
+            style |= BTNS_CHECK;
+        }
+
+        if button.show_text {
+            style |= BTNS_SHOWTEXT;
+        }
+
+        let mut state = 0;
+
+        if button.enabled {
+            state |= TBSTATE_ENABLED;
+        }
+
+        if button.checked {
+            state |= TBSTATE_CHECKED;
+        }
+
+        let mut tb_button: TBBUTTON = unsafe { mem::zeroed() };
+        tb_button.iBitmap = button.image.map_or(I_IMAGENONE, |image| image as c_int);
+        tb_button.idCommand = (button.command.0) as c_int;
+        tb_button.fsState = state;
+        tb_button.fsStyle = style as BYTE;
+        tb_button.iString = text.as_ref().map_or(ptr::null(), WinString::as_ptr) as INT_PTR;
+
+        self.add_raw(&tb_button);
+    }
+
+    pub fn add_separator(&mut self) {
+        let mut tb_button: TBBUTTON = unsafe { mem::zeroed() };
+        tb_button.fsStyle = BTNS_SEP as BYTE;
+
+        self.add_raw(&tb_button);
+    }
+
+    /// Remove the button for `command`.
+    pub fn remove_button(&mut self, command: CommandId) {
+        if let Some(index) = self.index_of(command) {
+            unsafe {
+                user32::SendMessageW(self.ptr(), TB_DELETEBUTTON, index as WPARAM, 0);
+            }
+
+            self.autosize();
+        }
+    }
+
+    pub fn button_count(&self) -> usize {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TB_BUTTONCOUNT, 0, 0) as usize
+        }
+    }
+
+    pub fn set_enabled(&mut self, command: CommandId, enabled: bool) {
+        unsafe {
+            user32::SendMessageW(
+                self.ptr(), TB_ENABLEBUTTON, command.0 as WPARAM, MAKELONG(enabled as WORD, 0) as LPARAM
+            );
+        }
+    }
+
+    /// Set the state of a toggle button.
+    pub fn set_checked(&mut self, command: CommandId, checked: bool) {
+        unsafe {
+            user32::SendMessageW(
+                self.ptr(), TB_CHECKBUTTON, command.0 as WPARAM, MAKELONG(checked as WORD, 0) as LPARAM
+            );
+        }
+    }
+
+    pub fn is_checked(&self, command: CommandId) -> bool {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TB_ISBUTTONCHECKED, command.0 as WPARAM, 0) != 0
+        }
+    }
+
+    /// The height of the bar, in pixels.
+    pub fn height(&self) -> i32 {
+        unsafe {
+            let mut rect: RECT = mem::zeroed();
+            user32::GetWindowRect(self.ptr(), &mut rect);
+            rect.bottom - rect.top
+        }
+    }
+
+    /// Fit the bar to the top of its parent. Called when the parent is resized.
+    #[doc(hidden)]
+    pub fn parent_resized(&mut self) {
+        self.autosize();
+    }
+
+    fn add_raw(&mut self, tb_button: &TBBUTTON) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TB_ADDBUTTONSW, 1, tb_button as *const _ as LPARAM);
+        }
+
+        self.autosize();
+    }
+
+    fn autosize(&mut self) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TB_AUTOSIZE, 0, 0);
+        }
+    }
+
+    fn index_of(&self, command: CommandId) -> Option<usize> {
+        let index = unsafe {
+            user32::SendMessageW(self.ptr(), TB_COMMANDTOINDEX, command.0 as WPARAM, 0)
+        };
+
+        if index < 0 { None } else { Some(index as usize) }
+    }
+}
+
+impl AbsWindow for ToolBar {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+}
+
+impl WindowEvents for ToolBar {
+    type Data = ToolBarData;
+}
+
+pub struct ToolBarData {
+    parent: HWND,
+    images: Option<ImageList>,
+}
+
+impl ToolBarData {
+    fn new(parent: HWND) -> ToolBarData {
+        ToolBarData {
+            parent: parent,
+            images: None,
+        }
+    }
+}
+
+impl WindowData for ToolBarData {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE | TBSTYLE_FLAT | TBSTYLE_TOOLTIPS | CCS_TOP
+    }
+
+    fn ex_style(&self) -> DWORD { 0 }
+}
+
+/// A button to add to a `ToolBar`.
+#[derive(Clone, Debug)]
+pub struct ToolButton {
+    command: CommandId,
+    image: Option<usize>,
+    text: Option<String>,
+    show_text: bool,
+    toggle: bool,
+    checked: bool,
+    enabled: bool,
+}
+
+impl ToolButton {
+    pub fn new(command: CommandId) -> ToolButton {
+        ToolButton {
+            command: command,
+            image: None,
+            text: None,
+            show_text: false,
+            toggle: false,
+            checked: false,
+            enabled: true,
+        }
+    }
+
+    /// Show the image at `index` in the toolbar's image list.
+    pub fn image(mut self, index: usize) -> Self {
+        self.image = Some(index);
+        self
+    }
+
+    /// Set the text of the button, shown as a tooltip.
+    pub fn text<T: Into<String>>(mut self, text: T) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Show the text next to the image instead of as a tooltip.
+    pub fn show_text(mut self) -> Self {
+        self.show_text = true;
+        self
+    }
+
+    /// Make the button stay pressed until it is clicked again.
+    pub fn toggle(mut self, checked: bool) -> Self {
+        self.toggle = true;
+        self.checked = checked;
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
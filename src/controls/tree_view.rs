@@ -0,0 +1,589 @@
+#[cfg(windows)] use user32;
+
+#[cfg(windows)] use abs_window::AbsWindow;
+#[cfg(windows)] use ffi::{WindowHandle, OCM_BASE};
+#[cfg(windows)] use ffi::class::system as system_classes;
+#[cfg(windows)] use ffi::msg::Notify;
+#[cfg(windows)] use ffi::traits::{WindowData, WindowEvents};
+#[cfg(windows)] use winstr::WinString;
+
+#[cfg(windows)] use winapi::*;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+#[cfg(windows)] use std::{mem, ptr};
+
+/// The hierarchy displayed by a `TreeView`.
+///
+/// Nodes are identified by keys chosen by the model, which must be unique across the whole
+/// tree: the `TreeView` panics if a loaded key is returned again. Children are only requested
+/// when their parent is first expanded.
+pub trait TreeModel {
+    type Key: Clone + Eq + Hash;
+
+    /// The top-level nodes.
+    fn roots(&mut self) -> Vec<Self::Key>;
+
+    /// The children of `parent`.
+    fn children(&mut self, parent: &Self::Key) -> Vec<Self::Key>;
+
+    /// Whether `key` has any children, so an expand button can be shown before they're loaded.
+    fn has_children(&mut self, key: &Self::Key) -> bool;
+
+    /// The text displayed for `key`.
+    fn label(&mut self, key: &Self::Key) -> String;
+}
+
+/// A hierarchical list of nodes (`SysTreeView32`), populated lazily from a `TreeModel`.
+#[cfg(windows)]
+pub struct TreeView<M: TreeModel> {
+    hand: WindowHandle<Self>,
+}
+
+#[cfg(windows)]
+impl<M: TreeModel> TreeView<M> {
+    pub fn new<P: AbsWindow>(parent: &P, model: M) -> TreeView<M> {
+        let data = TreeViewData::new(parent.ptr(), model);
+        let hand = WindowHandle::create_instance(system_classes::TREE_VIEW, data).unwrap();
+
+        let mut tree = TreeView {
+            hand: hand
+        };
+
+        tree.reload();
+        tree
+    }
+
+    pub fn model(&self) -> &M {
+        unsafe { &self.hand.data_mut().model }
+    }
+
+    /// Get the model mutably. Call `reload()` or `refresh()` afterwards to display any changes.
+    pub fn model_mut(&mut self) -> &mut M {
+        unsafe { &mut self.hand.data_mut().model }
+    }
+
+    /// Discard all nodes and reload the top level from the model.
+    pub fn reload(&mut self) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), TVM_DELETEITEM, 0, TVI_ROOT as LPARAM);
+
+            self.hand.data_mut().nodes.clear();
+
+            self.populate(TVI_ROOT);
+        }
+    }
+
+    /// Reload the label and children of `key`. Returns `false` if it isn't loaded.
+    pub fn refresh(&mut self, key: &M::Key) -> bool {
+        let item = unwrap_or_ret!(self.node(key), false);
+
+        unsafe {
+            let mut child = self.next_item(item, TVGN_CHILD);
+
+            while !child.is_null() {
+                let next = self.next_item(child, TVGN_NEXT);
+                self.delete_node(child);
+                child = next;
+            }
+
+            let data = self.hand.data_mut();
+            let label = WinString::from_str(data.model.label(key));
+
+            let mut tv_item: TVITEMW = mem::zeroed();
+            tv_item.mask = TVIF_TEXT | TVIF_CHILDREN;
+            tv_item.hItem = item;
+            tv_item.pszText = label.as_ptr() as LPWSTR;
+            tv_item.cChildren = data.model.has_children(key) as c_int;
+
+            user32::SendMessageW(self.ptr(), TVM_SETITEMW, 0, &tv_item as *const _ as LPARAM);
+
+            // Collapse and clear the "expanded once" flag so the children get reloaded
+            user32::SendMessageW(
+                self.ptr(), TVM_EXPAND, TVE_COLLAPSE | TVE_COLLAPSERESET, item as LPARAM
+            );
+        }
+
+        true
+    }
+
+    /// Whether the node for `key` has been loaded.
+    pub fn is_loaded(&self, key: &M::Key) -> bool {
+        self.node(key).is_some()
+    }
+
+    pub fn selected(&self) -> Option<M::Key> {
+        let item = unsafe { self.next_item(ptr::null_mut(), TVGN_CARET) };
+        self.key(item)
+    }
+
+    /// Select the node for `key`, scrolling it into view. Returns `false` if it isn't loaded.
+    pub fn select(&mut self, key: &M::Key) -> bool {
+        let item = unwrap_or_ret!(self.node(key), false);
+
+        unsafe {
+            user32::SendMessageW(self.ptr(), TVM_SELECTITEM, TVGN_CARET, item as LPARAM);
+            user32::SendMessageW(self.ptr(), TVM_ENSUREVISIBLE, 0, item as LPARAM);
+        }
+
+        true
+    }
+
+    /// Expand or collapse the node for `key`. Returns `false` if it isn't loaded.
+    pub fn set_expanded(&mut self, key: &M::Key, expanded: bool) -> bool {
+        let item = unwrap_or_ret!(self.node(key), false);
+        let action = if expanded { TVE_EXPAND } else { TVE_COLLAPSE };
+
+        unsafe {
+            user32::SendMessageW(self.ptr(), TVM_EXPAND, action, item as LPARAM);
+        }
+
+        true
+    }
+
+    /// Show a checkbox next to each node.
+    pub fn set_checkboxes(&mut self, checkboxes: bool) {
+        // `TVS_CHECKBOXES` has to be set after the control is created.
+        self.set_style(TVS_CHECKBOXES, checkboxes);
+    }
+
+    pub fn is_checked(&self, key: &M::Key) -> bool {
+        let item = unwrap_or_ret!(self.node(key), false);
+
+        unsafe {
+            let mut tv_item: TVITEMW = mem::zeroed();
+            tv_item.mask = TVIF_STATE;
+            tv_item.hItem = item;
+            tv_item.stateMask = TVIS_STATEIMAGEMASK;
+
+            user32::SendMessageW(self.ptr(), TVM_GETITEMW, 0, &mut tv_item as *mut _ as LPARAM);
+
+            tv_item.state & TVIS_STATEIMAGEMASK == CHECKED
+        }
+    }
+
+    /// Check or uncheck the node for `key`. Returns `false` if it isn't loaded.
+    pub fn set_checked(&mut self, key: &M::Key, checked: bool) -> bool {
+        let item = unwrap_or_ret!(self.node(key), false);
+
+        unsafe {
+            let mut tv_item: TVITEMW = mem::zeroed();
+            tv_item.mask = TVIF_STATE;
+            tv_item.hItem = item;
+            tv_item.stateMask = TVIS_STATEIMAGEMASK;
+            tv_item.state = if checked { CHECKED } else { UNCHECKED };
+
+            user32::SendMessageW(self.ptr(), TVM_SETITEMW, 0, &tv_item as *const _ as LPARAM);
+        }
+
+        true
+    }
+
+    /// Allow the user to edit the labels of nodes by clicking on them.
+    pub fn set_label_editing(&mut self, editable: bool) {
+        self.set_style(TVS_EDITLABELS, editable);
+    }
+
+    /// Start editing the label of the node for `key`. Returns `false` if it isn't loaded.
+    pub fn edit_label(&mut self, key: &M::Key) -> bool {
+        let item = unwrap_or_ret!(self.node(key), false);
+
+        unsafe {
+            user32::SetFocus(self.ptr());
+            user32::SendMessageW(self.ptr(), TVM_EDITLABELW, 0, item as LPARAM);
+        }
+
+        true
+    }
+
+    pub fn on_selection_changed<F>(&mut self, on_selection_changed: F) -> &mut Self
+    where F: FnMut(&mut TreeView<M>, Option<M::Key>) + 'static {
+        unsafe {
+            self.hand.data_mut().on_selection_changed = Some(Box::new(on_selection_changed));
+        }
+
+        self
+    }
+
+    /// Called when a node's checkbox is toggled, by the user or by `set_checked()`.
+    pub fn on_check_changed<F>(&mut self, on_check_changed: F) -> &mut Self
+    where F: FnMut(&mut TreeView<M>, M::Key, bool) + 'static {
+        unsafe {
+            self.hand.data_mut().on_check_changed = Some(Box::new(on_check_changed));
+        }
+
+        self
+    }
+
+    /// Called with the new label when the user finishes editing one.
+    ///
+    /// Return `true` to accept the new label. The model should be updated to match.
+    pub fn on_label_edited<F>(&mut self, on_label_edited: F) -> &mut Self
+    where F: FnMut(&mut TreeView<M>, M::Key, String) -> bool + 'static {
+        unsafe {
+            self.hand.data_mut().on_label_edited = Some(Box::new(on_label_edited));
+        }
+
+        self
+    }
+
+    /// Called with the dragged node and the node it was dropped on when the user drags a
+    /// node with the mouse.
+    ///
+    /// The tree itself is not changed; update the model and call `refresh()` on the affected
+    /// parents to move the node.
+    pub fn on_drop<F>(&mut self, on_drop: F) -> &mut Self
+    where F: FnMut(&mut TreeView<M>, M::Key, M::Key) + 'static {
+        unsafe {
+            self.hand.data_mut().on_drop = Some(Box::new(on_drop));
+        }
+
+        self
+    }
+
+    fn node(&self, key: &M::Key) -> Option<HTREEITEM> {
+        unsafe { self.hand.data_mut().nodes.item(key) }
+    }
+
+    fn key(&self, item: HTREEITEM) -> Option<M::Key> {
+        unsafe { self.hand.data_mut().nodes.key(item) }
+    }
+
+    fn set_style(&mut self, flag: DWORD, set: bool) {
+        unsafe {
+            let style = user32::GetWindowLongPtrW(self.ptr(), GWL_STYLE) as DWORD;
+            let style = if set { style | flag } else { style & !flag };
+
+            user32::SetWindowLongPtrW(self.ptr(), GWL_STYLE, style as LONG_PTR);
+        }
+    }
+
+    unsafe fn next_item(&self, item: HTREEITEM, flag: WPARAM) -> HTREEITEM {
+        user32::SendMessageW(self.ptr(), TVM_GETNEXTITEM, flag, item as LPARAM) as HTREEITEM
+    }
+
+    /// Insert the children of `parent` (or the roots, for `TVI_ROOT`) from the model.
+    unsafe fn populate(&mut self, parent: HTREEITEM) {
+        let data = self.hand.data_mut();
+
+        let keys = if parent == TVI_ROOT {
+            data.model.roots()
+        } else {
+            let key = unwrap_or_ret!(data.nodes.key(parent), ());
+            data.model.children(&key)
+        };
+
+        for key in keys {
+            let label = WinString::from_str(data.model.label(&key));
+
+            let mut insert: TVINSERTSTRUCTW = mem::zeroed();
+            insert.hParent = parent;
+            insert.hInsertAfter = TVI_LAST;
+
+            {
+                let item = insert.item_mut();
+                item.mask = TVIF_TEXT | TVIF_CHILDREN;
+                item.pszText = label.as_ptr() as LPWSTR;
+                item.cChildren = data.model.has_children(&key) as c_int;
+            }
+
+            let item = user32::SendMessageW(
+                self.ptr(), TVM_INSERTITEMW, 0, &insert as *const _ as LPARAM
+            ) as HTREEITEM;
+
+            data.nodes.insert(item, key);
+        }
+    }
+
+    /// Delete `item` and its descendants, and forget their keys.
+    unsafe fn delete_node(&mut self, item: HTREEITEM) {
+        let mut child = self.next_item(item, TVGN_CHILD);
+
+        while !child.is_null() {
+            let next = self.next_item(child, TVGN_NEXT);
+            self.delete_node(child);
+            child = next;
+        }
+
+        self.hand.data_mut().nodes.remove(item);
+
+        user32::SendMessageW(self.ptr(), TVM_DELETEITEM, 0, item as LPARAM);
+    }
+
+    unsafe fn hit_test(&self, lparam: LPARAM) -> HTREEITEM {
+        let mut info: TVHITTESTINFO = mem::zeroed();
+        info.pt.x = LOWORD(lparam as DWORD) as i16 as LONG;
+        info.pt.y = HIWORD(lparam as DWORD) as i16 as LONG;
+
+        user32::SendMessageW(self.ptr(), TVM_HITTEST, 0, &mut info as *mut _ as LPARAM);
+
+        if info.flags & TVHT_ONITEM != 0 { info.hItem } else { ptr::null_mut() }
+    }
+
+    fn handle_notify(&mut self, lparam: LPARAM) -> Option<LRESULT> {
+        let data = unsafe { self.hand.data_mut() };
+
+        match unsafe { Notify::from_lparam(lparam) } {
+            Notify::TreeViewExpanding { item, expand: true } => unsafe {
+                if self.next_item(item, TVGN_CHILD).is_null() {
+                    self.populate(item);
+                }
+            },
+            Notify::TreeViewSelChanged { new, .. } => {
+                let key = self.key(new);
+
+                if let Some(mut on_selection_changed) = data.on_selection_changed.take() {
+                    on_selection_changed(self, key);
+                    data.on_selection_changed = Some(on_selection_changed);
+                }
+            },
+            Notify::TreeViewItemChanged { item, old_state, new_state } => {
+                let new_check = new_state & TVIS_STATEIMAGEMASK;
+
+                if old_state & TVIS_STATEIMAGEMASK == new_check {
+                    return Some(0);
+                }
+
+                if let (Some(key), Some(mut on_check_changed)) =
+                    (self.key(item), data.on_check_changed.take()) {
+                    on_check_changed(self, key, new_check == CHECKED);
+                    data.on_check_changed = Some(on_check_changed);
+                }
+            },
+            Notify::TreeViewEndLabelEdit { item, text } => {
+                let (key, text) = match (self.key(item), text) {
+                    (Some(key), Some(text)) => (key, text),
+                    _ => return Some(0),
+                };
+
+                let accept = match data.on_label_edited.take() {
+                    Some(mut on_label_edited) => {
+                        let accept = on_label_edited(self, key, text);
+                        data.on_label_edited = Some(on_label_edited);
+                        accept
+                    },
+                    None => true,
+                };
+
+                return Some(accept as LRESULT);
+            },
+            Notify::TreeViewBeginDrag { item } => {
+                if data.on_drop.is_some() {
+                    data.dragging = Some(item);
+
+                    unsafe {
+                        user32::SetCapture(self.ptr());
+                    }
+                }
+            },
+            _ => return None,
+        }
+
+        Some(0)
+    }
+
+    fn handle_drag(&mut self, msg: UINT, lparam: LPARAM) -> Option<LRESULT> {
+        let data = unsafe { self.hand.data_mut() };
+        let dragged = unwrap_or_ret!(data.dragging, None);
+
+        match msg {
+            WM_MOUSEMOVE => unsafe {
+                let target = self.hit_test(lparam);
+                user32::SendMessageW(self.ptr(), TVM_SELECTITEM, TVGN_DROPHILITE, target as LPARAM);
+            },
+            WM_LBUTTONUP => unsafe {
+                let target = self.hit_test(lparam);
+
+                data.dragging = None;
+                user32::ReleaseCapture();
+                user32::SendMessageW(self.ptr(), TVM_SELECTITEM, TVGN_DROPHILITE, 0);
+
+                if target.is_null() || target == dragged {
+                    return Some(0);
+                }
+
+                if let (Some(dragged), Some(target), Some(mut on_drop)) =
+                    (self.key(dragged), self.key(target), data.on_drop.take()) {
+                    on_drop(self, dragged, target);
+                    data.on_drop = Some(on_drop);
+                }
+            },
+            WM_CAPTURECHANGED => unsafe {
+                data.dragging = None;
+                user32::SendMessageW(self.ptr(), TVM_SELECTITEM, TVGN_DROPHILITE, 0);
+            },
+            _ => return None,
+        }
+
+        Some(0)
+    }
+}
+
+/// State image indices for the checkboxes (`INDEXTOSTATEIMAGEMASK(1)` and `(2)`).
+#[cfg(windows)]
+const UNCHECKED: UINT = 1 << 12;
+#[cfg(windows)]
+const CHECKED: UINT = 2 << 12;
+
+#[cfg(windows)]
+impl<M: TreeModel> AbsWindow for TreeView<M> {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+}
+
+#[cfg(windows)]
+impl<M: TreeModel> WindowEvents for TreeView<M> {
+    type Data = TreeViewData<M>;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, _: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let mut tree = TreeView { hand: hnd.clone() };
+
+        if msg == OCM_BASE + WM_NOTIFY {
+            tree.handle_notify(lparam)
+        } else {
+            tree.handle_drag(msg, lparam)
+        }
+    }
+}
+
+#[cfg(windows)]
+pub struct TreeViewData<M: TreeModel> {
+    parent: HWND,
+    model: M,
+    nodes: NodeMap<M::Key, HTREEITEM>,
+    dragging: Option<HTREEITEM>,
+    on_selection_changed: Option<Box<FnMut(&mut TreeView<M>, Option<M::Key>)>>,
+    on_check_changed: Option<Box<FnMut(&mut TreeView<M>, M::Key, bool)>>,
+    on_label_edited: Option<Box<FnMut(&mut TreeView<M>, M::Key, String) -> bool>>,
+    on_drop: Option<Box<FnMut(&mut TreeView<M>, M::Key, M::Key)>>,
+}
+
+#[cfg(windows)]
+impl<M: TreeModel> TreeViewData<M> {
+    fn new(parent: HWND, model: M) -> TreeViewData<M> {
+        TreeViewData {
+            parent: parent,
+            model: model,
+            nodes: NodeMap::new(),
+            dragging: None,
+            on_selection_changed: None,
+            on_check_changed: None,
+            on_label_edited: None,
+            on_drop: None,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl<M: TreeModel> WindowData for TreeViewData<M> {
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE | WS_TABSTOP |
+        TVS_HASBUTTONS | TVS_HASLINES | TVS_LINESATROOT | TVS_SHOWSELALWAYS
+    }
+}
+
+/// The loaded model keys and the tree items displaying them, looked up either way.
+#[cfg_attr(not(windows), allow(dead_code))]
+struct NodeMap<K, I> {
+    items: HashMap<I, K>,
+    nodes: HashMap<K, I>,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl<K: Clone + Eq + Hash, I: Copy + Eq + Hash> NodeMap<K, I> {
+    fn new() -> Self {
+        NodeMap {
+            items: HashMap::new(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// ## Panics
+    /// If `key` is already loaded, since the item it was loaded for would become unreachable.
+    fn insert(&mut self, item: I, key: K) {
+        assert!(!self.nodes.contains_key(&key), "TreeModel returned a key that is already loaded");
+
+        self.items.insert(item, key.clone());
+        self.nodes.insert(key, item);
+    }
+
+    fn item(&self, key: &K) -> Option<I> {
+        self.nodes.get(key).cloned()
+    }
+
+    fn key(&self, item: I) -> Option<K> {
+        self.items.get(&item).cloned()
+    }
+
+    /// Forget `item`, returning the key it was loaded for.
+    fn remove(&mut self, item: I) -> Option<K> {
+        let key = self.items.remove(&item);
+
+        if let Some(ref key) = key {
+            self.nodes.remove(key);
+        }
+
+        key
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+        self.nodes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn look_up_both_ways() {
+        let mut nodes = NodeMap::new();
+        nodes.insert(1, "a");
+        nodes.insert(2, "b");
+
+        assert_eq!(nodes.item(&"a"), Some(1));
+        assert_eq!(nodes.item(&"b"), Some(2));
+        assert_eq!(nodes.key(1), Some("a"));
+        assert_eq!(nodes.key(2), Some("b"));
+
+        assert_eq!(nodes.item(&"c"), None);
+        assert_eq!(nodes.key(3), None);
+    }
+
+    #[test]
+    fn remove_forgets_both_ways() {
+        let mut nodes = NodeMap::new();
+        nodes.insert(1, "a");
+        nodes.insert(2, "b");
+
+        assert_eq!(nodes.remove(1), Some("a"));
+        assert_eq!(nodes.remove(1), None);
+
+        assert_eq!(nodes.item(&"a"), None);
+        assert_eq!(nodes.key(1), None);
+        assert_eq!(nodes.item(&"b"), Some(2));
+
+        // A removed key can be loaded again, e.g. by `refresh()`.
+        nodes.insert(3, "a");
+        assert_eq!(nodes.item(&"a"), Some(3));
+        assert_eq!(nodes.key(3), Some("a"));
+
+        nodes.clear();
+        assert_eq!(nodes.item(&"a"), None);
+        assert_eq!(nodes.key(2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "already loaded")]
+    fn duplicate_keys_panic() {
+        let mut nodes = NodeMap::new();
+        nodes.insert(1, "a");
+        nodes.insert(2, "a");
+    }
+}
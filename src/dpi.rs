@@ -0,0 +1,137 @@
+//! Scaling between logical units and pixels on high-DPI displays.
+//!
+//! Layouts and window size limits are in logical units, where 96 DPI is one pixel per unit,
+//! and are scaled to the DPI of the window they're applied to. Call
+//! `enable_per_monitor_awareness()` before creating any windows to have them follow the
+//! DPI of the monitor they're on; otherwise Windows scales the whole window as a bitmap.
+
+#[cfg(windows)] use user32;
+
+#[cfg(windows)] use abs_window::AbsWindow;
+#[cfg(windows)] use ffi::{self, gdi};
+use layout::{Rect, Size};
+
+#[cfg(windows)] use winapi::*;
+
+#[cfg(windows)] use std::mem;
+#[cfg(windows)] use std::ptr;
+
+/// The DPI at which one logical unit is one pixel.
+pub const DEFAULT_DPI: u32 = 96;
+
+// `DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2`
+#[cfg(windows)] const PER_MONITOR_AWARE_V2: isize = -4;
+
+#[cfg(windows)]
+lazy_static! {
+    static ref GET_DPI_FOR_WINDOW: usize = ffi::load_proc("user32.dll", "GetDpiForWindow");
+}
+
+/// The dots per inch of a window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Dpi(pub u32);
+
+impl Default for Dpi {
+    fn default() -> Self {
+        Dpi(DEFAULT_DPI)
+    }
+}
+
+impl Dpi {
+    /// The DPI `window` is displayed at.
+    #[cfg(windows)]
+    pub fn of_window<W: AbsWindow + ?Sized>(window: &W) -> Dpi {
+        Self::of_hwnd(window.ptr())
+    }
+
+    #[cfg(windows)]
+    #[doc(hidden)]
+    pub fn of_hwnd(hwnd: HWND) -> Dpi {
+        unsafe {
+            if *GET_DPI_FOR_WINDOW != 0 {
+                let get_dpi_for_window: unsafe extern "system" fn(HWND) -> UINT =
+                    mem::transmute(*GET_DPI_FOR_WINDOW);
+
+                let dpi = get_dpi_for_window(hwnd);
+
+                if dpi != 0 {
+                    return Dpi(dpi);
+                }
+            }
+
+            // Before Windows 10 the DPI is the same for the whole system.
+            Dpi::system()
+        }
+    }
+
+    /// The DPI of the primary monitor when the user logged in, which system metrics such
+    /// as the default fonts are given in.
+    #[cfg(windows)]
+    pub fn system() -> Dpi {
+        unsafe {
+            let hdc = user32::GetDC(ptr::null_mut());
+            let dpi = gdi::GetDeviceCaps(hdc, LOGPIXELSX);
+            user32::ReleaseDC(ptr::null_mut(), hdc);
+
+            if dpi > 0 { Dpi(dpi as u32) } else { Dpi::default() }
+        }
+    }
+
+    /// The number of pixels per logical unit.
+    pub fn scale_factor(self) -> f64 {
+        self.0 as f64 / DEFAULT_DPI as f64
+    }
+
+    /// Convert logical units to pixels.
+    pub fn scale(self, logical: i32) -> i32 {
+        (logical as f64 * self.scale_factor()).round() as i32
+    }
+
+    /// Convert pixels to logical units.
+    pub fn unscale(self, pixels: i32) -> i32 {
+        (pixels as f64 / self.scale_factor()).round() as i32
+    }
+
+    pub fn scale_size(self, size: Size) -> Size {
+        Size::new(self.scale(size.width), self.scale(size.height))
+    }
+
+    pub fn unscale_size(self, size: Size) -> Size {
+        Size::new(self.unscale(size.width), self.unscale(size.height))
+    }
+
+    /// Scale the edges of `rect` rather than its size, so that rectangles which touch
+    /// before scaling still touch afterwards.
+    pub fn scale_rect(self, rect: Rect) -> Rect {
+        let x = self.scale(rect.x);
+        let y = self.scale(rect.y);
+
+        Rect::new(x, y, self.scale(rect.right()) - x, self.scale(rect.bottom()) - y)
+    }
+
+    pub fn unscale_rect(self, rect: Rect) -> Rect {
+        let x = self.unscale(rect.x);
+        let y = self.unscale(rect.y);
+
+        Rect::new(x, y, self.unscale(rect.right()) - x, self.unscale(rect.bottom()) - y)
+    }
+}
+
+/// Make windows created from now on scale to the DPI of the monitor they're on (per-monitor
+/// v2 awareness), instead of being stretched by Windows.
+///
+/// Returns `false` if this version of Windows doesn't support it, or awareness was already
+/// set, e.g. by the application manifest.
+#[cfg(windows)]
+pub fn enable_per_monitor_awareness() -> bool {
+    let set_context = ffi::load_proc("user32.dll", "SetProcessDpiAwarenessContext");
+
+    if set_context == 0 {
+        return false;
+    }
+
+    unsafe {
+        let set_context: unsafe extern "system" fn(isize) -> BOOL = mem::transmute(set_context);
+        set_context(PER_MONITOR_AWARE_V2) != 0
+    }
+}
@@ -0,0 +1,11 @@
+//! Registry functions, since `advapi32-sys` isn't a dependency.
+
+use winapi::*;
+
+#[link(name = "advapi32")]
+extern "system" {
+    pub fn RegGetValueW(
+        hkey: HKEY, lpSubKey: LPCWSTR, lpValue: LPCWSTR, dwFlags: DWORD, pdwType: LPDWORD,
+        pvData: PVOID, pcbData: LPDWORD
+    ) -> LONG;
+}
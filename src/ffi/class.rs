@@ -3,11 +3,13 @@ use winapi::*;
 
 use winstr::WinString;
 
+use super::comctl;
 use super::error::WindowsError;
 use super::traits::WindowEvents;
 
 use std::collections::HashMap;
 use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
 
 use std::{mem, ptr};
 
@@ -15,6 +17,9 @@ pub trait Class {
     fn is_system(&self) -> bool { false }
 
     fn atom(&self) -> *const u16;
+
+    /// Perform any one-time initialization required before this class can be instantiated.
+    fn init(&self) {}
 }
 
 pub trait CustomClass {
@@ -100,9 +105,47 @@ unsafe fn register_class<W: CustomClass>() -> ATOM {
     user32::RegisterClassExW(&class_def)        
 }
 
-mod system {
+// `GetClassLongPtrW()` index of the class style.
+const GCL_STYLE: c_int = -26;
+
+/// Add `style` to the class of `hwnd`, for all windows of the class.
+pub unsafe fn add_class_style(hwnd: HWND, style: DWORD) {
+    let current = user32::GetClassLongPtrW(hwnd, GCL_STYLE) as DWORD;
+
+    if current & style != style {
+        user32::SetClassLongPtrW(hwnd, GCL_STYLE, (current | style) as LONG_PTR);
+    }
+}
+
+static COMMON_CONTROLS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Call `InitCommonControlsEx()` for the given `ICC_*` flags, unless they have already been
+/// initialized for this process.
+pub fn init_common_controls(flags: DWORD) {
+    let flags = flags as usize;
+
+    if COMMON_CONTROLS.load(Ordering::SeqCst) & flags == flags {
+        return;
+    }
+
+    let init = INITCOMMONCONTROLSEX {
+        dwSize: mem::size_of::<INITCOMMONCONTROLSEX>() as DWORD,
+        dwICC: flags as DWORD,
+    };
+
+    if unsafe { comctl::InitCommonControlsEx(&init) } == 0 {
+        error!("Failed to initialize common controls {:X}", flags);
+        return;
+    }
+
+    COMMON_CONTROLS.fetch_or(flags, Ordering::SeqCst);
+}
+
+pub mod system {
+    use winapi::*;
+
     #[derive(Copy, Clone)]
-    pub struct SystemClass(&'static [u16]);
+    pub struct SystemClass(&'static [u16], DWORD);
 
     impl super::Class for SystemClass {
         fn is_system(&self) -> bool { true }
@@ -110,7 +153,13 @@ mod system {
         fn atom(&self) -> *const u16 {
             self.0.as_ptr()
         }
+
+        fn init(&self) {
+            if self.1 != 0 {
+                super::init_common_controls(self.1);
+            }
+        }
     }
 
-    include!("system_classes.rs");
+    include!(concat!(env!("OUT_DIR"), "/system_classes.rs"));
 }
@@ -0,0 +1,14 @@
+//! Common controls functions, since `comctl32-sys` isn't a dependency.
+
+use winapi::*;
+
+#[link(name = "comctl32")]
+extern "system" {
+    pub fn ImageList_Create(
+        cx: c_int, cy: c_int, flags: UINT, cInitial: c_int, cGrow: c_int
+    ) -> HIMAGELIST;
+    pub fn ImageList_Destroy(himl: HIMAGELIST) -> BOOL;
+    pub fn ImageList_GetImageCount(himl: HIMAGELIST) -> c_int;
+    pub fn ImageList_ReplaceIcon(himl: HIMAGELIST, i: c_int, hicon: HICON) -> c_int;
+    pub fn InitCommonControlsEx(picce: *const INITCOMMONCONTROLSEX) -> BOOL;
+}
@@ -0,0 +1,28 @@
+//! GDI functions missing from `gdi32-sys`.
+
+use winapi::*;
+
+#[link(name = "gdi32")]
+extern "system" {
+    pub fn CreatePen(iStyle: c_int, cWidth: c_int, color: COLORREF) -> HPEN;
+    pub fn ExcludeClipRect(hdc: HDC, left: c_int, top: c_int, right: c_int, bottom: c_int) -> c_int;
+    pub fn GdiFlush() -> BOOL;
+    pub fn GetDeviceCaps(hdc: HDC, index: c_int) -> c_int;
+    pub fn GetObjectW(h: HANDLE, c: c_int, pv: LPVOID) -> c_int;
+    pub fn GetTextExtentExPointW(
+        hdc: HDC, lpszString: LPCWSTR, cchString: c_int, nMaxExtent: c_int, lpnFit: LPINT,
+        lpnDx: LPINT, lpSize: *mut SIZE
+    ) -> BOOL;
+    pub fn GetTextExtentPoint32W(hdc: HDC, lpString: LPCWSTR, c: c_int, psizl: *mut SIZE) -> BOOL;
+    pub fn GetTextMetricsW(hdc: HDC, lptm: *mut TEXTMETRICW) -> BOOL;
+    pub fn IntersectClipRect(hdc: HDC, left: c_int, top: c_int, right: c_int, bottom: c_int) -> c_int;
+    pub fn RestoreDC(hdc: HDC, nSavedDC: c_int) -> BOOL;
+    pub fn SaveDC(hdc: HDC) -> c_int;
+    pub fn SetBkMode(hdc: HDC, mode: c_int) -> c_int;
+    pub fn SetDIBitsToDevice(
+        hdc: HDC, xDest: c_int, yDest: c_int, w: DWORD, h: DWORD, xSrc: c_int, ySrc: c_int,
+        StartScan: UINT, cLines: UINT, lpvBits: *const VOID, lpbmi: *const BITMAPINFO, ColorUse: UINT
+    ) -> c_int;
+    pub fn SetStretchBltMode(hdc: HDC, mode: c_int) -> c_int;
+    pub fn SetViewportOrgEx(hdc: HDC, x: c_int, y: c_int, lppt: LPPOINT) -> BOOL;
+}
@@ -2,6 +2,7 @@ use winapi::*;
 
 use {kernel32, user32};
 
+use {accel, color, focus, font, theme, window};
 use winstr::WinString;
 
 use std::marker::PhantomData;
@@ -13,8 +14,13 @@ use std::{mem, ptr, thread};
 use self::class::Class;
 use self::traits::{WindowEvents, WindowData};
 
+pub mod advapi;
 pub mod class;
+pub mod comctl;
+pub mod gdi;
+pub mod msg;
 pub mod traits;
+pub mod user;
 
 mod error;
 
@@ -24,16 +30,6 @@ pub type FFIResult<T> = Result<T, WindowsError>;
 
 const RET_ERR: LRESULT = -1;
 
-macro_rules! unwrap_or_ret (
-    ($expr:expr, $or:expr) => (
-        if let Some(val) = $expr {
-            val
-        } else {
-            return $or;
-        }
-    )
-);
-
 pub struct WindowHandle<W: WindowEvents> {
     hwnd: HWND,
     data: *mut ProcData<<W as WindowEvents>::Data>,
@@ -42,19 +38,22 @@ pub struct WindowHandle<W: WindowEvents> {
 
 impl<W: WindowEvents> WindowHandle<W> {
     pub fn create_instance<C: Class>(class: C, data: <W as WindowEvents>::Data) -> FFIResult<Self> {
+        class.init();
+
         let window_name = data.name().map_or_else(ptr::null, WinString::as_ptr);
 
         let pos = data.pos();
         let size = data.size();
         let parent = data.parent();
         let menu = data.menu();
+        let style = data.style();
 
         let hwnd = unsafe {
             user32::CreateWindowExW(
-                WS_EX_CLIENTEDGE,
+                data.ex_style(),
                 class.atom(),
                 window_name,
-                WS_OVERLAPPEDWINDOW,
+                style,
                 pos[0], pos[1], size[0], size[1],
                 parent,
                 menu,
@@ -66,19 +65,29 @@ impl<W: WindowEvents> WindowHandle<W> {
         if hwnd.is_null() {
             Err(WindowsError::last())
         } else {
-            let orig_proc = if class.is_system() {
-                unsafe { set_wnd_proc::<W>(hwnd) }
-            } else {
-                None
-            };
-
             let proc_data = ProcData {
-                orig_proc: orig_proc,
+                orig_proc: None,
                 window_data: data,
             };
 
             let data_ptr = Box::into_raw(Box::new(proc_data));
 
+            unsafe {
+                user32::SetWindowLongPtrW(hwnd, GWLP_USERDATA, data_ptr as LONG_PTR);
+
+                // The data pointer has to be in place before we start receiving messages.
+                if class.is_system() {
+                    (*data_ptr).orig_proc = set_wnd_proc::<W>(hwnd);
+                }
+            }
+
+            // Otherwise controls draw their text in the bitmap system font.
+            if !parent.is_null() && style & WS_CHILD != 0 {
+                font::apply(hwnd);
+            }
+
+            theme::apply(hwnd);
+
             let handle = WindowHandle {
                 hwnd: hwnd,
                 data: data_ptr,
@@ -110,18 +119,36 @@ impl<W: WindowEvents> WindowHandle<W> {
             Some(Self::from_ptrs(hwnd, data_ptr))
         }
     } 
+
+    /// Get the handle of `hwnd` if it is a window of type `W`, e.g. to reach the data of a
+    /// parent window.
+    pub unsafe fn downcast(hwnd: HWND) -> Option<Self> {
+        let proc_addr = user32::GetWindowLongPtrW(hwnd, GWLP_WNDPROC);
+
+        if proc_addr != window_proc::<W> as LONG_PTR {
+            return None;
+        }
+
+        Self::from_hwnd(hwnd)
+    }
     
+    /// Free the data of a window on `WM_NCDESTROY`, the last message it gets, handing the
+    /// window back to its original window procedure.
     unsafe fn cleanup(&mut self) {
         self.fresh = false;
-        user32::DestroyWindow(self.hwnd);
+        user32::SetWindowLongPtrW(self.hwnd, GWLP_USERDATA, 0);
 
         if !self.data.is_null() {
+            if let Some(orig_proc) = (*self.data).orig_proc {
+                user32::SetWindowLongPtrW(self.hwnd, GWLP_WNDPROC, orig_proc as LONG_PTR);
+            }
+
             Box::from_raw(self.data);
             self.data = ptr::null_mut();
         }
     }
 
-    fn hwnd(&self) -> HWND {
+    pub fn hwnd(&self) -> HWND {
         self.hwnd
     }
 
@@ -146,9 +173,17 @@ impl<W: WindowEvents> Clone for WindowHandle<W> {
 
 impl<W: WindowEvents> Drop for WindowHandle<W> {
     fn drop(&mut self) {
-        if self.fresh {
+        // If the window was already destroyed along with its parent, the data was freed in
+        // `WM_NCDESTROY` and the user data pointer cleared.
+        let alive = unsafe {
+            user32::GetWindowLongPtrW(self.hwnd, GWLP_USERDATA) as *mut _ == self.data
+        };
+
+        // The window procedure still gets `WM_DESTROY` with the data in place, and frees it
+        // afterwards.
+        if self.fresh && alive {
             unsafe {
-                self.cleanup();
+                user32::DestroyWindow(self.hwnd);
             }
         }
     }
@@ -163,6 +198,29 @@ pub unsafe fn destroy_thread_windows() -> BOOL {
     user32::EnumThreadWindows(thread_id, Some(destroy_thread_proc), 0)
 }
 
+/// Look up `name` in the system DLL `module`, for functions that only exist in newer
+/// versions of Windows. Returns the address of the function, or 0 if it isn't there.
+///
+/// The result can be cached in a static, since the DLLs looked up are never unloaded.
+pub fn load_proc(module: &str, name: &str) -> usize {
+    let module = WinString::from_str(module);
+    let name = ::std::ffi::CString::new(name).unwrap();
+
+    unsafe {
+        let mut handle = kernel32::GetModuleHandleW(module.as_ptr());
+
+        if handle.is_null() {
+            handle = kernel32::LoadLibraryW(module.as_ptr());
+        }
+
+        if handle.is_null() {
+            return 0;
+        }
+
+        kernel32::GetProcAddress(handle, name.as_ptr()) as usize
+    }
+}
+
 type WindowProc = unsafe extern "system" fn(HWND, UINT, WPARAM, LPARAM) -> LRESULT;
 
 unsafe fn set_wnd_proc<W: WindowEvents>(hwnd: HWND) -> Option<WindowProc> {
@@ -180,12 +238,61 @@ impl<D> ProcData<D> {
     }
 }
 
+/// Notifications from child controls are sent back to the child as `OCM_BASE + msg` so the
+/// control's `WindowEvents` impl can handle them.
+pub const OCM_BASE: UINT = WM_USER + 0x1c00;
+
+/// Get the child control a notification message is about, if any.
+unsafe fn notify_target(msg: UINT, lparam: LPARAM) -> HWND {
+    match msg {
+        WM_COMMAND | WM_HSCROLL | WM_VSCROLL |
+        WM_CTLCOLORBTN | WM_CTLCOLOREDIT | WM_CTLCOLORSTATIC |
+        WM_CTLCOLORLISTBOX | WM_CTLCOLORSCROLLBAR => lparam as HWND,
+        WM_NOTIFY if lparam != 0 => (*(lparam as *const NMHDR)).hwndFrom,
+        _ => ptr::null_mut(),
+    }
+}
+
+unsafe fn reflect_msg(msg: UINT, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+    let target = notify_target(msg, lparam);
+
+    if target.is_null() {
+        return None;
+    }
+
+    match user32::SendMessageW(target, OCM_BASE + msg, wparam, lparam) {
+        0 => None,
+        res => Some(res),
+    }
+}
+
+/// Apply the colors set on a window with `AbsWindow::set_background_color()` and
+/// `set_text_color()`.
+unsafe fn handle_colors<W: WindowEvents>(handle: &WindowHandle<W>, msg: UINT, wparam: WPARAM) -> Option<LRESULT> {
+    match msg {
+        _ if msg >= OCM_BASE + WM_CTLCOLORMSGBOX && msg <= OCM_BASE + WM_CTLCOLORSTATIC =>
+            color::handle_ctl_color(handle.hwnd, msg - OCM_BASE, wparam as HDC),
+        // Windows of our own classes erase their background themselves.
+        WM_ERASEBKGND if (*handle.data).orig_proc.is_none() =>
+            if color::erase_background(handle.hwnd, wparam as HDC) { Some(TRUE as LRESULT) } else { None },
+        _ => None,
+    }
+}
+
 unsafe extern "system" fn window_proc<W: WindowEvents>(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT { 
-    let mut handle = unwrap_or_ret!(WindowHandle::from_hwnd(hwnd), RET_ERR);
+    // Messages sent during `CreateWindowExW()` arrive before the data pointer is set.
+    let mut handle = unwrap_or_ret!(
+        WindowHandle::from_hwnd(hwnd), 
+        user32::DefWindowProcW(hwnd, msg, wparam, lparam)
+    );
 
     let mut handle = AssertRecoverSafe::new(&mut handle);
+
+    if msg == WM_SETFOCUS || msg == WM_KILLFOCUS {
+        ::recover(|| focus::notify(handle.hwnd(), msg == WM_SETFOCUS));
+    }
      
-    let res = match msg {
+    match msg {
         WM_SHOWWINDOW => {
             ::recover(||{
                 if wparam != 0 {
@@ -193,28 +300,45 @@ unsafe extern "system" fn window_proc<W: WindowEvents>(hwnd: HWND, msg: UINT, wp
                 } else {
                     W::on_hide(&handle);
                 }
-                
-                0
-            })
+            });
         },
         WM_DESTROY => {
-            let mut res = Some(RET_ERR);
-
             if !thread::panicking() { 
-                res = ::recover(|| { W::on_destroy(&handle); 0 });
+                ::recover(|| W::on_destroy(&handle));
             }
 
+            accel::forget(hwnd);
+            color::forget(hwnd);
+            focus::forget(hwnd);
+            window::forget_child(hwnd);
+        },
+        WM_NCDESTROY => {
+            let orig_proc = handle.orig_proc();
             handle.cleanup();
-            res
+            return orig_proc(hwnd, msg, wparam, lparam);
         },
         _ => {
-            let mut res = ::recover(|| W::handle_msg(&handle, msg));
-            res
-        },
-    };
+            if let Some(res) = reflect_msg(msg, wparam, lparam) {
+                return res;
+            }
 
-    (handle.orig_proc())(hwnd, msg, wparam, lparam);
+            match ::recover(|| W::handle_msg(&handle, msg, wparam, lparam)) {
+                Some(Some(res)) => return res,
+                Some(None) => {
+                    // After `handle_msg()`, so that windows can do their own erasing.
+                    if let Some(res) = handle_colors(&handle, msg, wparam) {
+                        return res;
+                    }
+
+                    if msg >= OCM_BASE && msg < OCM_BASE + WM_USER {
+                        // The original window procedure doesn't know about reflected messages.
+                        return 0;
+                    }
+                },
+                None => return RET_ERR,
+            }
+        },
+    }
 
-    res.unwrap_or(RET_ERR)
+    (handle.orig_proc())(hwnd, msg, wparam, lparam)
 }
-
@@ -0,0 +1,621 @@
+//! Typed decoding of raw window messages.
+//!
+//! Everything here works on the message parameters alone, without touching the window
+//! they were sent to, so it builds on every platform: elsewhere, the winapi definitions it
+//! needs are replaced by the stand-ins in `winapi_types`.
+
+#[cfg(windows)] use winapi::*;
+#[cfg(not(windows))] use self::winapi_types::*;
+
+/// A decoded `WM_NOTIFY` message.
+pub enum Notify<'a> {
+    /// `LVN_GETDISPINFOW`: a virtual list view needs the contents of a cell.
+    ListViewDispInfo(&'a mut LVITEMW),
+    /// `LVN_ODCACHEHINT`: a virtual list view is about to ask for these rows, inclusive.
+    ListViewCacheHint {
+        from: usize,
+        to: usize,
+    },
+    /// `LVN_COLUMNCLICK`: a column header was clicked.
+    ListViewColumnClick {
+        column: usize,
+    },
+    /// `LVN_ITEMCHANGED`: the state (e.g. selection) of a row changed.
+    ///
+    /// `row` is `None` if the change applies to all rows.
+    ListViewItemChanged {
+        row: Option<usize>,
+        old_state: UINT,
+        new_state: UINT,
+    },
+    /// `TVN_ITEMEXPANDINGW`: a tree node is about to be expanded or collapsed.
+    TreeViewExpanding {
+        item: HTREEITEM,
+        expand: bool,
+    },
+    /// `TVN_SELCHANGEDW`: the selected tree node changed.
+    TreeViewSelChanged {
+        old: HTREEITEM,
+        new: HTREEITEM,
+    },
+    /// `TVN_ITEMCHANGEDW`: the state (e.g. checkbox) of a tree node changed.
+    TreeViewItemChanged {
+        item: HTREEITEM,
+        old_state: UINT,
+        new_state: UINT,
+    },
+    /// `TVN_BEGINLABELEDITW`: the user wants to edit the label of a tree node.
+    TreeViewBeginLabelEdit {
+        item: HTREEITEM,
+    },
+    /// `TVN_ENDLABELEDITW`: label editing finished; `text` is `None` if it was cancelled.
+    TreeViewEndLabelEdit {
+        item: HTREEITEM,
+        text: Option<String>,
+    },
+    /// `TVN_BEGINDRAGW`: the user started dragging a tree node with the left mouse button.
+    TreeViewBeginDrag {
+        item: HTREEITEM,
+    },
+    /// `TCN_SELCHANGE`: a different tab was selected.
+    TabSelChange,
+    /// Any notification not decoded above.
+    Other {
+        code: UINT,
+    },
+}
+
+impl<'a> Notify<'a> {
+    /// Decode the `NMHDR` pointed to by the `lparam` of a `WM_NOTIFY` message.
+    ///
+    /// ## Safety
+    /// `lparam` must point to a valid `NMHDR`, embedded in the structure matching its `code`.
+    pub unsafe fn from_lparam(lparam: LPARAM) -> Notify<'a> {
+        let hdr = &*(lparam as *const NMHDR);
+
+        match hdr.code {
+            LVN_GETDISPINFOW => {
+                let info = &mut *(lparam as *mut NMLVDISPINFOW);
+                Notify::ListViewDispInfo(&mut info.item)
+            },
+            LVN_ODCACHEHINT => {
+                let hint = &*(lparam as *const NMLVCACHEHINT);
+                Notify::ListViewCacheHint {
+                    from: hint.iFrom as usize,
+                    to: hint.iTo as usize,
+                }
+            },
+            LVN_COLUMNCLICK => {
+                let nmlv = &*(lparam as *const NMLISTVIEW);
+                Notify::ListViewColumnClick {
+                    column: nmlv.iSubItem as usize,
+                }
+            },
+            LVN_ITEMCHANGED => {
+                let nmlv = &*(lparam as *const NMLISTVIEW);
+                Notify::ListViewItemChanged {
+                    row: index(nmlv.iItem),
+                    old_state: nmlv.uOldState,
+                    new_state: nmlv.uNewState,
+                }
+            },
+            TVN_ITEMEXPANDINGW => {
+                let nmtv = &*(lparam as *const NMTREEVIEWW);
+                Notify::TreeViewExpanding {
+                    item: nmtv.itemNew.hItem,
+                    expand: nmtv.action as WPARAM & TVE_EXPAND != 0,
+                }
+            },
+            TVN_SELCHANGEDW => {
+                let nmtv = &*(lparam as *const NMTREEVIEWW);
+                Notify::TreeViewSelChanged {
+                    old: nmtv.itemOld.hItem,
+                    new: nmtv.itemNew.hItem,
+                }
+            },
+            TVN_ITEMCHANGEDW => {
+                let change = &*(lparam as *const NMTVITEMCHANGE);
+                Notify::TreeViewItemChanged {
+                    item: change.hItem,
+                    old_state: change.uStateOld,
+                    new_state: change.uStateNew,
+                }
+            },
+            TVN_BEGINLABELEDITW => {
+                let info = &*(lparam as *const NMTVDISPINFOW);
+                Notify::TreeViewBeginLabelEdit {
+                    item: info.item.hItem,
+                }
+            },
+            TVN_ENDLABELEDITW => {
+                let info = &*(lparam as *const NMTVDISPINFOW);
+                Notify::TreeViewEndLabelEdit {
+                    item: info.item.hItem,
+                    text: wide_to_string(info.item.pszText),
+                }
+            },
+            TVN_BEGINDRAGW => {
+                let nmtv = &*(lparam as *const NMTREEVIEWW);
+                Notify::TreeViewBeginDrag {
+                    item: nmtv.itemNew.hItem,
+                }
+            },
+            TCN_SELCHANGE => Notify::TabSelChange,
+            code => Notify::Other { code: code },
+        }
+    }
+}
+
+/// A decoded `WM_COMMAND` message.
+#[derive(Copy, Clone, Debug)]
+pub enum CommandMsg {
+    /// A menu item was chosen.
+    Menu(u16),
+    /// A keyboard accelerator was pressed.
+    Accelerator(u16),
+    /// A notification code from a child control.
+    Control {
+        id: u16,
+        code: u16,
+        hwnd: HWND,
+    },
+}
+
+impl CommandMsg {
+    pub fn from_params(wparam: WPARAM, lparam: LPARAM) -> CommandMsg {
+        let id = LOWORD(wparam as DWORD);
+        let code = HIWORD(wparam as DWORD);
+
+        match (lparam, code) {
+            (0, 0) => CommandMsg::Menu(id),
+            (0, _) => CommandMsg::Accelerator(id),
+            (hwnd, code) => CommandMsg::Control {
+                id: id,
+                code: code,
+                hwnd: hwnd as HWND,
+            },
+        }
+    }
+}
+
+/// Convert a control item index where -1 means "none" or "all".
+pub fn index(idx: c_int) -> Option<usize> {
+    if idx < 0 { None } else { Some(idx as usize) }
+}
+
+/// Read a NUL-terminated UTF-16 string, or `None` if `ptr` is null.
+pub unsafe fn wide_to_string(ptr: *const u16) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    let mut len = 0;
+
+    while *ptr.offset(len) != 0 {
+        len += 1;
+    }
+
+    Some(String::from_utf16_lossy(::std::slice::from_raw_parts(ptr, len as usize)))
+}
+
+/// Copy `text` into a fixed-size buffer supplied by a control, truncating if necessary.
+///
+/// The result is always NUL-terminated, unless `buf` is empty.
+pub fn copy_text(text: &[u16], buf: &mut [u16]) {
+    if buf.is_empty() {
+        return;
+    }
+
+    // Strip any terminator `text` might already have
+    let text = match text.iter().position(|&c| c == 0) {
+        Some(end) => &text[..end],
+        None => text,
+    };
+
+    let len = ::std::cmp::min(text.len(), buf.len() - 1);
+    buf[..len].copy_from_slice(&text[..len]);
+    buf[len] = 0;
+}
+
+/// Layout-compatible copies of the winapi definitions used above, which only exist when
+/// building for Windows.
+#[cfg(not(windows))]
+#[allow(non_camel_case_types, non_snake_case)]
+mod winapi_types {
+    pub use std::os::raw::{c_int, c_void};
+
+    pub type DWORD = u32;
+    pub type UINT = u32;
+    pub type UINT_PTR = usize;
+    pub type WPARAM = usize;
+    pub type LPARAM = isize;
+    pub type HWND = *mut c_void;
+    pub type HTREEITEM = *mut c_void;
+    pub type LPWSTR = *mut u16;
+
+    pub fn LOWORD(l: DWORD) -> u16 { l as u16 }
+    pub fn HIWORD(l: DWORD) -> u16 { (l >> 16) as u16 }
+
+    pub const NM_FIRST: UINT = 0;
+    pub const LVN_FIRST: UINT = -100i32 as UINT;
+    pub const TVN_FIRST: UINT = -400i32 as UINT;
+    pub const TCN_FIRST: UINT = -550i32 as UINT;
+
+    pub const NM_CLICK: UINT = (NM_FIRST as i32 - 2) as UINT;
+    pub const LVN_ITEMCHANGED: UINT = LVN_FIRST - 1;
+    pub const LVN_COLUMNCLICK: UINT = LVN_FIRST - 8;
+    pub const LVN_ODCACHEHINT: UINT = LVN_FIRST - 13;
+    pub const LVN_GETDISPINFOW: UINT = LVN_FIRST - 77;
+    pub const TVN_ITEMCHANGEDW: UINT = TVN_FIRST - 19;
+    pub const TVN_SELCHANGEDW: UINT = TVN_FIRST - 51;
+    pub const TVN_ITEMEXPANDINGW: UINT = TVN_FIRST - 54;
+    pub const TVN_BEGINDRAGW: UINT = TVN_FIRST - 56;
+    pub const TVN_BEGINLABELEDITW: UINT = TVN_FIRST - 59;
+    pub const TVN_ENDLABELEDITW: UINT = TVN_FIRST - 60;
+    pub const TCN_SELCHANGE: UINT = TCN_FIRST - 1;
+
+    pub const BN_CLICKED: u16 = 0;
+    pub const LVIF_TEXT: UINT = 0x1;
+    pub const LVIS_SELECTED: UINT = 0x2;
+    pub const TVE_COLLAPSE: WPARAM = 0x1;
+    pub const TVE_EXPAND: WPARAM = 0x2;
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct POINT {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct NMHDR {
+        pub hwndFrom: HWND,
+        pub idFrom: UINT_PTR,
+        pub code: UINT,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct LVITEMW {
+        pub mask: UINT,
+        pub iItem: c_int,
+        pub iSubItem: c_int,
+        pub state: UINT,
+        pub stateMask: UINT,
+        pub pszText: LPWSTR,
+        pub cchTextMax: c_int,
+        pub iImage: c_int,
+        pub lParam: LPARAM,
+        pub iIndent: c_int,
+        pub iGroupId: c_int,
+        pub cColumns: UINT,
+        pub puColumns: *mut UINT,
+        pub piColFmt: *mut c_int,
+        pub iGroup: c_int,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct NMLVDISPINFOW {
+        pub hdr: NMHDR,
+        pub item: LVITEMW,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct NMLVCACHEHINT {
+        pub hdr: NMHDR,
+        pub iFrom: c_int,
+        pub iTo: c_int,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct NMLISTVIEW {
+        pub hdr: NMHDR,
+        pub iItem: c_int,
+        pub iSubItem: c_int,
+        pub uNewState: UINT,
+        pub uOldState: UINT,
+        pub uChanged: UINT,
+        pub ptAction: POINT,
+        pub lParam: LPARAM,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct TVITEMW {
+        pub mask: UINT,
+        pub hItem: HTREEITEM,
+        pub state: UINT,
+        pub stateMask: UINT,
+        pub pszText: LPWSTR,
+        pub cchTextMax: c_int,
+        pub iImage: c_int,
+        pub iSelectedImage: c_int,
+        pub cChildren: c_int,
+        pub lParam: LPARAM,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct NMTREEVIEWW {
+        pub hdr: NMHDR,
+        pub action: UINT,
+        pub itemOld: TVITEMW,
+        pub itemNew: TVITEMW,
+        pub ptDrag: POINT,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct NMTVITEMCHANGE {
+        pub hdr: NMHDR,
+        pub uChanged: UINT,
+        pub hItem: HTREEITEM,
+        pub uStateNew: UINT,
+        pub uStateOld: UINT,
+        pub lParam: LPARAM,
+    }
+
+    #[repr(C)] #[derive(Copy, Clone)]
+    pub struct NMTVDISPINFOW {
+        pub hdr: NMHDR,
+        pub item: TVITEMW,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::mem;
+
+    fn header(code: UINT) -> NMHDR {
+        NMHDR { hwndFrom: 0x1234 as HWND, idFrom: 7, code: code }
+    }
+
+    #[test]
+    fn notify_list_view_disp_info() {
+        let mut buf = [0u16; 16];
+
+        let mut info: NMLVDISPINFOW = unsafe { mem::zeroed() };
+        info.hdr = header(LVN_GETDISPINFOW);
+        info.item.mask = LVIF_TEXT;
+        info.item.iItem = 3;
+        info.item.iSubItem = 1;
+        info.item.pszText = buf.as_mut_ptr();
+        info.item.cchTextMax = buf.len() as c_int;
+
+        match unsafe { Notify::from_lparam(&mut info as *mut _ as LPARAM) } {
+            Notify::ListViewDispInfo(item) => {
+                assert_eq!((item.iItem, item.iSubItem), (3, 1));
+
+                // The handler answers through the item, in the notification itself.
+                let text = unsafe {
+                    ::std::slice::from_raw_parts_mut(item.pszText, item.cchTextMax as usize)
+                };
+                copy_text(&[72, 105], text);
+            },
+            _ => panic!("not decoded as a request for a cell"),
+        }
+
+        assert_eq!(&buf[..3], &[72, 105, 0]);
+    }
+
+    #[test]
+    fn notify_list_view_cache_hint() {
+        let mut hint: NMLVCACHEHINT = unsafe { mem::zeroed() };
+        hint.hdr = header(LVN_ODCACHEHINT);
+        hint.iFrom = 10;
+        hint.iTo = 25;
+
+        match unsafe { Notify::from_lparam(&mut hint as *mut _ as LPARAM) } {
+            Notify::ListViewCacheHint { from: 10, to: 25 } => (),
+            _ => panic!("not decoded as a cache hint"),
+        }
+    }
+
+    #[test]
+    fn notify_list_view_column_click() {
+        let mut nmlv: NMLISTVIEW = unsafe { mem::zeroed() };
+        nmlv.hdr = header(LVN_COLUMNCLICK);
+        nmlv.iItem = -1;
+        nmlv.iSubItem = 2;
+
+        match unsafe { Notify::from_lparam(&mut nmlv as *mut _ as LPARAM) } {
+            Notify::ListViewColumnClick { column: 2 } => (),
+            _ => panic!("not decoded as a column click"),
+        }
+    }
+
+    #[test]
+    fn notify_list_view_item_changed() {
+        let mut nmlv: NMLISTVIEW = unsafe { mem::zeroed() };
+        nmlv.hdr = header(LVN_ITEMCHANGED);
+        nmlv.iItem = 4;
+        nmlv.uOldState = 0;
+        nmlv.uNewState = LVIS_SELECTED;
+
+        match unsafe { Notify::from_lparam(&mut nmlv as *mut _ as LPARAM) } {
+            Notify::ListViewItemChanged { row: Some(4), old_state: 0, new_state } =>
+                assert_eq!(new_state, LVIS_SELECTED),
+            _ => panic!("not decoded as an item change"),
+        }
+    }
+
+    #[test]
+    fn notify_list_view_item_changed_for_all_rows() {
+        let mut nmlv: NMLISTVIEW = unsafe { mem::zeroed() };
+        nmlv.hdr = header(LVN_ITEMCHANGED);
+        nmlv.iItem = -1;
+        nmlv.uOldState = LVIS_SELECTED;
+        nmlv.uNewState = 0;
+
+        match unsafe { Notify::from_lparam(&mut nmlv as *mut _ as LPARAM) } {
+            Notify::ListViewItemChanged { row: None, old_state, new_state: 0 } =>
+                assert_eq!(old_state, LVIS_SELECTED),
+            _ => panic!("not decoded as an item change"),
+        }
+    }
+
+    #[test]
+    fn notify_tree_view_expanding() {
+        let mut nmtv: NMTREEVIEWW = unsafe { mem::zeroed() };
+        nmtv.hdr = header(TVN_ITEMEXPANDINGW);
+        nmtv.action = TVE_EXPAND as UINT;
+        nmtv.itemNew.hItem = 0x42 as HTREEITEM;
+
+        match unsafe { Notify::from_lparam(&mut nmtv as *mut _ as LPARAM) } {
+            Notify::TreeViewExpanding { item, expand: true } => assert_eq!(item, 0x42 as HTREEITEM),
+            _ => panic!("not decoded as expanding"),
+        }
+
+        nmtv.action = TVE_COLLAPSE as UINT;
+
+        match unsafe { Notify::from_lparam(&mut nmtv as *mut _ as LPARAM) } {
+            Notify::TreeViewExpanding { expand: false, .. } => (),
+            _ => panic!("not decoded as collapsing"),
+        }
+    }
+
+    #[test]
+    fn notify_tree_view_sel_changed() {
+        let mut nmtv: NMTREEVIEWW = unsafe { mem::zeroed() };
+        nmtv.hdr = header(TVN_SELCHANGEDW);
+        nmtv.itemOld.hItem = 0x10 as HTREEITEM;
+        nmtv.itemNew.hItem = 0x20 as HTREEITEM;
+
+        match unsafe { Notify::from_lparam(&mut nmtv as *mut _ as LPARAM) } {
+            Notify::TreeViewSelChanged { old, new } => {
+                assert_eq!(old, 0x10 as HTREEITEM);
+                assert_eq!(new, 0x20 as HTREEITEM);
+            },
+            _ => panic!("not decoded as a selection change"),
+        }
+    }
+
+    #[test]
+    fn notify_tree_view_item_changed() {
+        let mut change: NMTVITEMCHANGE = unsafe { mem::zeroed() };
+        change.hdr = header(TVN_ITEMCHANGEDW);
+        change.hItem = 0x42 as HTREEITEM;
+        change.uStateOld = 0x1000;
+        change.uStateNew = 0x2000;
+
+        match unsafe { Notify::from_lparam(&mut change as *mut _ as LPARAM) } {
+            Notify::TreeViewItemChanged { item, old_state: 0x1000, new_state: 0x2000 } =>
+                assert_eq!(item, 0x42 as HTREEITEM),
+            _ => panic!("not decoded as an item change"),
+        }
+    }
+
+    #[test]
+    fn notify_tree_view_begin_label_edit_and_drag() {
+        let mut info: NMTVDISPINFOW = unsafe { mem::zeroed() };
+        info.hdr = header(TVN_BEGINLABELEDITW);
+        info.item.hItem = 0x42 as HTREEITEM;
+
+        match unsafe { Notify::from_lparam(&mut info as *mut _ as LPARAM) } {
+            Notify::TreeViewBeginLabelEdit { item } => assert_eq!(item, 0x42 as HTREEITEM),
+            _ => panic!("not decoded as the start of an edit"),
+        }
+
+        let mut nmtv: NMTREEVIEWW = unsafe { mem::zeroed() };
+        nmtv.hdr = header(TVN_BEGINDRAGW);
+        nmtv.itemNew.hItem = 0x43 as HTREEITEM;
+
+        match unsafe { Notify::from_lparam(&mut nmtv as *mut _ as LPARAM) } {
+            Notify::TreeViewBeginDrag { item } => assert_eq!(item, 0x43 as HTREEITEM),
+            _ => panic!("not decoded as the start of a drag"),
+        }
+    }
+
+    #[test]
+    fn notify_tree_view_end_label_edit() {
+        let mut text: Vec<u16> = "Renamed".encode_utf16().chain(Some(0)).collect();
+
+        let mut info: NMTVDISPINFOW = unsafe { mem::zeroed() };
+        info.hdr = header(TVN_ENDLABELEDITW);
+        info.item.pszText = text.as_mut_ptr();
+
+        match unsafe { Notify::from_lparam(&mut info as *mut _ as LPARAM) } {
+            Notify::TreeViewEndLabelEdit { text: Some(ref text), .. } => assert_eq!(text, "Renamed"),
+            _ => panic!("not decoded as an edited label"),
+        }
+
+        // Cancelled edits have no text.
+        info.item.pszText = ::std::ptr::null_mut();
+
+        match unsafe { Notify::from_lparam(&mut info as *mut _ as LPARAM) } {
+            Notify::TreeViewEndLabelEdit { text: None, .. } => (),
+            _ => panic!("not decoded as a cancelled edit"),
+        }
+    }
+
+    #[test]
+    fn notify_tab_sel_change() {
+        let mut hdr = header(TCN_SELCHANGE);
+
+        match unsafe { Notify::from_lparam(&mut hdr as *mut _ as LPARAM) } {
+            Notify::TabSelChange => (),
+            _ => panic!("not decoded as a tab change"),
+        }
+    }
+
+    #[test]
+    fn notify_other() {
+        let mut hdr = header(NM_CLICK);
+
+        match unsafe { Notify::from_lparam(&mut hdr as *mut _ as LPARAM) } {
+            Notify::Other { code } => assert_eq!(code, NM_CLICK),
+            _ => panic!("decoded an unknown notification"),
+        }
+    }
+
+    #[test]
+    fn command_from_params() {
+        match CommandMsg::from_params(12, 0) {
+            CommandMsg::Menu(12) => (),
+            other => panic!("{:?}", other),
+        }
+
+        match CommandMsg::from_params(1 << 16 | 12, 0) {
+            CommandMsg::Accelerator(12) => (),
+            other => panic!("{:?}", other),
+        }
+
+        match CommandMsg::from_params((BN_CLICKED as WPARAM) << 16 | 300, 0x5678) {
+            CommandMsg::Control { id: 300, code, hwnd } => {
+                assert_eq!(code, BN_CLICKED as u16);
+                assert_eq!(hwnd, 0x5678 as HWND);
+            },
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn index_of_none() {
+        assert_eq!(index(-1), None);
+        assert_eq!(index(0), Some(0));
+        assert_eq!(index(5), Some(5));
+    }
+
+    #[test]
+    fn copy_text_truncates_and_terminates() {
+        let text: Vec<u16> = "Hello".encode_utf16().collect();
+
+        let mut buf = [0xffff; 8];
+        copy_text(&text, &mut buf);
+        assert_eq!(&buf[..6], &[72, 101, 108, 108, 111, 0]);
+        assert_eq!(buf[6], 0xffff);
+
+        let mut buf = [0xffff; 3];
+        copy_text(&text, &mut buf);
+        assert_eq!(buf, [72, 101, 0]);
+
+        let mut buf = [];
+        copy_text(&text, &mut buf);
+    }
+
+    #[test]
+    fn copy_text_stops_at_terminator() {
+        let text = [65, 66, 0, 67];
+        let mut buf = [0xffff; 5];
+
+        copy_text(&text, &mut buf);
+        assert_eq!(buf, [65, 66, 0, 0xffff, 0xffff]);
+    }
+}
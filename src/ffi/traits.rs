@@ -4,7 +4,7 @@ use std::ptr;
 
 use winstr::WinString;
 
-use super::{WindowHandle, RET_ERR};
+use super::WindowHandle;
 
 pub trait WindowEvents: Sized {
     type Data: WindowData;
@@ -17,7 +17,11 @@ pub trait WindowEvents: Sized {
 
     fn on_destroy(_: &WindowHandle<Self>) {}
 
-    fn handle_msg(_: &WindowHandle<Self>, msg: DWORD) -> LRESULT { RET_ERR }
+    /// Handle any other message, including notifications reflected from the parent
+    /// (`OCM_BASE + WM_*`).
+    ///
+    /// Return `None` to pass the message on to the original window procedure.
+    fn handle_msg(_: &WindowHandle<Self>, _msg: UINT, _wparam: WPARAM, _lparam: LPARAM) -> Option<LRESULT> { None }
 }
 
 pub trait WindowData {
@@ -32,6 +36,10 @@ pub trait WindowData {
     fn menu(&self) -> HMENU { ptr::null_mut() }
 
     fn is_subclass(&self) -> bool { true }
+
+    fn style(&self) -> DWORD { WS_OVERLAPPEDWINDOW }
+
+    fn ex_style(&self) -> DWORD { WS_EX_CLIENTEDGE }
 }
 
 pub trait BorrowHandle<W> where W: WindowEvents {
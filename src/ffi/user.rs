@@ -0,0 +1,63 @@
+//! User32 functions and definitions missing from `user32-sys` and `winapi`.
+
+use winapi::*;
+
+pub const WM_MENUCOMMAND: UINT = 0x0126;
+
+pub const MF_BYCOMMAND: UINT = 0x0000;
+pub const MF_BYPOSITION: UINT = 0x0400;
+
+pub const MIM_STYLE: DWORD = 0x0010;
+pub const MIM_APPLYTOSUBMENUS: DWORD = 0x80000000;
+pub const MNS_NOTIFYBYPOS: DWORD = 0x08000000;
+
+pub const MIIM_STATE: UINT = 0x0001;
+pub const MIIM_ID: UINT = 0x0002;
+pub const MIIM_SUBMENU: UINT = 0x0004;
+pub const MIIM_DATA: UINT = 0x0020;
+pub const MIIM_STRING: UINT = 0x0040;
+pub const MIIM_BITMAP: UINT = 0x0080;
+pub const MIIM_FTYPE: UINT = 0x0100;
+
+pub const MFT_STRING: UINT = 0x0000;
+pub const MFT_RADIOCHECK: UINT = 0x0200;
+pub const MFT_SEPARATOR: UINT = 0x0800;
+
+pub const MFS_CHECKED: UINT = 0x0008;
+pub const MFS_DISABLED: UINT = 0x0003;
+
+pub const FVIRTKEY: BYTE = 0x01;
+pub const FSHIFT: BYTE = 0x04;
+pub const FCONTROL: BYTE = 0x08;
+pub const FALT: BYTE = 0x10;
+
+pub const TPM_RIGHTBUTTON: UINT = 0x0002;
+pub const TPM_RETURNCMD: UINT = 0x0100;
+
+#[repr(C)]
+#[allow(non_snake_case)]
+pub struct MENUINFO {
+    pub cbSize: DWORD,
+    pub fMask: DWORD,
+    pub dwStyle: DWORD,
+    pub cyMax: UINT,
+    pub hbrBack: HBRUSH,
+    pub dwContextHelpID: DWORD,
+    pub dwMenuData: ULONG_PTR,
+}
+
+#[link(name = "user32")]
+extern "system" {
+    pub fn GetMenuItemCount(hMenu: HMENU) -> c_int;
+    pub fn GetMenuItemInfoW(
+        hmenu: HMENU, item: UINT, fByPosition: BOOL, lpmii: LPMENUITEMINFOW
+    ) -> BOOL;
+    pub fn SetMenuInfo(hMenu: HMENU, lpmi: *const MENUINFO) -> BOOL;
+    pub fn SetMenuItemInfoW(
+        hmenu: HMENU, item: UINT, fByPosition: BOOL, lpmii: LPCMENUITEMINFOW
+    ) -> BOOL;
+    pub fn TrackPopupMenuEx(
+        hMenu: HMENU, uFlags: UINT, x: c_int, y: c_int, hwnd: HWND, lptpm: LPVOID
+    ) -> BOOL;
+    pub fn TranslateAcceleratorW(hWnd: HWND, hAccTable: HACCEL, lpMsg: LPMSG) -> c_int;
+}
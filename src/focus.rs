@@ -0,0 +1,111 @@
+//! Keyboard focus: focus events, the Tab order and dialog-style keyboard navigation.
+//!
+//! `start()` passes keyboard messages through `IsDialogMessageW()` for top-level windows
+//! with `WS_EX_CONTROLPARENT`, like every `Window`, so Tab and Shift+Tab move the focus
+//! between controls with `WS_TABSTOP` and the arrow keys within groups. Both go in Z order,
+//! which `AbsWindow::set_tab_index()` rearranges.
+
+use user32;
+
+use winapi::*;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+// `GetAncestor()` flag for the top-level window.
+const GA_ROOT: UINT = 2;
+
+#[derive(Default)]
+struct Focus {
+    on_focus: Option<Box<FnMut()>>,
+    on_blur: Option<Box<FnMut()>>,
+    tab_index: Option<u32>,
+}
+
+thread_local!(static FOCUS: RefCell<HashMap<HWND, Focus>> = RefCell::new(HashMap::new()));
+
+fn update<F: FnOnce(&mut Focus)>(hwnd: HWND, f: F) {
+    FOCUS.with(|focus| f(focus.borrow_mut().entry(hwnd).or_insert_with(Focus::default)));
+}
+
+pub fn set_on_focus(hwnd: HWND, on_focus: Box<FnMut()>) {
+    update(hwnd, |focus| focus.on_focus = Some(on_focus));
+}
+
+pub fn set_on_blur(hwnd: HWND, on_blur: Box<FnMut()>) {
+    update(hwnd, |focus| focus.on_blur = Some(on_blur));
+}
+
+/// Call the handler for `hwnd` getting the focus, or losing it if `focused` is false.
+pub fn notify(hwnd: HWND, focused: bool) {
+    let take = |focus: &mut Focus| if focused { focus.on_focus.take() } else { focus.on_blur.take() };
+    let handler = FOCUS.with(|focus| focus.borrow_mut().get_mut(&hwnd).and_then(take));
+
+    if let Some(mut handler) = handler {
+        handler();
+
+        // Unless the handler replaced itself.
+        FOCUS.with(|focus| if let Some(focus) = focus.borrow_mut().get_mut(&hwnd) {
+            let slot = if focused { &mut focus.on_focus } else { &mut focus.on_blur };
+
+            if slot.is_none() {
+                *slot = Some(handler);
+            }
+        });
+    }
+}
+
+pub fn tab_index(hwnd: HWND) -> Option<u32> {
+    FOCUS.with(|focus| focus.borrow().get(&hwnd).and_then(|focus| focus.tab_index))
+}
+
+/// Move `hwnd` to `index` in the Tab order of its siblings, by putting the siblings with an
+/// index first in the Z order, sorted by it.
+pub fn set_tab_index(hwnd: HWND, index: u32) {
+    update(hwnd, |focus| focus.tab_index = Some(index));
+
+    unsafe {
+        let parent = user32::GetParent(hwnd);
+        let mut indexed = vec![];
+        let mut child = user32::GetWindow(parent, GW_CHILD);
+
+        while !child.is_null() {
+            if let Some(index) = tab_index(child) {
+                indexed.push((index, child));
+            }
+
+            child = user32::GetWindow(child, GW_HWNDNEXT);
+        }
+
+        // Stable, so siblings with the same index keep their order.
+        indexed.sort_by_key(|&(index, _)| index);
+
+        let mut after = HWND_TOP;
+
+        for (_, child) in indexed {
+            user32::SetWindowPos(child, after, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE);
+            after = child;
+        }
+    }
+}
+
+/// Let the top-level window of `msg` handle it if it's for keyboard navigation. Returns
+/// whether it was handled, in which case it must not be dispatched.
+pub unsafe fn handle_navigation(msg: &mut MSG) -> bool {
+    if msg.hwnd.is_null() {
+        return false;
+    }
+
+    let root = user32::GetAncestor(msg.hwnd, GA_ROOT);
+
+    if root.is_null() {
+        return false;
+    }
+
+    let ex_style = user32::GetWindowLongPtrW(root, GWL_EXSTYLE) as DWORD;
+    ex_style & WS_EX_CONTROLPARENT != 0 && user32::IsDialogMessageW(root, msg) != 0
+}
+
+pub fn forget(hwnd: HWND) {
+    FOCUS.with(|focus| focus.borrow_mut().remove(&hwnd));
+}
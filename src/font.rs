@@ -0,0 +1,227 @@
+//! Fonts for the text in controls.
+//!
+//! Every control gets the font of the nearest window it is inside that has one set with
+//! `Window::set_font()`, or the system message font (the one used in dialogs) if none does.
+//! Fonts are sized in points, so they're created anew for each DPI.
+
+use {gdi32, user32};
+
+use dpi::Dpi;
+
+use winapi::*;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::{cmp, mem};
+
+/// The thickness of the strokes of a font, from 100 to 900.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Weight(pub u16);
+
+impl Weight {
+    pub fn normal() -> Weight {
+        Weight(400)
+    }
+
+    pub fn bold() -> Weight {
+        Weight(700)
+    }
+}
+
+impl Default for Weight {
+    fn default() -> Self {
+        Weight::normal()
+    }
+}
+
+/// A description of a font.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Font {
+    pub family: String,
+    /// The height in points.
+    pub size: f32,
+    pub weight: Weight,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl Font {
+    pub fn new<T: Into<String>>(family: T, size: f32) -> Font {
+        Font {
+            family: family.into(),
+            size: size,
+            weight: Weight::normal(),
+            italic: false,
+            underline: false,
+        }
+    }
+
+    /// The font Windows uses for message boxes and dialogs, as set by the user.
+    pub fn message_font() -> Font {
+        unsafe {
+            let mut metrics: NONCLIENTMETRICSW = mem::zeroed();
+            metrics.cbSize = mem::size_of::<NONCLIENTMETRICSW>() as UINT;
+
+            let ok = user32::SystemParametersInfoW(
+                SPI_GETNONCLIENTMETRICS, metrics.cbSize, &mut metrics as *mut _ as PVOID, 0
+            );
+
+            if ok == 0 {
+                return Font::new("Segoe UI", 9.0);
+            }
+
+            Font::from_log_font(&metrics.lfMessageFont, Dpi::system())
+        }
+    }
+
+    pub fn weight(mut self, weight: Weight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    pub fn underline(mut self, underline: bool) -> Self {
+        self.underline = underline;
+        self
+    }
+
+    /// Get the GDI font for this description at `dpi`, creating it if it isn't in this
+    /// thread's cache yet.
+    pub fn handle(&self, dpi: Dpi) -> Rc<FontHandle> {
+        let key = CacheKey {
+            family: self.family.clone(),
+            hundredths: (self.size * 100.0).round() as i32,
+            weight: self.weight,
+            italic: self.italic,
+            underline: self.underline,
+            dpi: dpi,
+        };
+
+        FONT_CACHE.with(|cache| {
+            cache.borrow_mut().entry(key).or_insert_with(|| Rc::new(self.create(dpi))).clone()
+        })
+    }
+
+    fn create(&self, dpi: Dpi) -> FontHandle {
+        unsafe {
+            let mut log_font: LOGFONTW = mem::zeroed();
+
+            // A negative height is the size of the characters, not including internal leading.
+            log_font.lfHeight = -((self.size * dpi.0 as f32 / 72.0).round() as LONG);
+            log_font.lfWeight = self.weight.0 as LONG;
+            log_font.lfItalic = self.italic as BYTE;
+            log_font.lfUnderline = self.underline as BYTE;
+            log_font.lfCharSet = DEFAULT_CHARSET as BYTE;
+
+            // Leave room for the NUL terminator.
+            let family: Vec<u16> = self.family.encode_utf16().take(LF_FACESIZE - 1).collect();
+            log_font.lfFaceName[..family.len()].copy_from_slice(&family);
+
+            FontHandle(gdi32::CreateFontIndirectW(&log_font))
+        }
+    }
+
+    fn from_log_font(log_font: &LOGFONTW, dpi: Dpi) -> Font {
+        let len = log_font.lfFaceName.iter().position(|&c| c == 0).unwrap_or(LF_FACESIZE);
+
+        Font {
+            family: String::from_utf16_lossy(&log_font.lfFaceName[..len]),
+            size: log_font.lfHeight.abs() as f32 * 72.0 / dpi.0 as f32,
+            weight: Weight(cmp::max(log_font.lfWeight, 0) as u16),
+            italic: log_font.lfItalic != 0,
+            underline: log_font.lfUnderline != 0,
+        }
+    }
+}
+
+/// An owned GDI font, deleted when dropped.
+#[derive(Debug)]
+pub struct FontHandle(HFONT);
+
+impl FontHandle {
+    pub fn hfont(&self) -> HFONT {
+        self.0
+    }
+}
+
+impl Drop for FontHandle {
+    fn drop(&mut self) {
+        unsafe {
+            gdi32::DeleteObject(self.0 as HGDIOBJ);
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    family: String,
+    hundredths: i32,
+    weight: Weight,
+    italic: bool,
+    underline: bool,
+    dpi: Dpi,
+}
+
+thread_local! {
+    static FONT_CACHE: RefCell<HashMap<CacheKey, Rc<FontHandle>>> = RefCell::new(HashMap::new());
+    // Windows with a font set explicitly.
+    static WINDOW_FONTS: RefCell<HashMap<HWND, Font>> = RefCell::new(HashMap::new());
+    static MESSAGE_FONT: Font = Font::message_font();
+}
+
+/// Set the font inherited by the descendants of `hwnd`, or go back to inheriting it.
+#[doc(hidden)]
+pub fn set_window_font(hwnd: HWND, font: Option<Font>) {
+    WINDOW_FONTS.with(|fonts| match font {
+        Some(font) => fonts.borrow_mut().insert(hwnd, font),
+        None => fonts.borrow_mut().remove(&hwnd),
+    });
+}
+
+/// The font `hwnd` gets: its own, the closest ancestor's, or the message font.
+#[doc(hidden)]
+pub fn font_for(hwnd: HWND) -> Font {
+    WINDOW_FONTS.with(|fonts| {
+        let fonts = fonts.borrow();
+        let mut hwnd = hwnd;
+
+        while !hwnd.is_null() {
+            if let Some(font) = fonts.get(&hwnd) {
+                return font.clone();
+            }
+
+            hwnd = unsafe { user32::GetParent(hwnd) };
+        }
+
+        MESSAGE_FONT.with(Font::clone)
+    })
+}
+
+/// Give `hwnd` the font it should have at its current DPI.
+#[doc(hidden)]
+pub fn apply(hwnd: HWND) {
+    let handle = font_for(hwnd).handle(Dpi::of_hwnd(hwnd));
+
+    unsafe {
+        user32::SendMessageW(hwnd, WM_SETFONT, handle.hfont() as WPARAM, TRUE as LPARAM);
+    }
+}
+
+/// Give every descendant of `hwnd` the font it should have, e.g. after a font was set or
+/// the DPI changed.
+#[doc(hidden)]
+pub fn apply_to_children(hwnd: HWND) {
+    unsafe extern "system" fn apply_proc(child: HWND, _: LPARAM) -> BOOL {
+        apply(child);
+        TRUE
+    }
+
+    unsafe {
+        user32::EnumChildWindows(hwnd, Some(apply_proc), 0);
+    }
+}
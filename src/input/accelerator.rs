@@ -0,0 +1,313 @@
+//! Keyboard shortcuts, written like `"Ctrl+Shift+S"`.
+
+use super::{Key, Modifiers};
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A key pressed with modifiers to trigger a command, bound with `Window::add_accelerator()`.
+///
+/// Parsed from and formatted as the modifiers followed by the key, joined with `+`, e.g.
+/// `Ctrl+Shift+S`, `Alt+F4` or `Ctrl++`. Parsing ignores case and accepts `Control` for
+/// `Ctrl`, and keys by the name of their `Key` variant as well as the names shown here.
+/// Punctuation keys are those of US keyboards.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Accelerator {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl Accelerator {
+    pub fn new(key: Key, modifiers: Modifiers) -> Accelerator {
+        Accelerator { key: key, modifiers: modifiers }
+    }
+}
+
+/// Names shown for keys, where they differ from the `Key` variant.
+const KEY_NAMES: &'static [(Key, &'static str)] = &[
+    (Key::Escape, "Esc"),
+    (Key::Delete, "Del"),
+    (Key::Insert, "Ins"),
+    (Key::PageUp, "PgUp"),
+    (Key::PageDown, "PgDn"),
+    (Key::PrintScreen, "PrtSc"),
+    (Key::OemPlus, "+"),
+    (Key::OemMinus, "-"),
+    (Key::OemComma, ","),
+    (Key::OemPeriod, "."),
+    (Key::Oem1, ";"),
+    (Key::Oem2, "/"),
+    (Key::Oem3, "`"),
+    (Key::Oem4, "["),
+    (Key::Oem5, "\\"),
+    (Key::Oem6, "]"),
+    (Key::Oem7, "'"),
+];
+
+/// More names accepted when parsing.
+const KEY_ALIASES: &'static [(Key, &'static str)] = &[
+    (Key::Enter, "Return"),
+    (Key::OemPlus, "Plus"),
+    (Key::OemPlus, "="),
+    (Key::OemMinus, "Minus"),
+    (Key::Numpad0, "Num0"),
+    (Key::Numpad1, "Num1"),
+    (Key::Numpad2, "Num2"),
+    (Key::Numpad3, "Num3"),
+    (Key::Numpad4, "Num4"),
+    (Key::Numpad5, "Num5"),
+    (Key::Numpad6, "Num6"),
+    (Key::Numpad7, "Num7"),
+    (Key::Numpad8, "Num8"),
+    (Key::Numpad9, "Num9"),
+];
+
+fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+
+    // Digits, since their variants are `Digit0` and so on.
+    if let (Some(c @ '0'...'9'), None) = (chars.next(), chars.next()) {
+        return Some(Key::from_vk(c as u8));
+    }
+
+    // Keys without a name, as formatted.
+    if name.starts_with("0x") || name.starts_with("0X") {
+        return u8::from_str_radix(&name[2..], 16).ok().map(Key::from_vk);
+    }
+
+    let named = KEY_NAMES.iter().chain(KEY_ALIASES)
+        .find(|&&(_, key_name)| key_name.eq_ignore_ascii_case(name))
+        .map(|&(key, _)| key);
+
+    named.or_else(|| {
+        (0..256).map(|vk| Key::from_vk(vk as u8))
+            .find(|key| match *key {
+                Key::Other(_) => false,
+                key => format!("{:?}", key).eq_ignore_ascii_case(name),
+            })
+    })
+}
+
+/// Whether `key` is only ever held down with another key, so it can't be the key of a
+/// shortcut.
+fn is_modifier(key: Key) -> bool {
+    match key {
+        Key::Shift | Key::Control | Key::Alt |
+        Key::LShift | Key::RShift | Key::LControl | Key::RControl | Key::LAlt | Key::RAlt => true,
+        _ => false,
+    }
+}
+
+/// Why a string isn't an `Accelerator`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseAcceleratorError {
+    /// There was nothing after the last `+`.
+    MissingKey,
+    /// The key has no name this module knows.
+    UnknownKey(String),
+    /// A modifier isn't Ctrl, Shift or Alt.
+    UnknownModifier(String),
+    /// The key is itself a modifier, as in `Ctrl+Shift`.
+    ModifierAsKey(String),
+}
+
+impl fmt::Display for ParseAcceleratorError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseAcceleratorError::MissingKey => fmt.write_str(self.description()),
+            ParseAcceleratorError::UnknownKey(ref name) |
+            ParseAcceleratorError::UnknownModifier(ref name) |
+            ParseAcceleratorError::ModifierAsKey(ref name) =>
+                write!(fmt, "{}: `{}`", self.description(), name),
+        }
+    }
+}
+
+impl Error for ParseAcceleratorError {
+    fn description(&self) -> &str {
+        match *self {
+            ParseAcceleratorError::MissingKey => "the shortcut has no key",
+            ParseAcceleratorError::UnknownKey(_) => "unknown key",
+            ParseAcceleratorError::UnknownModifier(_) => "unknown modifier",
+            ParseAcceleratorError::ModifierAsKey(_) => "the key is a modifier",
+        }
+    }
+}
+
+impl FromStr for Accelerator {
+    type Err = ParseAcceleratorError;
+
+    fn from_str(s: &str) -> Result<Accelerator, ParseAcceleratorError> {
+        let mut names: Vec<&str> = s.split('+').map(str::trim).collect();
+
+        // The key can be `+` itself, which leaves two empty names at the end.
+        let key = if names.len() >= 2 && names[names.len() - 2..].iter().all(|name| name.is_empty()) {
+            names.pop();
+            names.pop();
+            "+"
+        } else {
+            names.pop().unwrap()
+        };
+
+        if key.is_empty() {
+            return Err(ParseAcceleratorError::MissingKey);
+        }
+
+        let mut accelerator = Accelerator::new(Key::Other(0), Modifiers::none());
+
+        for &name in &names {
+            let modifier = match &*name.to_ascii_lowercase() {
+                "ctrl" | "control" => &mut accelerator.modifiers.ctrl,
+                "shift" => &mut accelerator.modifiers.shift,
+                "alt" => &mut accelerator.modifiers.alt,
+                _ => return Err(ParseAcceleratorError::UnknownModifier(name.to_string())),
+            };
+
+            *modifier = true;
+        }
+
+        accelerator.key = match &*key.to_ascii_lowercase() {
+            "ctrl" | "control" | "shift" | "alt" =>
+                return Err(ParseAcceleratorError::ModifierAsKey(key.to_string())),
+            _ => match parse_key(key) {
+                Some(key) if !is_modifier(key) => key,
+                Some(_) => return Err(ParseAcceleratorError::ModifierAsKey(key.to_string())),
+                None => return Err(ParseAcceleratorError::UnknownKey(key.to_string())),
+            },
+        };
+
+        Ok(accelerator)
+    }
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.alt, "Alt"),
+        ];
+
+        for &(_, name) in modifiers.iter().filter(|&&(down, _)| down) {
+            try!(write!(fmt, "{}+", name));
+        }
+
+        let vk = self.key.vk();
+
+        match KEY_NAMES.iter().find(|&&(key, _)| key == self.key) {
+            Some(&(_, name)) => fmt.write_str(name),
+            None if vk >= b'0' && vk <= b'9' => write!(fmt, "{}", vk as char),
+            None => match self.key {
+                Key::Other(vk) => write!(fmt, "0x{:02X}", vk),
+                key => write!(fmt, "{:?}", key),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Accelerator, ParseAcceleratorError> {
+        s.parse()
+    }
+
+    fn modifiers(ctrl: bool, shift: bool, alt: bool) -> Modifiers {
+        Modifiers { ctrl: ctrl, shift: shift, alt: alt }
+    }
+
+    fn ctrl() -> Modifiers {
+        modifiers(true, false, false)
+    }
+
+    #[test]
+    fn parse_modifiers() {
+        assert_eq!(parse("Ctrl+Shift+S"), Ok(Accelerator::new(Key::S, modifiers(true, true, false))));
+        assert_eq!(parse("alt+f4"), Ok(Accelerator::new(Key::F4, modifiers(false, false, true))));
+        assert_eq!(parse(" Control + S "), Ok(Accelerator::new(Key::S, ctrl())));
+        assert_eq!(parse("Esc"), Ok(Accelerator::new(Key::Escape, Modifiers::none())));
+    }
+
+    #[test]
+    fn parse_keys() {
+        let key = |s| parse(s).map(|accelerator| accelerator.key);
+
+        assert_eq!(key("Ctrl++"), Ok(Key::OemPlus));
+        assert_eq!(key("+"), Ok(Key::OemPlus));
+        assert_eq!(key(" + "), Ok(Key::OemPlus));
+        assert_eq!(key("Ctrl + +"), Ok(Key::OemPlus));
+        assert_eq!(key("Ctrl+Shift + +"), Ok(Key::OemPlus));
+        assert_eq!(key("Ctrl+="), Ok(Key::OemPlus));
+        assert_eq!(key("Ctrl+1"), Ok(Key::Digit1));
+        assert_eq!(key("Ctrl+pgdn"), Ok(Key::PageDown));
+        assert_eq!(key("Ctrl+PageDown"), Ok(Key::PageDown));
+        assert_eq!(key("Return"), Ok(Key::Enter));
+        assert_eq!(key("Num5"), Ok(Key::Numpad5));
+        assert_eq!(key("VolumeUp"), Ok(Key::VolumeUp));
+        assert_eq!(key("Ctrl+0x07"), Ok(Key::Other(0x07)));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse("Ctrl+"), Err(ParseAcceleratorError::MissingKey));
+        assert_eq!(parse(""), Err(ParseAcceleratorError::MissingKey));
+        let unknown_key = |name: &str| Err(ParseAcceleratorError::UnknownKey(name.to_string()));
+
+        assert_eq!(parse("Hyper+A"), Err(ParseAcceleratorError::UnknownModifier("Hyper".to_string())));
+        assert_eq!(parse("Ctrl+Foo"), unknown_key("Foo"));
+        assert_eq!(parse("Ctrl+0xZZ"), unknown_key("0xZZ"));
+        assert_eq!(parse("Ctrl++S"), Err(ParseAcceleratorError::UnknownModifier(String::new())));
+    }
+
+    #[test]
+    fn modifiers_are_not_keys() {
+        let modifier_as_key = |name: &str| Err(ParseAcceleratorError::ModifierAsKey(name.to_string()));
+
+        assert_eq!(parse("Ctrl+Shift"), modifier_as_key("Shift"));
+        assert_eq!(parse("Ctrl"), modifier_as_key("Ctrl"));
+        assert_eq!(parse("Alt+control"), modifier_as_key("control"));
+        assert_eq!(parse("Shift+LShift"), modifier_as_key("LShift"));
+        assert_eq!(parse("RAlt"), modifier_as_key("RAlt"));
+        assert_eq!(parse("Ctrl+0x10"), modifier_as_key("0x10"));
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(ParseAcceleratorError::MissingKey.to_string(), "the shortcut has no key");
+        assert_eq!(ParseAcceleratorError::UnknownModifier("Hyper".to_string()).to_string(),
+                   "unknown modifier: `Hyper`");
+        assert_eq!(ParseAcceleratorError::UnknownKey("Foo".to_string()).to_string(),
+                   "unknown key: `Foo`");
+        assert_eq!(ParseAcceleratorError::ModifierAsKey("Shift".to_string()).to_string(),
+                   "the key is a modifier: `Shift`");
+    }
+
+    #[test]
+    fn display() {
+        let all = modifiers(true, true, true);
+
+        assert_eq!(Accelerator::new(Key::Enter, all).to_string(), "Ctrl+Shift+Alt+Enter");
+        assert_eq!(Accelerator::new(Key::OemPlus, ctrl()).to_string(), "Ctrl++");
+        assert_eq!(Accelerator::new(Key::Delete, Modifiers::none()).to_string(), "Del");
+        assert_eq!(Accelerator::new(Key::Digit1, ctrl()).to_string(), "Ctrl+1");
+        assert_eq!(Accelerator::new(Key::Other(0x07), ctrl()).to_string(), "Ctrl+0x07");
+    }
+
+    #[test]
+    fn round_trip() {
+        let shortcuts = [
+            "Ctrl+Shift+S", "Alt+F4", "Ctrl++", "Shift+Del", "Ctrl+PgDn", "Ctrl+1", "Ctrl+0x07",
+            "Ctrl+Shift+Alt+Enter", "Esc", "VolumeUp", "Ctrl+\\", "Ctrl+`",
+        ];
+
+        for s in &shortcuts {
+            assert_eq!(parse(s).unwrap().to_string(), *s);
+        }
+
+        // Modifiers are always shown in the same order.
+        assert_eq!(parse("Alt+Shift+Ctrl+Return").unwrap().to_string(), "Ctrl+Shift+Alt+Enter");
+        assert_eq!(parse("Control + =").unwrap().to_string(), "Ctrl++");
+    }
+}
@@ -0,0 +1,440 @@
+//! Keyboard input: keys going down and up, and the text they type.
+
+use super::Modifiers;
+
+#[cfg(windows)] use winapi::*;
+
+use std::char;
+
+macro_rules! keys {
+    ($($(#[$attr:meta])* $name:ident = $vk:tt,)*) => {
+        /// A virtual key.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum Key {
+            $($(#[$attr])* $name,)*
+            /// A virtual key code without a name.
+            Other(u8),
+        }
+
+        impl Key {
+            pub fn from_vk(vk: u8) -> Key {
+                match vk {
+                    $($vk => Key::$name,)*
+                    _ => Key::Other(vk),
+                }
+            }
+
+            /// The virtual key code, `VK_*`.
+            pub fn vk(self) -> u8 {
+                match self {
+                    $(Key::$name => $vk,)*
+                    Key::Other(vk) => vk,
+                }
+            }
+        }
+    }
+}
+
+keys! {
+    LButton = 0x01,
+    RButton = 0x02,
+    /// Ctrl+Break.
+    Cancel = 0x03,
+    MButton = 0x04,
+    XButton1 = 0x05,
+    XButton2 = 0x06,
+    Backspace = 0x08,
+    Tab = 0x09,
+    Clear = 0x0c,
+    Enter = 0x0d,
+    Shift = 0x10,
+    Control = 0x11,
+    Alt = 0x12,
+    Pause = 0x13,
+    CapsLock = 0x14,
+    /// Also Hangul.
+    Kana = 0x15,
+    ImeOn = 0x16,
+    Junja = 0x17,
+    Final = 0x18,
+    /// Also Hanja.
+    Kanji = 0x19,
+    ImeOff = 0x1a,
+    Escape = 0x1b,
+    Convert = 0x1c,
+    NonConvert = 0x1d,
+    Accept = 0x1e,
+    ModeChange = 0x1f,
+    Space = 0x20,
+    PageUp = 0x21,
+    PageDown = 0x22,
+    End = 0x23,
+    Home = 0x24,
+    Left = 0x25,
+    Up = 0x26,
+    Right = 0x27,
+    Down = 0x28,
+    Select = 0x29,
+    Print = 0x2a,
+    Execute = 0x2b,
+    PrintScreen = 0x2c,
+    Insert = 0x2d,
+    Delete = 0x2e,
+    Help = 0x2f,
+    Digit0 = 0x30,
+    Digit1 = 0x31,
+    Digit2 = 0x32,
+    Digit3 = 0x33,
+    Digit4 = 0x34,
+    Digit5 = 0x35,
+    Digit6 = 0x36,
+    Digit7 = 0x37,
+    Digit8 = 0x38,
+    Digit9 = 0x39,
+    A = 0x41,
+    B = 0x42,
+    C = 0x43,
+    D = 0x44,
+    E = 0x45,
+    F = 0x46,
+    G = 0x47,
+    H = 0x48,
+    I = 0x49,
+    J = 0x4a,
+    K = 0x4b,
+    L = 0x4c,
+    M = 0x4d,
+    N = 0x4e,
+    O = 0x4f,
+    P = 0x50,
+    Q = 0x51,
+    R = 0x52,
+    S = 0x53,
+    T = 0x54,
+    U = 0x55,
+    V = 0x56,
+    W = 0x57,
+    X = 0x58,
+    Y = 0x59,
+    Z = 0x5a,
+    LWin = 0x5b,
+    RWin = 0x5c,
+    /// The context menu key.
+    Apps = 0x5d,
+    Sleep = 0x5f,
+    Numpad0 = 0x60,
+    Numpad1 = 0x61,
+    Numpad2 = 0x62,
+    Numpad3 = 0x63,
+    Numpad4 = 0x64,
+    Numpad5 = 0x65,
+    Numpad6 = 0x66,
+    Numpad7 = 0x67,
+    Numpad8 = 0x68,
+    Numpad9 = 0x69,
+    Multiply = 0x6a,
+    Add = 0x6b,
+    Separator = 0x6c,
+    Subtract = 0x6d,
+    Decimal = 0x6e,
+    Divide = 0x6f,
+    F1 = 0x70,
+    F2 = 0x71,
+    F3 = 0x72,
+    F4 = 0x73,
+    F5 = 0x74,
+    F6 = 0x75,
+    F7 = 0x76,
+    F8 = 0x77,
+    F9 = 0x78,
+    F10 = 0x79,
+    F11 = 0x7a,
+    F12 = 0x7b,
+    F13 = 0x7c,
+    F14 = 0x7d,
+    F15 = 0x7e,
+    F16 = 0x7f,
+    F17 = 0x80,
+    F18 = 0x81,
+    F19 = 0x82,
+    F20 = 0x83,
+    F21 = 0x84,
+    F22 = 0x85,
+    F23 = 0x86,
+    F24 = 0x87,
+    NumLock = 0x90,
+    ScrollLock = 0x91,
+    LShift = 0xa0,
+    RShift = 0xa1,
+    LControl = 0xa2,
+    RControl = 0xa3,
+    LAlt = 0xa4,
+    RAlt = 0xa5,
+    BrowserBack = 0xa6,
+    BrowserForward = 0xa7,
+    BrowserRefresh = 0xa8,
+    BrowserStop = 0xa9,
+    BrowserSearch = 0xaa,
+    BrowserFavorites = 0xab,
+    BrowserHome = 0xac,
+    VolumeMute = 0xad,
+    VolumeDown = 0xae,
+    VolumeUp = 0xaf,
+    MediaNextTrack = 0xb0,
+    MediaPrevTrack = 0xb1,
+    MediaStop = 0xb2,
+    MediaPlayPause = 0xb3,
+    LaunchMail = 0xb4,
+    LaunchMediaSelect = 0xb5,
+    LaunchApp1 = 0xb6,
+    LaunchApp2 = 0xb7,
+    /// `;:` on US keyboards.
+    Oem1 = 0xba,
+    /// `=+` on any keyboard.
+    OemPlus = 0xbb,
+    OemComma = 0xbc,
+    OemMinus = 0xbd,
+    OemPeriod = 0xbe,
+    /// `/?` on US keyboards.
+    Oem2 = 0xbf,
+    /// `` `~ `` on US keyboards.
+    Oem3 = 0xc0,
+    /// `[{` on US keyboards.
+    Oem4 = 0xdb,
+    /// `\|` on US keyboards.
+    Oem5 = 0xdc,
+    /// `]}` on US keyboards.
+    Oem6 = 0xdd,
+    /// `'"` on US keyboards.
+    Oem7 = 0xde,
+    Oem8 = 0xdf,
+    /// `<>` or `\|` next to the left Shift on non-US keyboards.
+    Oem102 = 0xe2,
+    /// Sent instead of the key while an IME processes it.
+    ProcessKey = 0xe5,
+    Packet = 0xe7,
+    Attn = 0xf6,
+    CrSel = 0xf7,
+    ExSel = 0xf8,
+    EraseEof = 0xf9,
+    Play = 0xfa,
+    Zoom = 0xfb,
+    Pa1 = 0xfd,
+    OemClear = 0xfe,
+}
+
+/// What the flags in the `lparam` of keyboard messages say about a key.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeyFlags {
+    /// How many times the key repeated because it was held down, for this one message.
+    pub repeat_count: u16,
+    pub scan_code: u8,
+    /// Whether the key is one of the right-hand keys duplicated on enhanced keyboards, like
+    /// the right Alt or the arrow keys outside the numeric keypad.
+    pub extended: bool,
+    /// Whether Alt was held down.
+    pub alt: bool,
+    /// Whether the key was already down, i.e. this is an auto-repeat.
+    pub was_down: bool,
+    /// Whether the key is being released.
+    pub released: bool,
+}
+
+impl KeyFlags {
+    /// The flags in the low 32 bits of an `lparam`.
+    pub fn from_bits(bits: u32) -> KeyFlags {
+        KeyFlags {
+            repeat_count: (bits & 0xffff) as u16,
+            scan_code: (bits >> 16 & 0xff) as u8,
+            extended: bits & 1 << 24 != 0,
+            alt: bits & 1 << 29 != 0,
+            was_down: bits & 1 << 30 != 0,
+            released: bits & 1 << 31 != 0,
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn from_lparam(lparam: LPARAM) -> KeyFlags {
+        KeyFlags::from_bits(lparam as u32)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyEventKind {
+    Down,
+    Up,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub kind: KeyEventKind,
+    pub key: Key,
+    pub scan_code: u8,
+    /// How many times the key repeated because it was held down, for this one event.
+    pub repeat_count: u16,
+    /// Whether this is an auto-repeat of a key that was already down.
+    pub repeat: bool,
+    /// See `KeyFlags::extended`.
+    pub extended: bool,
+    /// Whether this is a system key, which goes to the menu bar: one pressed with Alt held,
+    /// or F10.
+    pub system: bool,
+    pub modifiers: Modifiers,
+}
+
+// The keyboard messages, so that they can be decoded without winapi.
+const WM_KEYDOWN: u32 = 0x0100;
+const WM_KEYUP: u32 = 0x0101;
+const WM_SYSKEYDOWN: u32 = 0x0104;
+const WM_SYSKEYUP: u32 = 0x0105;
+
+impl KeyEvent {
+    /// Decode a keyboard message, sent while `modifiers` were held.
+    #[doc(hidden)]
+    pub fn decode(msg: u32, wparam: usize, lparam: isize, modifiers: Modifiers) -> Option<KeyEvent> {
+        let (kind, system) = match msg {
+            WM_KEYDOWN => (KeyEventKind::Down, false),
+            WM_KEYUP => (KeyEventKind::Up, false),
+            WM_SYSKEYDOWN => (KeyEventKind::Down, true),
+            WM_SYSKEYUP => (KeyEventKind::Up, true),
+            _ => return None,
+        };
+
+        let flags = KeyFlags::from_bits(lparam as u32);
+
+        Some(KeyEvent {
+            kind: kind,
+            key: Key::from_vk(wparam as u8),
+            scan_code: flags.scan_code,
+            repeat_count: flags.repeat_count,
+            repeat: kind == KeyEventKind::Down && flags.was_down,
+            extended: flags.extended,
+            system: system,
+            modifiers: modifiers,
+        })
+    }
+}
+
+/// A character typed, after the keyboard layout and any IME have turned keys into text.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TextInput {
+    pub ch: char,
+    /// How many times the character repeated because its key was held down.
+    pub repeat_count: u16,
+}
+
+/// Turns the UTF-16 code units of `WM_CHAR` into characters, keeping the first half of a
+/// surrogate pair until the second arrives.
+#[derive(Copy, Clone, Debug, Default)]
+#[doc(hidden)]
+pub struct Utf16Chars {
+    high_surrogate: Option<u16>,
+}
+
+impl Utf16Chars {
+    /// The character `unit` finishes, if any. A lone second half of a pair becomes U+FFFD,
+    /// and a first half that isn't followed by a second one is dropped.
+    pub fn push(&mut self, unit: u16) -> Option<char> {
+        match (self.high_surrogate.take(), unit) {
+            (_, 0xd800...0xdbff) => {
+                self.high_surrogate = Some(unit);
+                None
+            },
+            (Some(high), 0xdc00...0xdfff) => {
+                let code = 0x10000 + ((high as u32 - 0xd800) << 10) + (unit as u32 - 0xdc00);
+                char::from_u32(code)
+            },
+            (None, 0xdc00...0xdfff) => Some('\u{fffd}'),
+            (_, _) => char::from_u32(unit as u32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_keys() {
+        assert_eq!(Key::from_vk(0x0d), Key::Enter);
+        assert_eq!(Key::from_vk(0xbb), Key::OemPlus);
+        assert_eq!(Key::from_vk(0x07), Key::Other(0x07));
+
+        for vk in 0..256 {
+            assert_eq!(Key::from_vk(vk as u8).vk(), vk as u8);
+        }
+    }
+
+    #[test]
+    fn utf16_chars() {
+        let mut chars = Utf16Chars::default();
+
+        assert_eq!(chars.push('a' as u16), Some('a'));
+        assert_eq!(chars.push(0xe9), Some('\u{e9}'));
+        assert_eq!(chars.push(0xd83d), None);
+        assert_eq!(chars.push(0xde00), Some('\u{1f600}'));
+    }
+
+    #[test]
+    fn unpaired_surrogates() {
+        let mut chars = Utf16Chars::default();
+
+        assert_eq!(chars.push(0xde00), Some('\u{fffd}'));
+
+        // A first half followed by another one is dropped.
+        assert_eq!(chars.push(0xd83d), None);
+        assert_eq!(chars.push(0xd83c), None);
+        assert_eq!(chars.push(0xdf89), Some('\u{1f389}'));
+
+        // As is one followed by anything else.
+        assert_eq!(chars.push(0xd83d), None);
+        assert_eq!(chars.push('b' as u16), Some('b'));
+        assert_eq!(chars.push(0xde00), Some('\u{fffd}'));
+    }
+
+    #[test]
+    fn key_flags() {
+        // Right Alt going down for the third time in one message, with Alt held.
+        let bits = 3 | 0x38 << 16 | 1 << 24 | 1 << 29 | 1 << 30;
+
+        assert_eq!(KeyFlags::from_bits(bits), KeyFlags {
+            repeat_count: 3,
+            scan_code: 0x38,
+            extended: true,
+            alt: true,
+            was_down: true,
+            released: false,
+        });
+
+        let released = KeyFlags::from_bits(0xc01e0001);
+        assert_eq!((released.repeat_count, released.scan_code), (1, 0x1e));
+        assert!(released.released && released.was_down && !released.extended && !released.alt);
+    }
+
+    #[test]
+    fn decode_key_events() {
+        let ctrl = Modifiers { ctrl: true, shift: false, alt: false };
+
+        let lparam = 1 | 0x1e << 16 | 1 << 30;
+        assert_eq!(KeyEvent::decode(WM_KEYDOWN, 0x41, lparam, ctrl).unwrap(), KeyEvent {
+            kind: KeyEventKind::Down,
+            key: Key::A,
+            scan_code: 0x1e,
+            repeat_count: 1,
+            repeat: true,
+            extended: false,
+            system: false,
+            modifiers: ctrl,
+        });
+
+        // Bit 31 makes the lparam of key-up messages negative.
+        let lparam = 0xc0440001u32 as i32 as isize;
+        let up = KeyEvent::decode(WM_SYSKEYUP, 0x79, lparam, Modifiers::none()).unwrap();
+        assert_eq!((up.kind, up.key, up.system), (KeyEventKind::Up, Key::F10, true));
+        // Only keys going down repeat.
+        assert!(!up.repeat);
+
+        // WM_CHAR
+        assert_eq!(KeyEvent::decode(0x0102, 0x41, 1, ctrl), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! Typed mouse and keyboard input, and keyboard shortcuts.
+
+#[cfg(windows)] use user32;
+
+#[cfg(windows)] use winapi::*;
+
+pub mod accelerator;
+pub mod keyboard;
+pub mod mouse;
+
+pub use self::accelerator::{Accelerator, ParseAcceleratorError};
+pub use self::keyboard::{Key, KeyEvent, KeyEventKind, KeyFlags, TextInput};
+pub use self::mouse::{MouseButton, MouseButtons, MouseEvent, MouseEventKind};
+
+/// The modifier keys held down during an input event.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    /// The keys held down when the message being handled was sent.
+    #[cfg(windows)]
+    pub fn current() -> Modifiers {
+        let down = |key| unsafe { user32::GetKeyState(key) < 0 };
+
+        Modifiers {
+            ctrl: down(VK_CONTROL),
+            shift: down(VK_SHIFT),
+            alt: down(VK_MENU),
+        }
+    }
+
+    pub fn none() -> Modifiers {
+        Modifiers::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Modifiers::none()
+    }
+}
@@ -0,0 +1,130 @@
+//! Mouse input: buttons, movement, the wheel and hovering.
+
+use canvas::Point;
+
+use super::Modifiers;
+
+#[cfg(windows)] use winapi::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    /// The first extra button, usually "back".
+    X1,
+    /// The second extra button, usually "forward".
+    X2,
+}
+
+/// The mouse buttons held down during an event.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MouseButtons {
+    pub left: bool,
+    pub right: bool,
+    pub middle: bool,
+    pub x1: bool,
+    pub x2: bool,
+}
+
+impl MouseButtons {
+    pub fn contains(&self, button: MouseButton) -> bool {
+        match button {
+            MouseButton::Left => self.left,
+            MouseButton::Right => self.right,
+            MouseButton::Middle => self.middle,
+            MouseButton::X1 => self.x1,
+            MouseButton::X2 => self.x2,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == MouseButtons::default()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MouseEventKind {
+    Move,
+    Down(MouseButton),
+    Up(MouseButton),
+    /// The second press of a double click, which is also reported as `Down`.
+    DoubleClick(MouseButton),
+    /// The vertical wheel turned by an amount where a notch is `WHEEL_DELTA` (120), and
+    /// positive is away from the user.
+    Wheel(i32),
+    /// The horizontal wheel turned, with positive to the right.
+    HWheel(i32),
+    /// The mouse moved into the client area.
+    Enter,
+    /// The mouse left the client area.
+    Leave,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    /// Where the mouse is, relative to the client area.
+    pub pos: Point,
+    pub buttons: MouseButtons,
+    pub modifiers: Modifiers,
+    /// 2 for the second press of a double click, 1 for other presses and releases and 0
+    /// for everything else.
+    pub click_count: u32,
+}
+
+#[cfg(windows)]
+impl MouseEvent {
+    /// Decode a mouse message. The position is as given in the message: in pixels, and
+    /// relative to the screen for the wheel. Alt isn't part of mouse messages, so it isn't
+    /// set in `modifiers`.
+    #[doc(hidden)]
+    pub fn decode(msg: UINT, wparam: WPARAM, lparam: LPARAM) -> Option<MouseEvent> {
+        let x_button = || if GET_XBUTTON_WPARAM(wparam) as DWORD == XBUTTON2 {
+            MouseButton::X2
+        } else {
+            MouseButton::X1
+        };
+
+        let (kind, click_count) = match msg {
+            WM_MOUSEMOVE => (MouseEventKind::Move, 0),
+            WM_LBUTTONDOWN => (MouseEventKind::Down(MouseButton::Left), 1),
+            WM_RBUTTONDOWN => (MouseEventKind::Down(MouseButton::Right), 1),
+            WM_MBUTTONDOWN => (MouseEventKind::Down(MouseButton::Middle), 1),
+            WM_XBUTTONDOWN => (MouseEventKind::Down(x_button()), 1),
+            WM_LBUTTONUP => (MouseEventKind::Up(MouseButton::Left), 1),
+            WM_RBUTTONUP => (MouseEventKind::Up(MouseButton::Right), 1),
+            WM_MBUTTONUP => (MouseEventKind::Up(MouseButton::Middle), 1),
+            WM_XBUTTONUP => (MouseEventKind::Up(x_button()), 1),
+            WM_LBUTTONDBLCLK => (MouseEventKind::DoubleClick(MouseButton::Left), 2),
+            WM_RBUTTONDBLCLK => (MouseEventKind::DoubleClick(MouseButton::Right), 2),
+            WM_MBUTTONDBLCLK => (MouseEventKind::DoubleClick(MouseButton::Middle), 2),
+            WM_XBUTTONDBLCLK => (MouseEventKind::DoubleClick(x_button()), 2),
+            WM_MOUSEWHEEL => (MouseEventKind::Wheel(GET_WHEEL_DELTA_WPARAM(wparam) as i32), 0),
+            WM_MOUSEHWHEEL => (MouseEventKind::HWheel(GET_WHEEL_DELTA_WPARAM(wparam) as i32), 0),
+            WM_MOUSELEAVE => (MouseEventKind::Leave, 0),
+            _ => return None,
+        };
+
+        // The button state is in the low word, except for `WM_MOUSELEAVE` which has none.
+        let keys = if msg == WM_MOUSELEAVE { 0 } else { LOWORD(wparam as DWORD) as WPARAM };
+
+        Some(MouseEvent {
+            kind: kind,
+            pos: Point::new(GET_X_LPARAM(lparam), GET_Y_LPARAM(lparam)),
+            buttons: MouseButtons {
+                left: keys & MK_LBUTTON != 0,
+                right: keys & MK_RBUTTON != 0,
+                middle: keys & MK_MBUTTON != 0,
+                x1: keys & MK_XBUTTON1 != 0,
+                x2: keys & MK_XBUTTON2 != 0,
+            },
+            modifiers: Modifiers {
+                ctrl: keys & MK_CONTROL != 0,
+                shift: keys & MK_SHIFT != 0,
+                alt: false,
+            },
+            click_count: click_count,
+        })
+    }
+}
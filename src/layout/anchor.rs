@@ -0,0 +1,384 @@
+#[cfg(windows)] use abs_window::AbsWindow;
+#[cfg(windows)] use dpi::Dpi;
+
+use super::{LayoutItem, Rect, Size};
+#[cfg(windows)] use super::Widget;
+
+#[cfg(windows)] use winapi::HWND;
+
+use std::cmp;
+
+/// The edges of its parent an item keeps a fixed distance to as the parent is resized.
+///
+/// An item anchored to both left and right (or top and bottom) stretches with its parent;
+/// one anchored to neither stays centered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Anchor {
+    pub left: bool,
+    pub top: bool,
+    pub right: bool,
+    pub bottom: bool,
+}
+
+impl Anchor {
+    pub fn new(left: bool, top: bool, right: bool, bottom: bool) -> Anchor {
+        Anchor { left: left, top: top, right: right, bottom: bottom }
+    }
+
+    /// Stay in place relative to the top left corner. This is the default.
+    pub fn top_left() -> Anchor {
+        Anchor::new(true, true, false, false)
+    }
+
+    /// Stretch in both directions with the parent.
+    pub fn all() -> Anchor {
+        Anchor::new(true, true, true, true)
+    }
+}
+
+impl Default for Anchor {
+    fn default() -> Self {
+        Anchor::top_left()
+    }
+}
+
+/// An edge of its parent an item is attached to, taking the whole length of that edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Dock {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    /// Take up all the space left over by other docked items.
+    Fill,
+}
+
+enum Mode {
+    /// Position relative to the parent when the anchor was set, and the size of the parent then.
+    Anchor(Anchor, Rect, Size),
+    /// Size of the item across the edge it's docked to.
+    Dock(Dock, Size),
+}
+
+struct Item {
+    item: Box<LayoutItem>,
+    /// The address of the window (`HWND`) this item positions, or 0 if it isn't a control,
+    /// so its settings can be replaced.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    window: usize,
+    mode: Mode,
+}
+
+/// Positions items WinForms-style: docked to an edge of the parent, or anchored to keep
+/// their distance to some of its edges.
+///
+/// Docked items are laid out in the order they were added, each taking space from what the
+/// previous ones left. Anchored items are positioned within the full bounds.
+pub struct AnchorLayout {
+    items: Vec<Item>,
+}
+
+impl AnchorLayout {
+    pub fn new() -> AnchorLayout {
+        AnchorLayout {
+            items: Vec::new(),
+        }
+    }
+
+    /// Anchor an item that was at `design` (relative to the parent's origin) when the parent
+    /// was `parent` in size.
+    pub fn add_anchored<I: LayoutItem + 'static>(&mut self, item: I, design: Rect, parent: Size, anchor: Anchor) {
+        self.push(Box::new(item), 0, Mode::Anchor(anchor, design, parent));
+    }
+
+    /// Dock an item to an edge. `size` is its height for `Top`/`Bottom`, or its width for
+    /// `Left`/`Right`; it is raised to the item's minimum size if smaller.
+    pub fn add_docked<I: LayoutItem + 'static>(&mut self, item: I, size: Size, dock: Dock) {
+        self.push(Box::new(item), 0, Mode::Dock(dock, size));
+    }
+
+    /// Anchor a control, taking its current bounds within `parent_area` as the reference.
+    #[cfg(windows)]
+    pub fn set_widget_anchor<W: AbsWindow>(&mut self, widget: &W, parent_area: Rect, anchor: Anchor) {
+        let bounds = Dpi::of_window(widget).unscale_rect(widget.bounds());
+        let design = Rect::new(
+            bounds.x - parent_area.x, bounds.y - parent_area.y, bounds.width, bounds.height
+        );
+
+        self.remove_widget(widget);
+        self.push(
+            Box::new(Widget::new(widget)), widget.ptr() as usize,
+            Mode::Anchor(anchor, design, parent_area.size())
+        );
+    }
+
+    /// Dock a control, keeping its current size across the docked edge.
+    #[cfg(windows)]
+    pub fn set_widget_dock<W: AbsWindow>(&mut self, widget: &W, dock: Dock) {
+        let size = Dpi::of_window(widget).unscale_size(widget.bounds().size());
+
+        self.remove_widget(widget);
+        self.push(Box::new(Widget::new(widget)), widget.ptr() as usize, Mode::Dock(dock, size));
+    }
+
+    /// Stop positioning a control.
+    #[cfg(windows)]
+    pub fn remove_widget<W: AbsWindow>(&mut self, widget: &W) {
+        self.forget(widget.ptr());
+    }
+
+    /// Drop the item positioning `hwnd`, e.g. because the control is being destroyed.
+    #[cfg(windows)]
+    #[doc(hidden)]
+    pub fn forget(&mut self, hwnd: HWND) {
+        self.items.retain(|item| item.window != hwnd as usize);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Position all items within `bounds`, returning the space not taken by docked items.
+    pub fn arrange_docked(&mut self, bounds: Rect) -> Rect {
+        let mut remaining = bounds;
+
+        for item in &mut self.items {
+            let rect = match item.mode {
+                Mode::Dock(dock, size) => {
+                    let min = item.item.min_size();
+                    let size = Size::new(cmp::max(size.width, min.width), cmp::max(size.height, min.height));
+                    dock_rect(&mut remaining, dock, size)
+                },
+                Mode::Anchor(anchor, design, parent) => anchor_rect(design, parent, bounds, anchor),
+            };
+
+            item.item.arrange(rect);
+        }
+
+        remaining
+    }
+
+    fn push(&mut self, item: Box<LayoutItem>, window: usize, mode: Mode) {
+        self.items.push(Item {
+            item: item,
+            window: window,
+            mode: mode,
+        });
+    }
+}
+
+impl Default for AnchorLayout {
+    fn default() -> Self {
+        AnchorLayout::new()
+    }
+}
+
+impl LayoutItem for AnchorLayout {
+    /// Enough space for the docked items at their sizes and the anchored items at their
+    /// design positions.
+    fn min_size(&self) -> Size {
+        let mut docked = Size::default();
+        let mut fill = Size::default();
+        let mut anchored = Size::default();
+
+        for item in &self.items {
+            let min = item.item.min_size();
+
+            match item.mode {
+                Mode::Dock(Dock::Top, size) | Mode::Dock(Dock::Bottom, size) =>
+                    docked.height += cmp::max(size.height, min.height),
+                Mode::Dock(Dock::Left, size) | Mode::Dock(Dock::Right, size) =>
+                    docked.width += cmp::max(size.width, min.width),
+                Mode::Dock(Dock::Fill, _) => {
+                    fill.width = cmp::max(fill.width, min.width);
+                    fill.height = cmp::max(fill.height, min.height);
+                },
+                Mode::Anchor(_, design, _) => {
+                    anchored.width = cmp::max(anchored.width, design.right());
+                    anchored.height = cmp::max(anchored.height, design.bottom());
+                },
+            }
+        }
+
+        Size::new(
+            cmp::max(docked.width + fill.width, anchored.width),
+            cmp::max(docked.height + fill.height, anchored.height),
+        )
+    }
+
+    fn arrange(&mut self, bounds: Rect) {
+        self.arrange_docked(bounds);
+    }
+}
+
+/// Take a strip of `size` from the `dock` edge of `remaining`, returning it.
+pub fn dock_rect(remaining: &mut Rect, dock: Dock, size: Size) -> Rect {
+    let height = cmp::min(size.height, remaining.height);
+    let width = cmp::min(size.width, remaining.width);
+
+    let r = *remaining;
+
+    match dock {
+        Dock::Top => {
+            remaining.y += height;
+            remaining.height -= height;
+            Rect::new(r.x, r.y, r.width, height)
+        },
+        Dock::Bottom => {
+            remaining.height -= height;
+            Rect::new(r.x, r.bottom() - height, r.width, height)
+        },
+        Dock::Left => {
+            remaining.x += width;
+            remaining.width -= width;
+            Rect::new(r.x, r.y, width, r.height)
+        },
+        Dock::Right => {
+            remaining.width -= width;
+            Rect::new(r.right() - width, r.y, width, r.height)
+        },
+        Dock::Fill => {
+            remaining.width = 0;
+            remaining.height = 0;
+            r
+        },
+    }
+}
+
+/// Position an item that was at `design` in a parent of size `design_parent` within `parent`.
+pub fn anchor_rect(design: Rect, design_parent: Size, parent: Rect, anchor: Anchor) -> Rect {
+    let (x, width) = anchor_axis(
+        design.x, design.width, parent.width - design_parent.width, anchor.left, anchor.right
+    );
+    let (y, height) = anchor_axis(
+        design.y, design.height, parent.height - design_parent.height, anchor.top, anchor.bottom
+    );
+
+    Rect::new(parent.x + x, parent.y + y, width, height)
+}
+
+/// Apply the change in the parent's size, `delta`, along one axis.
+fn anchor_axis(start: i32, len: i32, delta: i32, near: bool, far: bool) -> (i32, i32) {
+    match (near, far) {
+        (true, true) => (start, cmp::max(len + delta, 0)),
+        (true, false) => (start, len),
+        (false, true) => (start + delta, len),
+        (false, false) => (start + delta / 2, len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use layout::tests::Probe;
+    use super::*;
+
+    #[test]
+    fn dock_in_order() {
+        let top = Probe::new(0, 0);
+        let left = Probe::new(0, 0);
+        let bottom = Probe::new(0, 0);
+        let right = Probe::new(0, 0);
+
+        let mut layout = AnchorLayout::new();
+        layout.add_docked(top.clone(), Size::new(0, 20), Dock::Top);
+        layout.add_docked(left.clone(), Size::new(30, 0), Dock::Left);
+        layout.add_docked(bottom.clone(), Size::new(0, 10), Dock::Bottom);
+        layout.add_docked(right.clone(), Size::new(40, 0), Dock::Right);
+
+        let rest = layout.arrange_docked(Rect::new(0, 0, 200, 100));
+
+        // Each edge takes its strip from what the ones before it left.
+        assert_eq!(top.bounds(), Rect::new(0, 0, 200, 20));
+        assert_eq!(left.bounds(), Rect::new(0, 20, 30, 80));
+        assert_eq!(bottom.bounds(), Rect::new(30, 90, 170, 10));
+        assert_eq!(right.bounds(), Rect::new(160, 20, 40, 70));
+        assert_eq!(rest, Rect::new(30, 20, 130, 70));
+    }
+
+    #[test]
+    fn fill_takes_the_remainder() {
+        let top = Probe::new(0, 0);
+        let fill = Probe::new(0, 0);
+
+        let mut layout = AnchorLayout::new();
+        layout.add_docked(top.clone(), Size::new(0, 25), Dock::Top);
+        layout.add_docked(fill.clone(), Size::default(), Dock::Fill);
+
+        let rest = layout.arrange_docked(Rect::new(10, 10, 100, 100));
+
+        assert_eq!(fill.bounds(), Rect::new(10, 35, 100, 75));
+        assert_eq!(rest.size(), Size::default());
+    }
+
+    #[test]
+    fn docked_size_is_at_least_the_minimum() {
+        let top = Probe::new(0, 30);
+
+        let mut layout = AnchorLayout::new();
+        layout.add_docked(top.clone(), Size::new(0, 10), Dock::Top);
+        layout.arrange_docked(Rect::new(0, 0, 100, 100));
+
+        assert_eq!(top.bounds(), Rect::new(0, 0, 100, 30));
+    }
+
+    #[test]
+    fn anchor_stretch() {
+        let design = Rect::new(10, 20, 50, 30);
+        let parent = Size::new(100, 100);
+        let resized = Rect::new(0, 0, 150, 120);
+
+        let horizontal = Anchor::new(true, true, true, false);
+        assert_eq!(anchor_rect(design, parent, resized, horizontal), Rect::new(10, 20, 100, 30));
+
+        let vertical = Anchor::new(true, true, false, true);
+        assert_eq!(anchor_rect(design, parent, resized, vertical), Rect::new(10, 20, 50, 50));
+
+        assert_eq!(anchor_rect(design, parent, resized, Anchor::all()), Rect::new(10, 20, 100, 50));
+
+        // Shrinking past the design size stops at zero.
+        let shrunk = Rect::new(0, 0, 20, 20);
+        assert_eq!(anchor_rect(design, parent, shrunk, Anchor::all()), Rect::new(10, 20, 0, 0));
+    }
+
+    #[test]
+    fn anchor_move() {
+        let design = Rect::new(10, 20, 50, 30);
+        let parent = Size::new(100, 100);
+        let resized = Rect::new(5, 5, 150, 120);
+
+        assert_eq!(
+            anchor_rect(design, parent, resized, Anchor::top_left()), Rect::new(15, 25, 50, 30)
+        );
+
+        let bottom_right = Anchor::new(false, false, true, true);
+        assert_eq!(anchor_rect(design, parent, resized, bottom_right), Rect::new(65, 45, 50, 30));
+    }
+
+    #[test]
+    fn no_anchor_stays_centered() {
+        // Centered in the design: 25 on each side horizontally, 35 vertically.
+        let design = Rect::new(25, 35, 50, 30);
+        let parent = Size::new(100, 100);
+
+        let none = Anchor::new(false, false, false, false);
+        let rect = anchor_rect(design, parent, Rect::new(0, 0, 200, 140), none);
+
+        assert_eq!(rect, Rect::new(75, 55, 50, 30));
+    }
+
+    #[test]
+    fn anchored_items_use_the_full_bounds() {
+        let docked = Probe::new(0, 0);
+        let anchored = Probe::new(0, 0);
+
+        let mut layout = AnchorLayout::new();
+        layout.add_docked(docked.clone(), Size::new(0, 20), Dock::Top);
+        layout.add_anchored(
+            anchored.clone(), Rect::new(10, 10, 20, 20), Size::new(100, 100), Anchor::all()
+        );
+        layout.arrange_docked(Rect::new(0, 0, 120, 100));
+
+        assert_eq!(anchored.bounds(), Rect::new(10, 10, 40, 20));
+        assert_eq!(layout.min_size(), Size::new(30, 30));
+    }
+}
@@ -0,0 +1,256 @@
+//! Layout by linear constraints between the edges of items, such as
+//! `a.right() + 8 == b.left` or `b.width >= 120`.
+
+use super::{LayoutItem, Rect, Size};
+
+use std::cell::{Cell, RefCell};
+
+mod solver;
+
+pub use self::solver::{
+    Constraint, ConstraintId, Expression, Relation, Solver, SolverError, Strength, Variable,
+    REQUIRED, STRONG, MEDIUM, WEAK
+};
+
+/// The variables for the position and size of a rectangle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub left: Variable,
+    pub top: Variable,
+    pub width: Variable,
+    pub height: Variable,
+}
+
+impl Frame {
+    fn new(solver: &mut Solver) -> Frame {
+        Frame {
+            left: solver.new_variable(),
+            top: solver.new_variable(),
+            width: solver.new_variable(),
+            height: solver.new_variable(),
+        }
+    }
+
+    pub fn right(&self) -> Expression {
+        self.left + self.width
+    }
+
+    pub fn bottom(&self) -> Expression {
+        self.top + self.height
+    }
+
+    pub fn center_x(&self) -> Expression {
+        self.left + self.width * 0.5
+    }
+
+    pub fn center_y(&self) -> Expression {
+        self.top + self.height * 0.5
+    }
+
+    /// Round the solved values to whole pixels, so that touching edges stay touching.
+    fn rect(&self, solver: &Solver) -> Rect {
+        let left = solver.value(self.left).round();
+        let top = solver.value(self.top).round();
+        let right = (solver.value(self.left) + solver.value(self.width)).round();
+        let bottom = (solver.value(self.top) + solver.value(self.height)).round();
+
+        Rect::new(left as i32, top as i32, (right - left) as i32, (bottom - top) as i32)
+    }
+}
+
+/// Positions items by constraints on their frames and the frame of the container.
+///
+/// Each item is at least its minimum size. The container's frame is suggested to the
+/// solver with strong strength whenever the layout is arranged, so the solution is updated
+/// incrementally as the window is resized.
+pub struct ConstraintLayout {
+    // Finding the minimum size means solving for a zero-sized container.
+    solver: RefCell<Solver>,
+    container: Frame,
+    items: Vec<(Box<LayoutItem>, Frame)>,
+    bounds: Cell<Rect>,
+}
+
+impl ConstraintLayout {
+    pub fn new() -> ConstraintLayout {
+        let mut solver = Solver::new();
+        let container = Frame::new(&mut solver);
+
+        for &var in &[container.left, container.top, container.width, container.height] {
+            // Neither can fail: the variables are new and the strength isn't required.
+            solver.add_edit_variable(var, STRONG).unwrap();
+        }
+
+        ConstraintLayout {
+            solver: RefCell::new(solver),
+            container: container,
+            items: Vec::new(),
+            bounds: Cell::new(Rect::default()),
+        }
+    }
+
+    /// The frame of the space the layout is arranged in.
+    pub fn container(&self) -> Frame {
+        self.container
+    }
+
+    /// Add an item, returning its frame to constrain.
+    pub fn add<I: LayoutItem + 'static>(&mut self, item: I) -> Frame {
+        let min = item.min_size();
+        let frame = Frame::new(self.solver.get_mut());
+
+        {
+            let solver = self.solver.get_mut();
+
+            // The variables are new, so these can't conflict with anything.
+            solver.add_constraint(Constraint::at_least(frame.width, min.width)).unwrap();
+            solver.add_constraint(Constraint::at_least(frame.height, min.height)).unwrap();
+        }
+
+        self.items.push((Box::new(item), frame));
+        frame
+    }
+
+    pub fn add_constraint(&mut self, constraint: Constraint) -> Result<ConstraintId, SolverError> {
+        self.solver.get_mut().add_constraint(constraint)
+    }
+
+    pub fn remove_constraint(&mut self, id: ConstraintId) -> Result<(), SolverError> {
+        self.solver.get_mut().remove_constraint(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Solve for the items' rectangles with the container at `bounds`, in the order the
+    /// items were added.
+    pub fn solve(&self, bounds: Rect) -> Result<Vec<Rect>, SolverError> {
+        let mut solver = self.solver.borrow_mut();
+
+        try!(Self::suggest(&mut solver, self.container, bounds));
+        self.bounds.set(bounds);
+
+        Ok(self.items.iter().map(|&(_, ref frame)| frame.rect(&solver)).collect())
+    }
+
+    fn suggest(solver: &mut Solver, container: Frame, bounds: Rect) -> Result<(), SolverError> {
+        try!(solver.suggest_value(container.left, bounds.x as f64));
+        try!(solver.suggest_value(container.top, bounds.y as f64));
+        try!(solver.suggest_value(container.width, bounds.width as f64));
+        solver.suggest_value(container.height, bounds.height as f64)
+    }
+}
+
+impl Default for ConstraintLayout {
+    fn default() -> Self {
+        ConstraintLayout::new()
+    }
+}
+
+impl LayoutItem for ConstraintLayout {
+    /// The size the required constraints push the container to when it is asked to be empty.
+    fn min_size(&self) -> Size {
+        let bounds = self.bounds.get();
+        let mut solver = self.solver.borrow_mut();
+
+        let empty = Rect::new(bounds.x, bounds.y, 0, 0);
+
+        if Self::suggest(&mut solver, self.container, empty).is_err() {
+            return Size::default();
+        }
+
+        let size = Size::new(
+            solver.value(self.container.width).ceil() as i32,
+            solver.value(self.container.height).ceil() as i32,
+        );
+
+        // Put the solution back, so it matches the last arrangement again.
+        let _ = Self::suggest(&mut solver, self.container, bounds);
+
+        size
+    }
+
+    fn arrange(&mut self, bounds: Rect) {
+        let rects = match self.solve(bounds) {
+            Ok(rects) => rects,
+            Err(_) => return,
+        };
+
+        for (&mut (ref mut item, _), rect) in self.items.iter_mut().zip(rects) {
+            item.arrange(rect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use layout::tests::Probe;
+    use layout::{LayoutItem, Rect, Size};
+
+    use super::{Constraint, ConstraintLayout, SolverError, WEAK};
+
+    /// Two items of equal width side by side, 10 from the edges and 8 apart.
+    fn side_by_side(a: &Probe, b: &Probe) -> ConstraintLayout {
+        let mut layout = ConstraintLayout::new();
+        let container = layout.container();
+
+        let fa = layout.add(a.clone());
+        let fb = layout.add(b.clone());
+
+        for constraint in vec![
+            Constraint::equal(fa.left, container.left + 10),
+            Constraint::equal(fb.left, fa.right() + 8),
+            Constraint::equal(fb.right(), container.right() - 10),
+            Constraint::equal(fa.width, fb.width),
+            Constraint::equal(fa.top, container.top + 10),
+            Constraint::equal(fb.top, fa.top),
+            Constraint::equal(fa.height, fb.height),
+            Constraint::at_most(fa.bottom(), container.bottom() - 10),
+            Constraint::equal(fa.height, 0).strength(WEAK),
+        ] {
+            layout.add_constraint(constraint).unwrap();
+        }
+
+        layout
+    }
+
+    #[test]
+    fn solve() {
+        let (a, b) = (Probe::new(30, 20), Probe::new(40, 10));
+        let mut layout = side_by_side(&a, &b);
+
+        assert_eq!(layout.solve(Rect::new(0, 0, 200, 100)).unwrap(), vec![
+            Rect::new(10, 10, 86, 20),
+            Rect::new(104, 10, 86, 20),
+        ]);
+
+        // Resizing re-solves, and arranging moves the items.
+        layout.arrange(Rect::new(50, 0, 300, 100));
+        assert_eq!(a.bounds(), Rect::new(60, 10, 136, 20));
+        assert_eq!(b.bounds(), Rect::new(204, 10, 136, 20));
+    }
+
+    #[test]
+    fn min_size_comes_from_required_constraints() {
+        let (a, b) = (Probe::new(30, 20), Probe::new(40, 10));
+        let mut layout = side_by_side(&a, &b);
+
+        layout.arrange(Rect::new(0, 0, 200, 100));
+
+        assert_eq!(layout.min_size(), Size::new(10 + 40 + 8 + 40 + 10, 10 + 20 + 10));
+
+        // Measuring doesn't disturb the last arrangement.
+        assert_eq!(layout.solve(Rect::new(0, 0, 200, 100)).unwrap()[0], Rect::new(10, 10, 86, 20));
+    }
+
+    #[test]
+    fn conflicting_constraint_is_rejected() {
+        let (a, b) = (Probe::new(30, 20), Probe::new(40, 10));
+        let mut layout = side_by_side(&a, &b);
+        let container = layout.container();
+
+        let conflict = Constraint::equal(container.width, 50);
+        assert_eq!(layout.add_constraint(conflict).err(), Some(SolverError::Unsatisfiable));
+    }
+}
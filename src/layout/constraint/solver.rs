@@ -0,0 +1,974 @@
+//! An incremental linear constraint solver using the Cassowary algorithm.
+//!
+//! This follows the formulation used by the Kiwi solver: every constraint becomes a row of
+//! a simplex tableau, non-required constraints get error variables weighted by their
+//! strength in the objective, and edit variables allow values to be suggested cheaply
+//! through the dual simplex method.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::{fmt, ops};
+
+/// An unknown to be solved for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Variable(usize);
+
+/// A linear combination of variables plus a constant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    terms: Vec<(Variable, f64)>,
+    constant: f64,
+}
+
+impl Expression {
+    pub fn constant(value: f64) -> Expression {
+        Expression { terms: Vec::new(), constant: value }
+    }
+
+    fn negate(mut self) -> Expression {
+        for term in &mut self.terms {
+            term.1 = -term.1;
+        }
+
+        self.constant = -self.constant;
+        self
+    }
+}
+
+impl From<Variable> for Expression {
+    fn from(var: Variable) -> Expression {
+        Expression { terms: vec![(var, 1.0)], constant: 0.0 }
+    }
+}
+
+impl From<f64> for Expression {
+    fn from(value: f64) -> Expression {
+        Expression::constant(value)
+    }
+}
+
+impl From<i32> for Expression {
+    fn from(value: i32) -> Expression {
+        Expression::constant(value as f64)
+    }
+}
+
+impl<T: Into<Expression>> ops::Add<T> for Expression {
+    type Output = Expression;
+
+    fn add(mut self, rhs: T) -> Expression {
+        let rhs = rhs.into();
+        self.terms.extend(rhs.terms);
+        self.constant += rhs.constant;
+        self
+    }
+}
+
+impl<T: Into<Expression>> ops::Sub<T> for Expression {
+    type Output = Expression;
+
+    fn sub(self, rhs: T) -> Expression {
+        self + rhs.into().negate()
+    }
+}
+
+impl ops::Mul<f64> for Expression {
+    type Output = Expression;
+
+    fn mul(mut self, rhs: f64) -> Expression {
+        for term in &mut self.terms {
+            term.1 *= rhs;
+        }
+
+        self.constant *= rhs;
+        self
+    }
+}
+
+impl ops::Neg for Expression {
+    type Output = Expression;
+
+    fn neg(self) -> Expression {
+        self.negate()
+    }
+}
+
+impl<T: Into<Expression>> ops::Add<T> for Variable {
+    type Output = Expression;
+
+    fn add(self, rhs: T) -> Expression {
+        Expression::from(self) + rhs
+    }
+}
+
+impl<T: Into<Expression>> ops::Sub<T> for Variable {
+    type Output = Expression;
+
+    fn sub(self, rhs: T) -> Expression {
+        Expression::from(self) - rhs
+    }
+}
+
+impl ops::Mul<f64> for Variable {
+    type Output = Expression;
+
+    fn mul(self, rhs: f64) -> Expression {
+        Expression::from(self) * rhs
+    }
+}
+
+impl ops::Neg for Variable {
+    type Output = Expression;
+
+    fn neg(self) -> Expression {
+        -Expression::from(self)
+    }
+}
+
+/// How hard the solver tries to satisfy a constraint.
+///
+/// Any number of weaker constraints are given up before a stronger one is.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Strength(f64);
+
+/// Must be satisfied; adding a required constraint that conflicts with others fails.
+pub const REQUIRED: Strength = Strength(1001001000.0);
+pub const STRONG: Strength = Strength(1000000.0);
+pub const MEDIUM: Strength = Strength(1000.0);
+pub const WEAK: Strength = Strength(1.0);
+
+impl Strength {
+    /// A strength between the predefined ones, with each component from 0 to 1000.
+    pub fn new(strong: f64, medium: f64, weak: f64) -> Strength {
+        let clamp = |value: f64| value.max(0.0).min(1000.0);
+        Strength(clamp(strong) * 1000000.0 + clamp(medium) * 1000.0 + clamp(weak))
+    }
+
+    fn clip(self) -> Strength {
+        Strength(self.0.max(0.0).min(REQUIRED.0))
+    }
+
+    fn is_required(self) -> bool {
+        self.0 >= REQUIRED.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Relation {
+    /// `lhs <= rhs`
+    AtMost,
+    /// `lhs == rhs`
+    Equal,
+    /// `lhs >= rhs`
+    AtLeast,
+}
+
+/// A linear relation between two expressions, with a strength.
+#[derive(Clone, Debug)]
+pub struct Constraint {
+    /// `lhs - rhs`, compared against zero.
+    expr: Expression,
+    relation: Relation,
+    strength: Strength,
+}
+
+impl Constraint {
+    /// A required constraint `lhs <relation> rhs`.
+    pub fn new<L, R>(lhs: L, relation: Relation, rhs: R) -> Constraint
+    where L: Into<Expression>, R: Into<Expression> {
+        Constraint {
+            expr: lhs.into() - rhs,
+            relation: relation,
+            strength: REQUIRED,
+        }
+    }
+
+    pub fn equal<L: Into<Expression>, R: Into<Expression>>(lhs: L, rhs: R) -> Constraint {
+        Constraint::new(lhs, Relation::Equal, rhs)
+    }
+
+    pub fn at_least<L: Into<Expression>, R: Into<Expression>>(lhs: L, rhs: R) -> Constraint {
+        Constraint::new(lhs, Relation::AtLeast, rhs)
+    }
+
+    pub fn at_most<L: Into<Expression>, R: Into<Expression>>(lhs: L, rhs: R) -> Constraint {
+        Constraint::new(lhs, Relation::AtMost, rhs)
+    }
+
+    pub fn strength(mut self, strength: Strength) -> Self {
+        self.strength = strength.clip();
+        self
+    }
+}
+
+/// Identifies a constraint added to a `Solver`, to remove it later.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConstraintId(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SolverError {
+    /// The constraint conflicts with the required constraints already added.
+    Unsatisfiable,
+    UnknownConstraint,
+    DuplicateEditVariable,
+    UnknownEditVariable,
+    /// Edit variables can't have required strength.
+    RequiredEditVariable,
+    /// The solver reached a state that should be impossible.
+    Internal(&'static str),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.description())
+    }
+}
+
+impl Error for SolverError {
+    fn description(&self) -> &str {
+        use self::SolverError::*;
+
+        match *self {
+            Unsatisfiable => "the constraint can not be satisfied",
+            UnknownConstraint => "the constraint was never added",
+            DuplicateEditVariable => "the variable is already an edit variable",
+            UnknownEditVariable => "the variable is not an edit variable",
+            RequiredEditVariable => "edit variables can not have required strength",
+            Internal(msg) => msg,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SymbolKind {
+    External,
+    Slack,
+    Error,
+    Dummy,
+}
+
+/// A variable of the tableau: either a user `Variable` or one introduced by the solver.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Symbol {
+    id: usize,
+    kind: SymbolKind,
+}
+
+impl Symbol {
+    /// Whether the symbol can be chosen to leave or enter the basis.
+    fn is_pivotable(&self) -> bool {
+        self.kind == SymbolKind::Slack || self.kind == SymbolKind::Error
+    }
+}
+
+fn near_zero(value: f64) -> bool {
+    value.abs() < 1.0e-8
+}
+
+/// `constant + sum(coefficient * symbol)`. The ordered map keeps pivoting deterministic.
+#[derive(Clone, Debug)]
+struct Row {
+    cells: BTreeMap<Symbol, f64>,
+    constant: f64,
+}
+
+impl Row {
+    fn new(constant: f64) -> Row {
+        Row { cells: BTreeMap::new(), constant: constant }
+    }
+
+    fn add(&mut self, value: f64) -> f64 {
+        self.constant += value;
+        self.constant
+    }
+
+    fn insert_symbol(&mut self, symbol: Symbol, coefficient: f64) {
+        let value = *self.cells.entry(symbol).or_insert(0.0) + coefficient;
+
+        if near_zero(value) {
+            self.cells.remove(&symbol);
+        } else {
+            self.cells.insert(symbol, value);
+        }
+    }
+
+    fn insert_row(&mut self, other: &Row, coefficient: f64) {
+        self.constant += other.constant * coefficient;
+
+        for (&symbol, &value) in &other.cells {
+            self.insert_symbol(symbol, value * coefficient);
+        }
+    }
+
+    fn remove(&mut self, symbol: Symbol) {
+        self.cells.remove(&symbol);
+    }
+
+    fn reverse_sign(&mut self) {
+        self.constant = -self.constant;
+
+        for value in self.cells.values_mut() {
+            *value = -*value;
+        }
+    }
+
+    /// Rearrange `0 = self` into `symbol = self'`.
+    fn solve_for(&mut self, symbol: Symbol) {
+        let coefficient = -1.0 / self.cells.remove(&symbol).expect("symbol not in row");
+
+        self.constant *= coefficient;
+
+        for value in self.cells.values_mut() {
+            *value *= coefficient;
+        }
+    }
+
+    /// Rearrange `lhs = self` into `rhs = self'`.
+    fn solve_for_pair(&mut self, lhs: Symbol, rhs: Symbol) {
+        self.insert_symbol(lhs, -1.0);
+        self.solve_for(rhs);
+    }
+
+    fn coefficient_for(&self, symbol: Symbol) -> f64 {
+        self.cells.get(&symbol).cloned().unwrap_or(0.0)
+    }
+
+    /// Replace `symbol` with the expression `row`.
+    fn substitute(&mut self, symbol: Symbol, row: &Row) {
+        if let Some(coefficient) = self.cells.remove(&symbol) {
+            self.insert_row(row, coefficient);
+        }
+    }
+}
+
+/// The symbols added to the tableau for a constraint.
+#[derive(Copy, Clone, Debug)]
+struct Tag {
+    marker: Symbol,
+    other: Option<Symbol>,
+}
+
+struct EditInfo {
+    constraint: ConstraintId,
+    tag: Tag,
+    constant: f64,
+}
+
+/// Keeps the values of a set of variables satisfying the constraints added to it.
+///
+/// Adding and removing constraints and suggesting values for edit variables update the
+/// solution incrementally, so re-solving after a small change is cheap.
+pub struct Solver {
+    constraints: HashMap<ConstraintId, (Constraint, Tag)>,
+    rows: BTreeMap<Symbol, Row>,
+    vars: HashMap<Variable, Symbol>,
+    edits: HashMap<Variable, EditInfo>,
+    infeasible: Vec<Symbol>,
+    objective: Row,
+    artificial: Option<Row>,
+    next_id: usize,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            constraints: HashMap::new(),
+            rows: BTreeMap::new(),
+            vars: HashMap::new(),
+            edits: HashMap::new(),
+            infeasible: Vec::new(),
+            objective: Row::new(0.0),
+            artificial: None,
+            next_id: 0,
+        }
+    }
+
+    pub fn new_variable(&mut self) -> Variable {
+        Variable(self.next_id())
+    }
+
+    /// The current value of `var`. Variables not in any constraint are 0.
+    pub fn value(&self, var: Variable) -> f64 {
+        self.vars.get(&var)
+            .and_then(|symbol| self.rows.get(symbol))
+            .map_or(0.0, |row| row.constant)
+    }
+
+    /// Add a constraint, re-solving the system.
+    ///
+    /// A constraint that conflicts with the required constraints already added fails with
+    /// `SolverError::Unsatisfiable`, leaving the solver as it was.
+    pub fn add_constraint(&mut self, constraint: Constraint) -> Result<ConstraintId, SolverError> {
+        // Finding out that a constraint is unsatisfiable takes pivoting it into the tableau.
+        let rows = self.rows.clone();
+        let objective = self.objective.clone();
+
+        let (mut row, tag) = self.create_row(&constraint);
+
+        let mut subject = Self::choose_subject(&row, tag);
+        let mut satisfiable = true;
+
+        if subject.is_none() && row.cells.keys().all(|symbol| symbol.kind == SymbolKind::Dummy) {
+            satisfiable = near_zero(row.constant);
+            subject = Some(tag.marker);
+        }
+
+        if satisfiable {
+            match subject {
+                Some(subject) => {
+                    row.solve_for(subject);
+                    self.substitute(subject, &row);
+                    self.rows.insert(subject, row);
+                },
+                None => satisfiable = try!(self.add_with_artificial_variable(row)),
+            }
+        }
+
+        if !satisfiable {
+            self.rows = rows;
+            self.objective = objective;
+            self.infeasible.clear();
+            return Err(SolverError::Unsatisfiable);
+        }
+
+        let id = ConstraintId(self.next_id());
+        self.constraints.insert(id, (constraint, tag));
+
+        try!(self.optimize(false));
+
+        Ok(id)
+    }
+
+    pub fn remove_constraint(&mut self, id: ConstraintId) -> Result<(), SolverError> {
+        let (constraint, tag) = try!(
+            self.constraints.remove(&id).ok_or(SolverError::UnknownConstraint)
+        );
+
+        self.remove_objective_errors(tag, constraint.strength);
+
+        if self.rows.remove(&tag.marker).is_none() {
+            let leaving = try!(
+                self.marker_leaving_row(tag.marker)
+                    .ok_or(SolverError::Internal("failed to find leaving row"))
+            );
+
+            let mut row = self.rows.remove(&leaving).unwrap();
+            row.solve_for_pair(leaving, tag.marker);
+            self.substitute(tag.marker, &row);
+        }
+
+        self.optimize(false)
+    }
+
+    /// Make `var` a variable whose value can be suggested with `suggest_value()`.
+    pub fn add_edit_variable(&mut self, var: Variable, strength: Strength) -> Result<(), SolverError> {
+        if self.edits.contains_key(&var) {
+            return Err(SolverError::DuplicateEditVariable);
+        }
+
+        let strength = strength.clip();
+
+        if strength.is_required() {
+            return Err(SolverError::RequiredEditVariable);
+        }
+
+        let id = try!(self.add_constraint(Constraint::equal(var, 0.0).strength(strength)));
+        let tag = self.constraints[&id].1;
+
+        self.edits.insert(var, EditInfo {
+            constraint: id,
+            tag: tag,
+            constant: 0.0,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_edit_variable(&mut self, var: Variable) -> Result<(), SolverError> {
+        let edit = try!(self.edits.remove(&var).ok_or(SolverError::UnknownEditVariable));
+        self.remove_constraint(edit.constraint)
+    }
+
+    pub fn has_edit_variable(&self, var: Variable) -> bool {
+        self.edits.contains_key(&var)
+    }
+
+    /// Ask for `var` to take `value`, as far as stronger constraints allow.
+    pub fn suggest_value(&mut self, var: Variable, value: f64) -> Result<(), SolverError> {
+        let (tag, delta) = {
+            let edit = try!(self.edits.get_mut(&var).ok_or(SolverError::UnknownEditVariable));
+            let delta = value - edit.constant;
+            edit.constant = value;
+            (edit.tag, delta)
+        };
+
+        // If one of the error variables is basic, only its row changes.
+        let basic = Some((tag.marker, -delta)).into_iter()
+            .chain(tag.other.map(|other| (other, delta)))
+            .find(|&(symbol, _)| self.rows.contains_key(&symbol));
+
+        match basic {
+            Some((symbol, delta)) => if self.rows.get_mut(&symbol).unwrap().add(delta) < 0.0 {
+                self.infeasible.push(symbol);
+            },
+            None => for (&symbol, row) in &mut self.rows {
+                let coefficient = row.coefficient_for(tag.marker);
+
+                if coefficient != 0.0 && row.add(delta * coefficient) < 0.0 &&
+                    symbol.kind != SymbolKind::External {
+                    self.infeasible.push(symbol);
+                }
+            },
+        }
+
+        self.dual_optimize()
+    }
+
+    fn next_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn new_symbol(&mut self, kind: SymbolKind) -> Symbol {
+        Symbol { id: self.next_id(), kind: kind }
+    }
+
+    fn var_symbol(&mut self, var: Variable) -> Symbol {
+        if let Some(&symbol) = self.vars.get(&var) {
+            return symbol;
+        }
+
+        let symbol = self.new_symbol(SymbolKind::External);
+        self.vars.insert(var, symbol);
+        symbol
+    }
+
+    /// Build the tableau row for a constraint, with basic variables substituted out.
+    fn create_row(&mut self, constraint: &Constraint) -> (Row, Tag) {
+        let mut row = Row::new(constraint.expr.constant);
+
+        for &(var, coefficient) in &constraint.expr.terms {
+            if near_zero(coefficient) {
+                continue;
+            }
+
+            let symbol = self.var_symbol(var);
+
+            match self.rows.get(&symbol) {
+                Some(basic) => row.insert_row(basic, coefficient),
+                None => row.insert_symbol(symbol, coefficient),
+            }
+        }
+
+        let strength = constraint.strength;
+
+        let tag = match constraint.relation {
+            Relation::AtMost | Relation::AtLeast => {
+                let coefficient = if constraint.relation == Relation::AtMost { 1.0 } else { -1.0 };
+
+                let slack = self.new_symbol(SymbolKind::Slack);
+                row.insert_symbol(slack, coefficient);
+
+                let other = if !strength.is_required() {
+                    let error = self.new_symbol(SymbolKind::Error);
+                    row.insert_symbol(error, -coefficient);
+                    self.objective.insert_symbol(error, strength.0);
+                    Some(error)
+                } else {
+                    None
+                };
+
+                Tag { marker: slack, other: other }
+            },
+            Relation::Equal => if !strength.is_required() {
+                let plus = self.new_symbol(SymbolKind::Error);
+                let minus = self.new_symbol(SymbolKind::Error);
+
+                row.insert_symbol(plus, -1.0);
+                row.insert_symbol(minus, 1.0);
+                self.objective.insert_symbol(plus, strength.0);
+                self.objective.insert_symbol(minus, strength.0);
+
+                Tag { marker: plus, other: Some(minus) }
+            } else {
+                let dummy = self.new_symbol(SymbolKind::Dummy);
+                row.insert_symbol(dummy, 1.0);
+
+                Tag { marker: dummy, other: None }
+            },
+        };
+
+        if row.constant < 0.0 {
+            row.reverse_sign();
+        }
+
+        (row, tag)
+    }
+
+    /// Pick the symbol to make basic for a new row: an external variable if there is one,
+    /// otherwise a new slack or error variable with a negative coefficient.
+    fn choose_subject(row: &Row, tag: Tag) -> Option<Symbol> {
+        if let Some(&symbol) = row.cells.keys().find(|symbol| symbol.kind == SymbolKind::External) {
+            return Some(symbol);
+        }
+
+        let candidates = Some(tag.marker).into_iter().chain(tag.other);
+
+        for symbol in candidates {
+            if symbol.is_pivotable() && row.coefficient_for(symbol) < 0.0 {
+                return Some(symbol);
+            }
+        }
+
+        None
+    }
+
+    /// Add a row by minimizing an artificial variable for it, returning whether the row
+    /// could be satisfied.
+    fn add_with_artificial_variable(&mut self, row: Row) -> Result<bool, SolverError> {
+        let art = self.new_symbol(SymbolKind::Slack);
+
+        self.rows.insert(art, row.clone());
+        self.artificial = Some(row);
+
+        try!(self.optimize(true));
+
+        let success = near_zero(self.artificial.take().unwrap().constant);
+
+        if let Some(mut row) = self.rows.remove(&art) {
+            if row.cells.is_empty() {
+                return Ok(success);
+            }
+
+            let entering = match row.cells.keys().cloned().find(Symbol::is_pivotable) {
+                Some(entering) => entering,
+                None => return Ok(false),
+            };
+
+            row.solve_for_pair(art, entering);
+            self.substitute(entering, &row);
+            self.rows.insert(entering, row);
+        }
+
+        for row in self.rows.values_mut() {
+            row.remove(art);
+        }
+
+        self.objective.remove(art);
+
+        Ok(success)
+    }
+
+    /// Replace `symbol` with `row` everywhere in the tableau.
+    fn substitute(&mut self, symbol: Symbol, row: &Row) {
+        for (&basic, other) in &mut self.rows {
+            other.substitute(symbol, row);
+
+            if basic.kind != SymbolKind::External && other.constant < 0.0 {
+                self.infeasible.push(basic);
+            }
+        }
+
+        self.objective.substitute(symbol, row);
+
+        if let Some(ref mut artificial) = self.artificial {
+            artificial.substitute(symbol, row);
+        }
+    }
+
+    /// Run the primal simplex method on the objective, or the artificial objective.
+    fn optimize(&mut self, artificial: bool) -> Result<(), SolverError> {
+        loop {
+            let entering = {
+                let objective = if artificial {
+                    self.artificial.as_ref().unwrap()
+                } else {
+                    &self.objective
+                };
+
+                objective.cells.iter()
+                    .find(|&(symbol, &value)| symbol.kind != SymbolKind::Dummy && value < 0.0)
+                    .map(|(&symbol, _)| symbol)
+            };
+
+            let entering = match entering {
+                Some(entering) => entering,
+                None => return Ok(()),
+            };
+
+            let leaving = try!(
+                self.leaving_row(entering).ok_or(SolverError::Internal("the objective is unbounded"))
+            );
+
+            let mut row = self.rows.remove(&leaving).unwrap();
+            row.solve_for_pair(leaving, entering);
+            self.substitute(entering, &row);
+            self.rows.insert(entering, row);
+        }
+    }
+
+    /// Restore feasibility after edit values changed, with the dual simplex method.
+    fn dual_optimize(&mut self) -> Result<(), SolverError> {
+        while let Some(leaving) = self.infeasible.pop() {
+            let entering = match self.rows.get(&leaving) {
+                Some(row) if row.constant < 0.0 => try!(
+                    self.dual_entering_symbol(row).ok_or(SolverError::Internal("dual optimize failed"))
+                ),
+                _ => continue,
+            };
+
+            let mut row = self.rows.remove(&leaving).unwrap();
+            row.solve_for_pair(leaving, entering);
+            self.substitute(entering, &row);
+            self.rows.insert(entering, row);
+        }
+
+        Ok(())
+    }
+
+    /// The row whose basic variable leaves when `entering` enters, by the minimum ratio test.
+    fn leaving_row(&self, entering: Symbol) -> Option<Symbol> {
+        let mut ratio = ::std::f64::MAX;
+        let mut found = None;
+
+        for (&symbol, row) in &self.rows {
+            if symbol.kind == SymbolKind::External {
+                continue;
+            }
+
+            let coefficient = row.coefficient_for(entering);
+
+            if coefficient < 0.0 {
+                let r = -row.constant / coefficient;
+
+                if r < ratio {
+                    ratio = r;
+                    found = Some(symbol);
+                }
+            }
+        }
+
+        found
+    }
+
+    fn dual_entering_symbol(&self, row: &Row) -> Option<Symbol> {
+        let mut ratio = ::std::f64::MAX;
+        let mut found = None;
+
+        for (&symbol, &value) in &row.cells {
+            if value > 0.0 && symbol.kind != SymbolKind::Dummy {
+                let r = self.objective.coefficient_for(symbol) / value;
+
+                if r < ratio {
+                    ratio = r;
+                    found = Some(symbol);
+                }
+            }
+        }
+
+        found
+    }
+
+    /// The row to pivot `marker` into when removing a constraint whose marker isn't basic.
+    fn marker_leaving_row(&self, marker: Symbol) -> Option<Symbol> {
+        let mut first = (::std::f64::MAX, None);
+        let mut second = (::std::f64::MAX, None);
+        let mut third = None;
+
+        for (&symbol, row) in &self.rows {
+            let coefficient = row.coefficient_for(marker);
+
+            if coefficient == 0.0 {
+                continue;
+            }
+
+            if symbol.kind == SymbolKind::External {
+                third = Some(symbol);
+            } else if coefficient < 0.0 {
+                let r = -row.constant / coefficient;
+
+                if r < first.0 {
+                    first = (r, Some(symbol));
+                }
+            } else {
+                let r = row.constant / coefficient;
+
+                if r < second.0 {
+                    second = (r, Some(symbol));
+                }
+            }
+        }
+
+        first.1.or(second.1).or(third)
+    }
+
+    /// Take the error variables of a constraint out of the objective.
+    fn remove_objective_errors(&mut self, tag: Tag, strength: Strength) {
+        let errors = Some(tag.marker).into_iter().chain(tag.other)
+            .filter(|symbol| symbol.kind == SymbolKind::Error);
+
+        for error in errors {
+            match self.rows.get(&error) {
+                Some(row) => self.objective.insert_row(row, -strength.0),
+                None => self.objective.insert_symbol(error, -strength.0),
+            }
+        }
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_value(solver: &Solver, var: Variable, expected: f64) {
+        let value = solver.value(var);
+        assert!((value - expected).abs() < 1e-6, "expected {}, got {}", expected, value);
+    }
+
+    #[test]
+    fn equalities() {
+        let mut solver = Solver::new();
+        let (x, y) = (solver.new_variable(), solver.new_variable());
+
+        solver.add_constraint(Constraint::equal(x, 10.0)).unwrap();
+        solver.add_constraint(Constraint::equal(y, x * 2.0 + 5)).unwrap();
+
+        assert_value(&solver, x, 10.0);
+        assert_value(&solver, y, 25.0);
+    }
+
+    #[test]
+    fn unconstrained_variables_are_zero() {
+        let mut solver = Solver::new();
+        let x = solver.new_variable();
+
+        assert_value(&solver, x, 0.0);
+    }
+
+    #[test]
+    fn required_inequality_beats_preference() {
+        let mut solver = Solver::new();
+        let x = solver.new_variable();
+
+        solver.add_constraint(Constraint::at_least(x, 100.0)).unwrap();
+        solver.add_constraint(Constraint::equal(x, 50.0).strength(WEAK)).unwrap();
+        assert_value(&solver, x, 100.0);
+
+        solver.add_constraint(Constraint::at_most(x, 80.0).strength(STRONG)).unwrap();
+        assert_value(&solver, x, 100.0);
+    }
+
+    #[test]
+    fn stronger_preference_wins() {
+        let mut solver = Solver::new();
+        let x = solver.new_variable();
+
+        solver.add_constraint(Constraint::equal(x, 20.0).strength(WEAK)).unwrap();
+        solver.add_constraint(Constraint::equal(x, 10.0).strength(MEDIUM)).unwrap();
+        assert_value(&solver, x, 10.0);
+
+        let medium_plus = Strength::new(0.0, 2.0, 0.0);
+        solver.add_constraint(Constraint::equal(x, 30.0).strength(medium_plus)).unwrap();
+        assert_value(&solver, x, 30.0);
+    }
+
+    #[test]
+    fn clamped_between_bounds() {
+        let mut solver = Solver::new();
+        let x = solver.new_variable();
+
+        solver.add_constraint(Constraint::at_least(x, 10.0)).unwrap();
+        solver.add_constraint(Constraint::at_most(x, 50.0)).unwrap();
+        solver.add_constraint(Constraint::equal(x, 100.0).strength(WEAK)).unwrap();
+
+        assert_value(&solver, x, 50.0);
+    }
+
+    #[test]
+    fn unsatisfiable_constraint_is_rejected() {
+        let mut solver = Solver::new();
+        let (x, y) = (solver.new_variable(), solver.new_variable());
+
+        solver.add_constraint(Constraint::equal(x + y, 20.0)).unwrap();
+        solver.add_constraint(Constraint::equal(x, 5.0)).unwrap();
+
+        let unsatisfiable = Err(SolverError::Unsatisfiable);
+        assert_eq!(solver.add_constraint(Constraint::equal(y, 30.0)), unsatisfiable);
+        assert_eq!(solver.add_constraint(Constraint::at_least(x, 6.0)), unsatisfiable);
+
+        // The solution is untouched.
+        assert_value(&solver, x, 5.0);
+        assert_value(&solver, y, 15.0);
+    }
+
+    #[test]
+    fn remove_constraint() {
+        let mut solver = Solver::new();
+        let x = solver.new_variable();
+
+        solver.add_constraint(Constraint::equal(x, 10.0).strength(WEAK)).unwrap();
+        let id = solver.add_constraint(Constraint::at_least(x, 40.0)).unwrap();
+        assert_value(&solver, x, 40.0);
+
+        solver.remove_constraint(id).unwrap();
+        assert_value(&solver, x, 10.0);
+
+        assert_eq!(solver.remove_constraint(id), Err(SolverError::UnknownConstraint));
+    }
+
+    #[test]
+    fn suggest_values() {
+        let mut solver = Solver::new();
+        let left = solver.new_variable();
+        let width = solver.new_variable();
+        let right = solver.new_variable();
+
+        solver.add_constraint(Constraint::equal(right, left + width)).unwrap();
+        solver.add_constraint(Constraint::at_least(width, 30.0)).unwrap();
+        solver.add_constraint(Constraint::at_most(right, 100.0)).unwrap();
+
+        solver.add_edit_variable(left, STRONG).unwrap();
+        solver.add_edit_variable(width, MEDIUM).unwrap();
+
+        solver.suggest_value(left, 20.0).unwrap();
+        solver.suggest_value(width, 50.0).unwrap();
+        assert_value(&solver, right, 70.0);
+
+        // The width gives way to the stronger left edge.
+        solver.suggest_value(left, 60.0).unwrap();
+        assert_value(&solver, width, 40.0);
+        assert_value(&solver, right, 100.0);
+
+        // Neither can go below the required minimum width.
+        solver.suggest_value(left, 90.0).unwrap();
+        assert_value(&solver, width, 30.0);
+        assert_value(&solver, left, 70.0);
+
+        solver.suggest_value(left, 0.0).unwrap();
+        assert_value(&solver, left, 0.0);
+        assert_value(&solver, right, 50.0);
+    }
+
+    #[test]
+    fn edit_variable_errors() {
+        let mut solver = Solver::new();
+        let (x, y) = (solver.new_variable(), solver.new_variable());
+
+        assert_eq!(solver.add_edit_variable(x, REQUIRED), Err(SolverError::RequiredEditVariable));
+        assert_eq!(solver.suggest_value(x, 1.0), Err(SolverError::UnknownEditVariable));
+
+        solver.add_edit_variable(x, STRONG).unwrap();
+        assert!(solver.has_edit_variable(x));
+        assert_eq!(solver.add_edit_variable(x, WEAK), Err(SolverError::DuplicateEditVariable));
+
+        solver.remove_edit_variable(x).unwrap();
+        assert!(!solver.has_edit_variable(x));
+        assert_eq!(solver.remove_edit_variable(y), Err(SolverError::UnknownEditVariable));
+    }
+}
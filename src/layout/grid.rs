@@ -0,0 +1,261 @@
+use super::{distribute, Child, Insets, LayoutItem, Placement, Rect, Size};
+
+use std::cmp;
+
+/// Arranges items in rows and columns.
+///
+/// Each row is as tall as its tallest item and each column as wide as its widest, with any
+/// extra space shared out according to the row and column stretch factors.
+pub struct Grid {
+    cells: Vec<Cell>,
+    row_stretch: Vec<u32>,
+    column_stretch: Vec<u32>,
+    row_spacing: i32,
+    column_spacing: i32,
+    padding: Insets,
+}
+
+struct Cell {
+    child: Child,
+    row: usize,
+    column: usize,
+    row_span: usize,
+    column_span: usize,
+}
+
+impl Grid {
+    pub fn new() -> Grid {
+        Grid {
+            cells: Vec::new(),
+            row_stretch: Vec::new(),
+            column_stretch: Vec::new(),
+            row_spacing: 0,
+            column_spacing: 0,
+            padding: Insets::default(),
+        }
+    }
+
+    /// Set the space between rows and between columns.
+    pub fn spacing(mut self, row_spacing: i32, column_spacing: i32) -> Self {
+        self.row_spacing = row_spacing;
+        self.column_spacing = column_spacing;
+        self
+    }
+
+    /// Set the space between the edges of the grid and its items.
+    pub fn padding(mut self, padding: Insets) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn row_stretch(mut self, row: usize, stretch: u32) -> Self {
+        set_stretch(&mut self.row_stretch, row, stretch);
+        self
+    }
+
+    pub fn column_stretch(mut self, column: usize, stretch: u32) -> Self {
+        set_stretch(&mut self.column_stretch, column, stretch);
+        self
+    }
+
+    /// Put an item in a single cell, filling it.
+    pub fn add<I: LayoutItem + 'static>(self, item: I, row: usize, column: usize) -> Self {
+        self.add_span(item, row, column, 1, 1, Placement::default())
+    }
+
+    /// Put an item in the cells `row .. row + row_span`, `column .. column + column_span`.
+    ///
+    /// The `stretch` of the placement is ignored; use `row_stretch()` and
+    /// `column_stretch()` instead.
+    pub fn add_span<I: LayoutItem + 'static>(
+        mut self, item: I, row: usize, column: usize,
+        row_span: usize, column_span: usize, placement: Placement
+    ) -> Self {
+        assert!(row_span > 0 && column_span > 0, "Grid items must span at least one cell");
+
+        self.cells.push(Cell {
+            child: Child {
+                item: Box::new(item),
+                placement: placement,
+            },
+            row: row,
+            column: column,
+            row_span: row_span,
+            column_span: column_span,
+        });
+
+        self
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.iter().map(|cell| cell.row + cell.row_span).max().unwrap_or(0)
+    }
+
+    pub fn columns(&self) -> usize {
+        self.cells.iter().map(|cell| cell.column + cell.column_span).max().unwrap_or(0)
+    }
+
+    /// The minimum height of each row and width of each column.
+    fn track_mins(&self) -> (Vec<i32>, Vec<i32>) {
+        let mut rows = vec![0; self.rows()];
+        let mut columns = vec![0; self.columns()];
+
+        // Single cells first, then make sure spanning items fit in the tracks they cover.
+        let (single, spanning): (Vec<_>, Vec<_>) = self.cells.iter()
+            .partition(|cell| cell.row_span == 1 && cell.column_span == 1);
+
+        for cell in single {
+            let min = cell.child.min_size();
+            rows[cell.row] = cmp::max(rows[cell.row], min.height);
+            columns[cell.column] = cmp::max(columns[cell.column], min.width);
+        }
+
+        for cell in spanning {
+            let min = cell.child.min_size();
+
+            grow_span(&mut rows[cell.row .. cell.row + cell.row_span], min.height, self.row_spacing);
+            grow_span(
+                &mut columns[cell.column .. cell.column + cell.column_span],
+                min.width, self.column_spacing
+            );
+        }
+
+        (rows, columns)
+    }
+}
+
+impl LayoutItem for Grid {
+    fn min_size(&self) -> Size {
+        let (rows, columns) = self.track_mins();
+
+        let height = rows.iter().fold(0, |sum, &row| sum + row) + spacing(rows.len(), self.row_spacing);
+        let width = columns.iter().fold(0, |sum, &col| sum + col) + spacing(columns.len(), self.column_spacing);
+
+        Size::new(width, height).outset(self.padding)
+    }
+
+    fn arrange(&mut self, bounds: Rect) {
+        let inner = bounds.inset(self.padding);
+        let (row_mins, column_mins) = self.track_mins();
+
+        let row_stretch = stretches(&self.row_stretch, row_mins.len());
+        let column_stretch = stretches(&self.column_stretch, column_mins.len());
+
+        let heights = distribute(
+            &row_mins, &row_stretch, inner.height - spacing(row_mins.len(), self.row_spacing)
+        );
+        let widths = distribute(
+            &column_mins, &column_stretch, inner.width - spacing(column_mins.len(), self.column_spacing)
+        );
+
+        let row_starts = starts(inner.y, &heights, self.row_spacing);
+        let column_starts = starts(inner.x, &widths, self.column_spacing);
+
+        for cell in &mut self.cells {
+            let last_row = cell.row + cell.row_span - 1;
+            let last_column = cell.column + cell.column_span - 1;
+
+            let x = column_starts[cell.column];
+            let y = row_starts[cell.row];
+
+            let rect = Rect::new(
+                x, y,
+                column_starts[last_column] + widths[last_column] - x,
+                row_starts[last_row] + heights[last_row] - y,
+            );
+
+            cell.child.arrange(rect);
+        }
+    }
+}
+
+fn set_stretch(stretches: &mut Vec<u32>, index: usize, stretch: u32) {
+    if stretches.len() <= index {
+        stretches.resize(index + 1, 0);
+    }
+
+    stretches[index] = stretch;
+}
+
+/// The stretch factors for `count` tracks, defaulting to 0.
+fn stretches(set: &[u32], count: usize) -> Vec<u32> {
+    (0 .. count).map(|i| set.get(i).cloned().unwrap_or(0)).collect()
+}
+
+fn spacing(count: usize, spacing: i32) -> i32 {
+    cmp::max(count as i32 - 1, 0) * spacing
+}
+
+/// The starting coordinate of each track.
+fn starts(origin: i32, sizes: &[i32], spacing: i32) -> Vec<i32> {
+    let mut pos = origin;
+
+    sizes.iter().map(|&size| {
+        let start = pos;
+        pos += size + spacing;
+        start
+    }).collect()
+}
+
+/// Enlarge the last of `tracks` so that together, with spacing, they are at least `min`.
+fn grow_span(tracks: &mut [i32], min: i32, track_spacing: i32) {
+    let current = tracks.iter().fold(0, |sum, &track| sum + track) + spacing(tracks.len(), track_spacing);
+
+    if current < min {
+        let last = tracks.len() - 1;
+        tracks[last] += min - current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use layout::tests::Probe;
+    use layout::{Insets, LayoutItem, Placement, Rect, Size};
+
+    use super::Grid;
+
+    #[test]
+    fn min_size_takes_largest_in_each_track() {
+        let grid = Grid::new()
+            .spacing(2, 4)
+            .padding(Insets::uniform(1))
+            .add(Probe::new(10, 5), 0, 0)
+            .add(Probe::new(30, 8), 0, 1)
+            .add(Probe::new(20, 12), 1, 0);
+
+        assert_eq!(grid.rows(), 2);
+        assert_eq!(grid.columns(), 2);
+        assert_eq!(grid.min_size(), Size::new(1 + 20 + 4 + 30 + 1, 1 + 8 + 2 + 12 + 1));
+    }
+
+    #[test]
+    fn spanning_item_grows_last_track() {
+        let grid = Grid::new()
+            .spacing(0, 10)
+            .add(Probe::new(20, 10), 0, 0)
+            .add(Probe::new(20, 10), 0, 1)
+            .add_span(Probe::new(100, 10), 1, 0, 1, 2, Placement::new());
+
+        // The columns and spacing give 50, so the second column grows by 50.
+        assert_eq!(grid.min_size(), Size::new(100, 20));
+    }
+
+    #[test]
+    fn arrange_stretches_tracks() {
+        let (a, b, wide) = (Probe::new(20, 10), Probe::new(20, 10), Probe::new(10, 10));
+
+        let mut grid = Grid::new()
+            .spacing(5, 10)
+            .column_stretch(1, 1)
+            .row_stretch(1, 1)
+            .add(a.clone(), 0, 0)
+            .add(b.clone(), 0, 1)
+            .add_span(wide.clone(), 1, 0, 1, 2, Placement::new());
+
+        grid.arrange(Rect::new(0, 0, 110, 55));
+
+        assert_eq!(a.bounds(), Rect::new(0, 0, 20, 10));
+        assert_eq!(b.bounds(), Rect::new(30, 0, 80, 10));
+        assert_eq!(wide.bounds(), Rect::new(0, 15, 110, 40));
+    }
+}
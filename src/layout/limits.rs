@@ -0,0 +1,193 @@
+use super::{Rect, Size};
+
+use std::cmp;
+
+/// The sizes a window's client area is allowed to take.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SizeLimits {
+    pub min: Size,
+    pub max: Option<Size>,
+    /// Width divided by height.
+    pub aspect_ratio: Option<f64>,
+}
+
+impl SizeLimits {
+    /// Clamp `size` between the minimum and maximum.
+    pub fn clamp(&self, size: Size) -> Size {
+        let max = self.max.unwrap_or(Size::new(::std::i32::MAX, ::std::i32::MAX));
+
+        Size::new(
+            cmp::max(cmp::min(size.width, max.width), self.min.width),
+            cmp::max(cmp::min(size.height, max.height), self.min.height),
+        )
+    }
+}
+
+/// The edge or corner of a window being dragged to resize it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SizingEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl SizingEdge {
+    fn moves_left(self) -> bool {
+        match self {
+            SizingEdge::Left | SizingEdge::TopLeft | SizingEdge::BottomLeft => true,
+            _ => false,
+        }
+    }
+
+    fn moves_top(self) -> bool {
+        match self {
+            SizingEdge::Top | SizingEdge::TopLeft | SizingEdge::TopRight => true,
+            _ => false,
+        }
+    }
+
+    /// Whether only the height is being dragged, so the width follows it.
+    fn is_vertical(self) -> bool {
+        self == SizingEdge::Top || self == SizingEdge::Bottom
+    }
+}
+
+/// Adjust the window rectangle `rect` proposed while dragging `edge` so that the client
+/// area satisfies `limits`.
+///
+/// `frame` is how much larger the window is than its client area. The edges that aren't
+/// being dragged stay where they are. When keeping the aspect ratio, dragging a side
+/// resizes the other dimension to match, and dragging a corner follows the width.
+pub fn adjust_sizing_rect(rect: Rect, edge: SizingEdge, limits: &SizeLimits, frame: Size) -> Rect {
+    let client = Size::new(
+        cmp::max(rect.width - frame.width, 0), cmp::max(rect.height - frame.height, 0)
+    );
+
+    let mut size = limits.clamp(client);
+
+    if let Some(ratio) = limits.aspect_ratio {
+        if ratio > 0.0 {
+            if edge.is_vertical() {
+                size.width = (size.height as f64 * ratio).round() as i32;
+            } else {
+                size.height = (size.width as f64 / ratio).round() as i32;
+            }
+
+            // If the matched dimension went out of bounds, clamp it and match the other one
+            // to it instead.
+            let clamped = limits.clamp(size);
+
+            if clamped.width != size.width {
+                size = Size::new(clamped.width, (clamped.width as f64 / ratio).round() as i32);
+            } else if clamped.height != size.height {
+                size = Size::new((clamped.height as f64 * ratio).round() as i32, clamped.height);
+            }
+        }
+    }
+
+    let width = size.width + frame.width;
+    let height = size.height + frame.height;
+
+    let x = if edge.moves_left() { rect.right() - width } else { rect.x };
+    let y = if edge.moves_top() { rect.bottom() - height } else { rect.y };
+
+    Rect::new(x, y, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Size = Size { width: 16, height: 39 };
+
+    fn limits(min: (i32, i32), max: Option<(i32, i32)>, aspect_ratio: Option<f64>) -> SizeLimits {
+        SizeLimits {
+            min: Size::new(min.0, min.1),
+            max: max.map(|(width, height)| Size::new(width, height)),
+            aspect_ratio: aspect_ratio,
+        }
+    }
+
+    #[test]
+    fn clamp() {
+        let limits = limits((100, 50), Some((400, 300)), None);
+
+        assert_eq!(limits.clamp(Size::new(10, 1000)), Size::new(100, 300));
+        assert_eq!(limits.clamp(Size::new(200, 100)), Size::new(200, 100));
+        assert_eq!(SizeLimits::default().clamp(Size::new(-5, 5)), Size::new(0, 5));
+    }
+
+    #[test]
+    fn within_limits_is_unchanged() {
+        let rect = Rect::new(10, 20, 316, 239);
+        let limits = limits((100, 100), Some((500, 500)), None);
+
+        assert_eq!(adjust_sizing_rect(rect, SizingEdge::BottomRight, &limits, FRAME), rect);
+    }
+
+    #[test]
+    fn min_size_keeps_opposite_edge() {
+        let limits = limits((200, 100), None, None);
+
+        // Dragging the right edge keeps the left one in place.
+        let rect = adjust_sizing_rect(Rect::new(10, 20, 50, 239), SizingEdge::Right, &limits, FRAME);
+        assert_eq!(rect, Rect::new(10, 20, 216, 239));
+
+        // Dragging the top left corner keeps the bottom right corner in place.
+        let rect = adjust_sizing_rect(Rect::new(300, 300, 50, 50), SizingEdge::TopLeft, &limits, FRAME);
+        assert_eq!(rect, Rect::new(134, 211, 216, 139));
+    }
+
+    #[test]
+    fn max_size() {
+        let limits = limits((0, 0), Some((300, 200)), None);
+
+        let rect = adjust_sizing_rect(Rect::new(0, 0, 1000, 1000), SizingEdge::Bottom, &limits, FRAME);
+        assert_eq!(rect, Rect::new(0, 0, 316, 239));
+    }
+
+    #[test]
+    fn aspect_ratio_follows_dragged_dimension() {
+        let limits = limits((0, 0), None, Some(2.0));
+
+        // Sides change the other dimension to match.
+        let rect = adjust_sizing_rect(Rect::new(0, 0, 416, 139), SizingEdge::Right, &limits, FRAME);
+        assert_eq!(rect, Rect::new(0, 0, 416, 239));
+
+        let rect = adjust_sizing_rect(Rect::new(0, 0, 416, 139), SizingEdge::Top, &limits, FRAME);
+        assert_eq!(rect, Rect::new(0, 0, 216, 139));
+
+        // Corners follow the width.
+        let rect = Rect::new(0, 0, 416, 139);
+        let rect = adjust_sizing_rect(rect, SizingEdge::BottomLeft, &limits, FRAME);
+        assert_eq!(rect, Rect::new(0, 0, 416, 239));
+    }
+
+    #[test]
+    fn aspect_ratio_within_limits() {
+        let limits = limits((100, 100), Some((600, 250)), Some(2.0));
+
+        // Matching the height to a width of 800 would go over the maximum, so the width is
+        // matched to the maximum height instead.
+        let rect = adjust_sizing_rect(Rect::new(0, 0, 816, 339), SizingEdge::Right, &limits, FRAME);
+        assert_eq!(rect, Rect::new(0, 0, 516, 289));
+
+        // A width of 50 is raised to the minimum of 100, but that's too short for the
+        // minimum height, so the width is matched to it.
+        let rect = adjust_sizing_rect(Rect::new(0, 0, 66, 339), SizingEdge::Right, &limits, FRAME);
+        assert_eq!(rect, Rect::new(0, 0, 216, 139));
+    }
+
+    #[test]
+    fn frame_larger_than_rect() {
+        let limits = SizeLimits::default();
+
+        let rect = adjust_sizing_rect(Rect::new(0, 0, 5, 5), SizingEdge::BottomRight, &limits, FRAME);
+        assert_eq!(rect, Rect::new(0, 0, 16, 39));
+    }
+}
@@ -0,0 +1,406 @@
+//! Automatic positioning of controls within a window.
+//!
+//! Layouts work on plain rectangles: anything implementing `LayoutItem` can be arranged,
+//! and the containers here don't touch any window handles themselves. Attach a layout to
+//! a window with `Window::set_layout()` to have it rerun whenever the window is resized.
+//!
+//! Controls can also be docked or anchored individually with `Window::set_dock()` and
+//! `Window::set_anchor()`; docked controls are placed first and the layout gets the rest.
+//!
+//! All sizes and positions are in logical units, which `Widget` scales to the DPI of its
+//! control (see the `dpi` module).
+
+#[cfg(windows)] use abs_window::AbsWindow;
+#[cfg(windows)] use dpi::Dpi;
+
+#[cfg(windows)] use winapi::HWND;
+
+use std::cmp;
+
+pub mod constraint;
+
+mod anchor;
+mod grid;
+mod limits;
+mod stack;
+
+pub use self::anchor::{Anchor, AnchorLayout, Dock};
+pub use self::constraint::ConstraintLayout;
+pub use self::limits::{adjust_sizing_rect, SizeLimits, SizingEdge};
+pub use self::grid::Grid;
+pub use self::stack::{HStack, VStack};
+
+/// A rectangle in logical units, or in pixels where noted.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect { x: x, y: y, width: width, height: height }
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// Shrink the rectangle by `insets`, without going below zero width or height.
+    pub fn inset(&self, insets: Insets) -> Rect {
+        Rect {
+            x: self.x + insets.left,
+            y: self.y + insets.top,
+            width: cmp::max(self.width - insets.horizontal(), 0),
+            height: cmp::max(self.height - insets.vertical(), 0),
+        }
+    }
+}
+
+/// A width and height in logical units, or in pixels where noted.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Size {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Size {
+    pub fn new(width: i32, height: i32) -> Size {
+        Size { width: width, height: height }
+    }
+
+    /// Grow the size by `insets`.
+    pub fn outset(&self, insets: Insets) -> Size {
+        Size::new(self.width + insets.horizontal(), self.height + insets.vertical())
+    }
+}
+
+/// Space around the edges of a rectangle, for margins and padding.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Insets {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Insets {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Insets {
+        Insets { left: left, top: top, right: right, bottom: bottom }
+    }
+
+    /// The same amount of space on every side.
+    pub fn uniform(amount: i32) -> Insets {
+        Insets::new(amount, amount, amount, amount)
+    }
+
+    pub fn horizontal(&self) -> i32 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> i32 {
+        self.top + self.bottom
+    }
+}
+
+/// How an item is positioned within space larger than it needs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    /// Take up all of the space.
+    Fill,
+}
+
+impl Default for Align {
+    fn default() -> Self {
+        Align::Fill
+    }
+}
+
+impl Align {
+    /// Position an item of `size` within `avail` units starting at `start`, returning its
+    /// start and length.
+    pub fn place(self, start: i32, avail: i32, size: i32) -> (i32, i32) {
+        let size = cmp::min(size, avail);
+
+        match self {
+            Align::Start => (start, size),
+            Align::Center => (start + (avail - size) / 2, size),
+            Align::End => (start + avail - size, size),
+            Align::Fill => (start, avail),
+        }
+    }
+}
+
+/// Something that can be positioned by a layout: a control, or another layout.
+pub trait LayoutItem {
+    /// The smallest size this item can be given.
+    fn min_size(&self) -> Size;
+
+    /// Position this item, and anything inside it, within `bounds`.
+    fn arrange(&mut self, bounds: Rect);
+}
+
+impl<T: LayoutItem + ?Sized> LayoutItem for Box<T> {
+    fn min_size(&self) -> Size {
+        (**self).min_size()
+    }
+
+    fn arrange(&mut self, bounds: Rect) {
+        (**self).arrange(bounds)
+    }
+}
+
+/// A control placed in a layout.
+///
+/// This does not own the control; it has to be kept alive separately.
+#[cfg(windows)]
+pub struct Widget {
+    hwnd: HWND,
+    min_size: Size,
+}
+
+#[cfg(windows)]
+impl Widget {
+    /// Place `window`, with its measured size as the minimum.
+    pub fn new<W: AbsWindow>(window: &W) -> Widget {
+        Widget {
+            hwnd: window.ptr(),
+            min_size: window.measure(Size::new(::std::i32::MAX, ::std::i32::MAX)),
+        }
+    }
+
+    /// Set the smallest size the control may be given, instead of its measured size.
+    pub fn min_size(mut self, width: i32, height: i32) -> Self {
+        self.min_size = Size::new(width, height);
+        self
+    }
+}
+
+#[cfg(windows)]
+impl AbsWindow for Widget {
+    fn ptr(&self) -> HWND {
+        self.hwnd
+    }
+}
+
+#[cfg(windows)]
+impl LayoutItem for Widget {
+    fn min_size(&self) -> Size {
+        self.min_size
+    }
+
+    fn arrange(&mut self, bounds: Rect) {
+        self.set_bounds(Dpi::of_hwnd(self.hwnd).scale_rect(bounds));
+    }
+}
+
+/// How an item is placed within the space a container gives it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Placement {
+    pub margin: Insets,
+    /// The share of any extra space given to this item, relative to the other items.
+    /// Items with a stretch of 0 are only given their minimum size.
+    pub stretch: u32,
+    pub h_align: Align,
+    pub v_align: Align,
+}
+
+impl Placement {
+    pub fn new() -> Placement {
+        Placement::default()
+    }
+
+    pub fn margin(mut self, margin: Insets) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn stretch(mut self, stretch: u32) -> Self {
+        self.stretch = stretch;
+        self
+    }
+
+    pub fn h_align(mut self, align: Align) -> Self {
+        self.h_align = align;
+        self
+    }
+
+    pub fn v_align(mut self, align: Align) -> Self {
+        self.v_align = align;
+        self
+    }
+
+    /// Position an item with minimum size `min` within `cell`, after applying the margin.
+    fn place(&self, cell: Rect, min: Size) -> Rect {
+        let inner = cell.inset(self.margin);
+
+        let (x, width) = self.h_align.place(inner.x, inner.width, min.width);
+        let (y, height) = self.v_align.place(inner.y, inner.height, min.height);
+
+        Rect::new(x, y, width, height)
+    }
+}
+
+/// An item in a container, with its placement.
+struct Child {
+    item: Box<LayoutItem>,
+    placement: Placement,
+}
+
+impl Child {
+    /// The minimum size of the item including its margin.
+    fn min_size(&self) -> Size {
+        self.item.min_size().outset(self.placement.margin)
+    }
+
+    fn arrange(&mut self, cell: Rect) {
+        let bounds = self.placement.place(cell, self.item.min_size());
+        self.item.arrange(bounds);
+    }
+}
+
+/// Split `total` units between slots with the given minimum sizes.
+///
+/// Space beyond the minimums is shared out according to `stretches`; if no slot stretches,
+/// every slot gets exactly its minimum. If `total` is less than the sum of the minimums,
+/// slots still get their minimum and overflow.
+pub fn distribute(mins: &[i32], stretches: &[u32], total: i32) -> Vec<i32> {
+    assert_eq!(mins.len(), stretches.len());
+
+    let mut sizes = mins.to_vec();
+
+    let min_total = mins.iter().fold(0, |sum, &min| sum + min);
+    let stretch_total = stretches.iter().fold(0, |sum, &stretch| sum + stretch);
+
+    let extra = total - min_total;
+
+    if extra <= 0 || stretch_total == 0 {
+        return sizes;
+    }
+
+    let mut given = 0;
+
+    for (size, &stretch) in sizes.iter_mut().zip(stretches) {
+        let share = (extra as i64 * stretch as i64 / stretch_total as i64) as i32;
+        *size += share;
+        given += share;
+    }
+
+    // Hand out the units lost to rounding one at a time, so the total is exact.
+    let mut left = extra - given;
+
+    for (size, &stretch) in sizes.iter_mut().zip(stretches) {
+        if left == 0 {
+            break;
+        }
+
+        if stretch > 0 {
+            *size += 1;
+            left -= 1;
+        }
+    }
+
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// An item of a fixed minimum size that records where it was put.
+    #[derive(Clone)]
+    pub struct Probe {
+        min: Size,
+        bounds: Rc<Cell<Rect>>,
+    }
+
+    impl Probe {
+        pub fn new(width: i32, height: i32) -> Probe {
+            Probe { min: Size::new(width, height), bounds: Rc::new(Cell::new(Rect::default())) }
+        }
+
+        pub fn bounds(&self) -> Rect {
+            self.bounds.get()
+        }
+    }
+
+    impl LayoutItem for Probe {
+        fn min_size(&self) -> Size {
+            self.min
+        }
+
+        fn arrange(&mut self, bounds: Rect) {
+            self.bounds.set(bounds);
+        }
+    }
+
+    #[test]
+    fn distribute_by_stretch() {
+        assert_eq!(distribute(&[10, 20, 30], &[1, 0, 3], 100), vec![20, 20, 60]);
+    }
+
+    #[test]
+    fn distribute_rounding_is_exact() {
+        let sizes = distribute(&[0, 0, 0], &[1, 1, 1], 10);
+
+        assert_eq!(sizes, vec![4, 3, 3]);
+        assert_eq!(sizes.iter().fold(0, |sum, &size| sum + size), 10);
+    }
+
+    #[test]
+    fn distribute_without_stretch_gives_minimums() {
+        assert_eq!(distribute(&[10, 20], &[0, 0], 100), vec![10, 20]);
+    }
+
+    #[test]
+    fn distribute_overflow_keeps_minimums() {
+        assert_eq!(distribute(&[10, 20], &[1, 1], 15), vec![10, 20]);
+        assert_eq!(distribute(&[], &[], 15), vec![]);
+    }
+
+    #[test]
+    fn align_place() {
+        assert_eq!(Align::Start.place(10, 100, 30), (10, 30));
+        assert_eq!(Align::Center.place(10, 100, 30), (45, 30));
+        assert_eq!(Align::End.place(10, 100, 30), (80, 30));
+        assert_eq!(Align::Fill.place(10, 100, 30), (10, 100));
+
+        // Items larger than the space are cut down to it.
+        assert_eq!(Align::Center.place(10, 20, 30), (10, 20));
+    }
+
+    #[test]
+    fn inset_does_not_go_negative() {
+        let rect = Rect::new(0, 0, 10, 10);
+
+        assert_eq!(rect.inset(Insets::new(1, 2, 3, 4)), Rect::new(1, 2, 6, 4));
+        assert_eq!(rect.inset(Insets::uniform(8)), Rect::new(8, 8, 0, 0));
+    }
+
+    #[test]
+    fn placement_applies_margin_then_alignment() {
+        let placement = Placement::new()
+            .margin(Insets::uniform(5))
+            .h_align(Align::End)
+            .v_align(Align::Center);
+
+        let placed = placement.place(Rect::new(0, 0, 100, 50), Size::new(20, 10));
+        assert_eq!(placed, Rect::new(75, 20, 20, 10));
+    }
+}
@@ -0,0 +1,200 @@
+use controls::Orientation;
+
+use super::{distribute, Child, Insets, LayoutItem, Placement, Rect, Size};
+
+use std::cmp;
+
+/// The shared implementation of `HStack` and `VStack`.
+struct Stack {
+    orientation: Orientation,
+    children: Vec<Child>,
+    spacing: i32,
+    padding: Insets,
+}
+
+impl Stack {
+    fn new(orientation: Orientation) -> Stack {
+        Stack {
+            orientation: orientation,
+            children: Vec::new(),
+            spacing: 0,
+            padding: Insets::default(),
+        }
+    }
+
+    /// Split a size into (main axis, cross axis) components.
+    fn axes(&self, size: Size) -> (i32, i32) {
+        match self.orientation {
+            Orientation::Horizontal => (size.width, size.height),
+            Orientation::Vertical => (size.height, size.width),
+        }
+    }
+
+    fn total_spacing(&self) -> i32 {
+        cmp::max(self.children.len() as i32 - 1, 0) * self.spacing
+    }
+
+    fn min_size(&self) -> Size {
+        let (main, cross) = self.children.iter()
+            .map(|child| self.axes(child.min_size()))
+            .fold((0, 0), |(main, cross), (c_main, c_cross)| (main + c_main, cmp::max(cross, c_cross)));
+
+        let main = main + self.total_spacing();
+
+        let size = match self.orientation {
+            Orientation::Horizontal => Size::new(main, cross),
+            Orientation::Vertical => Size::new(cross, main),
+        };
+
+        size.outset(self.padding)
+    }
+
+    fn arrange(&mut self, bounds: Rect) {
+        let inner = bounds.inset(self.padding);
+        let (main_avail, _) = self.axes(inner.size());
+
+        let mins: Vec<i32> = self.children.iter().map(|child| self.axes(child.min_size()).0).collect();
+        let stretches: Vec<u32> = self.children.iter().map(|child| child.placement.stretch).collect();
+
+        let sizes = distribute(&mins, &stretches, main_avail - self.total_spacing());
+
+        let mut pos = 0;
+
+        for (child, size) in self.children.iter_mut().zip(sizes) {
+            let cell = match self.orientation {
+                Orientation::Horizontal => Rect::new(inner.x + pos, inner.y, size, inner.height),
+                Orientation::Vertical => Rect::new(inner.x, inner.y + pos, inner.width, size),
+            };
+
+            child.arrange(cell);
+
+            pos += size + self.spacing;
+        }
+    }
+}
+
+macro_rules! stack_type {
+    ($(#[$attr:meta])* pub struct $name:ident: $orientation:expr) => (
+        $(#[$attr])*
+        pub struct $name {
+            stack: Stack,
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    stack: Stack::new($orientation),
+                }
+            }
+
+            /// Set the space between consecutive items.
+            pub fn spacing(mut self, spacing: i32) -> Self {
+                self.stack.spacing = spacing;
+                self
+            }
+
+            /// Set the space between the edges of the stack and its items.
+            pub fn padding(mut self, padding: Insets) -> Self {
+                self.stack.padding = padding;
+                self
+            }
+
+            /// Append an item with the default placement: minimum size along the stack,
+            /// filling the space across it.
+            pub fn add<I: LayoutItem + 'static>(self, item: I) -> Self {
+                self.add_with(item, Placement::default())
+            }
+
+            pub fn add_with<I: LayoutItem + 'static>(mut self, item: I, placement: Placement) -> Self {
+                self.stack.children.push(Child {
+                    item: Box::new(item),
+                    placement: placement,
+                });
+
+                self
+            }
+
+            pub fn len(&self) -> usize {
+                self.stack.children.len()
+            }
+        }
+
+        impl LayoutItem for $name {
+            fn min_size(&self) -> Size {
+                self.stack.min_size()
+            }
+
+            fn arrange(&mut self, bounds: Rect) {
+                self.stack.arrange(bounds)
+            }
+        }
+    )
+}
+
+stack_type! {
+    /// Arranges items in a row, left to right.
+    pub struct HStack: Orientation::Horizontal
+}
+
+stack_type! {
+    /// Arranges items in a column, top to bottom.
+    pub struct VStack: Orientation::Vertical
+}
+
+#[cfg(test)]
+mod tests {
+    use layout::tests::Probe;
+    use layout::{Align, Insets, LayoutItem, Placement, Rect, Size};
+
+    use super::{HStack, VStack};
+
+    #[test]
+    fn min_size_includes_spacing_and_padding() {
+        let stack = HStack::new()
+            .spacing(4)
+            .padding(Insets::new(1, 2, 3, 4))
+            .add(Probe::new(10, 20))
+            .add(Probe::new(30, 5))
+            .add_with(Probe::new(10, 10), Placement::new().margin(Insets::uniform(2)));
+
+        assert_eq!(stack.min_size(), Size::new(1 + 10 + 4 + 30 + 4 + 14 + 3, 2 + 20 + 4));
+    }
+
+    #[test]
+    fn empty_stack_has_no_spacing() {
+        assert_eq!(VStack::new().spacing(10).min_size(), Size::new(0, 0));
+    }
+
+    #[test]
+    fn hstack_shares_extra_space_by_stretch() {
+        let (a, b, c) = (Probe::new(10, 10), Probe::new(20, 10), Probe::new(10, 30));
+
+        let mut stack = HStack::new()
+            .spacing(5)
+            .add(a.clone())
+            .add_with(b.clone(), Placement::new().stretch(1))
+            .add_with(c.clone(), Placement::new().stretch(3).v_align(Align::Center));
+
+        stack.arrange(Rect::new(100, 50, 150, 40));
+
+        // 150 - 10 of spacing leaves 100 beyond the minimums of 40.
+        assert_eq!(a.bounds(), Rect::new(100, 50, 10, 40));
+        assert_eq!(b.bounds(), Rect::new(115, 50, 45, 40));
+        assert_eq!(c.bounds(), Rect::new(165, 55, 85, 30));
+    }
+
+    #[test]
+    fn vstack_aligns_across() {
+        let (a, b) = (Probe::new(20, 10), Probe::new(20, 10));
+
+        let mut stack = VStack::new()
+            .padding(Insets::uniform(10))
+            .add_with(a.clone(), Placement::new().h_align(Align::Start))
+            .add_with(b.clone(), Placement::new().h_align(Align::End));
+
+        stack.arrange(Rect::new(0, 0, 100, 100));
+
+        assert_eq!(a.bounds(), Rect::new(10, 10, 20, 10));
+        assert_eq!(b.bounds(), Rect::new(70, 20, 20, 10));
+    }
+}
@@ -1,37 +1,66 @@
-#![feature(const_fn, std_panic, recover, fnbox)]
-
-#[macro_use] extern crate lazy_static;
-#[macro_use] extern crate log;
-
-extern crate winapi;
-extern crate kernel32;
-extern crate user32;
+#![cfg_attr(windows, feature(const_fn, std_panic, recover, fnbox))]
+
+#[cfg(windows)] #[macro_use] extern crate lazy_static;
+#[cfg(windows)] #[macro_use] extern crate log;
+
+#[cfg(windows)] extern crate winapi;
+#[cfg(windows)] extern crate gdi32;
+#[cfg(windows)] extern crate kernel32;
+#[cfg(windows)] extern crate user32;
+
+macro_rules! unwrap_or_ret (
+    ($expr:expr, $or:expr) => (
+        if let Some(val) = $expr {
+            val
+        } else {
+            return $or;
+        }
+    )
+);
 
-//mod abs_window;
+#[cfg(windows)] mod abs_window;
+#[cfg(windows)] mod accel;
 //mod context;
-mod ffi;
+#[cfg(windows)] mod ffi;
+// Message decoding builds everywhere, against stand-ins for the winapi types, to be tested.
+#[cfg(not(windows))] #[allow(dead_code)] mod ffi { pub mod msg; }
+#[cfg(windows)] mod focus;
 mod move_cell;
-mod winstr;
-
-pub mod window;
-// pub mod text;
-
+#[cfg(windows)] mod winstr;
+
+pub mod canvas;
+pub mod color;
+pub mod command;
+pub mod controls;
+pub mod dpi;
+#[cfg(windows)] pub mod font;
+pub mod input;
+pub mod layout;
+pub mod manifest;
+#[cfg(windows)] pub mod menu;
+#[cfg(windows)] pub mod theme;
+#[cfg(windows)] pub mod window;
+pub mod text;
+
+#[cfg(windows)] pub use abs_window::AbsWindow;
 pub use move_cell::MoveCell;
-use winstr::WinString;
-use window::Window;
+#[cfg(windows)] use winstr::WinString;
+#[cfg(windows)] use window::Window;
 
-use std::any::Any;
-use std::borrow::Cow;
-use std::cell::UnsafeCell;
-use std::error::Error;
-use std::ffi::OsStr;
-use std::os::windows::ffi::OsStrExt;
-use std::{mem, ptr, slice, thread};
+#[cfg(windows)] use std::any::Any;
+#[cfg(windows)] use std::borrow::Cow;
+#[cfg(windows)] use std::cell::UnsafeCell;
+#[cfg(windows)] use std::error::Error;
+#[cfg(windows)] use std::ffi::OsStr;
+#[cfg(windows)] use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)] use std::{mem, ptr, slice, thread};
 
-use std::panic::{self, RecoverSafe};
+#[cfg(windows)] use std::panic::{self, RecoverSafe};
 
+#[cfg(windows)]
 thread_local!(static LAST_ERR: MoveCell<Box<Any + Send>> = MoveCell::new());
 
+#[cfg(windows)]
 pub fn start<F>(init_fn: F) where F: FnOnce() -> Window {
     let window = init_fn();
 
@@ -43,6 +72,10 @@ pub fn start<F>(init_fn: F) where F: FnOnce() -> Window {
         let mut msg = mem::zeroed();
 
         while user32::GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+            if accel::translate(&mut msg) || focus::handle_navigation(&mut msg) {
+                continue;
+            }
+
             user32::TranslateMessage(&msg);
             user32::DispatchMessageW(&msg);
         }
@@ -55,24 +88,29 @@ pub fn start<F>(init_fn: F) where F: FnOnce() -> Window {
     }
 }
 
+#[cfg(windows)]
 fn post_last_err_msg() {
     post_error(ffi::WindowsError::last())
 }
 
+#[cfg(windows)]
 fn post_error<E: Any + Send>(err: E) {
     post_error_boxed(Box::new(err));
 }
 
+#[cfg(windows)]
 fn post_error_boxed(err: Box<Any + Send>) {
     LAST_ERR.with(move |last| last.set(err));
 }
 
+#[cfg(windows)]
 pub fn quit() {
     unsafe {
         user32::PostQuitMessage(0);
     }
 }
 
+#[cfg(windows)]
 fn recover<F, R>(closure: F) -> Option<R> where F: FnOnce() -> R + RecoverSafe {
     match panic::recover(closure) {
         Ok(res) => Some(res),
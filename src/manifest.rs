@@ -0,0 +1,145 @@
+//! Application manifests.
+//!
+//! With the `manifest` feature, the build script embeds a manifest with the default
+//! settings into the executables using this crate. Without it, controls keep the Windows 95
+//! look unless the application has a manifest of its own, which `manifest_xml()` can
+//! generate.
+
+/// What the application manifest declares.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ManifestSettings {
+    /// Use version 6 of the common controls, which draw with the visual style of the
+    /// system instead of the Windows 95 look.
+    pub common_controls_v6: bool,
+    /// Start per-monitor DPI aware, so that `dpi::enable_per_monitor_awareness()` isn't
+    /// needed.
+    pub per_monitor_dpi_aware: bool,
+    /// Make UTF-8 the code page of the `A` functions, on Windows 10 1903 and later.
+    pub utf8_code_page: bool,
+}
+
+impl Default for ManifestSettings {
+    fn default() -> Self {
+        ManifestSettings {
+            common_controls_v6: true,
+            per_monitor_dpi_aware: true,
+            utf8_code_page: true,
+        }
+    }
+}
+
+/// Generate the XML of an application manifest.
+pub fn manifest_xml(settings: &ManifestSettings) -> String {
+    let mut xml = String::new();
+
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    xml.push_str("<assembly xmlns=\"urn:schemas-microsoft-com:asm.v1\" manifestVersion=\"1.0\">\n");
+
+    if settings.common_controls_v6 {
+        xml.push_str(concat!(
+            "  <dependency>\n",
+            "    <dependentAssembly>\n",
+            "      <assemblyIdentity type=\"win32\" name=\"Microsoft.Windows.Common-Controls\" ",
+            "version=\"6.0.0.0\" processorArchitecture=\"*\" publicKeyToken=\"6595b64144ccf1df\" ",
+            "language=\"*\"/>\n",
+            "    </dependentAssembly>\n",
+            "  </dependency>\n",
+        ));
+    }
+
+    if settings.per_monitor_dpi_aware || settings.utf8_code_page {
+        xml.push_str("  <application xmlns=\"urn:schemas-microsoft-com:asm.v3\">\n");
+        xml.push_str("    <windowsSettings>\n");
+
+        if settings.per_monitor_dpi_aware {
+            // `dpiAware` is for Windows 8.1 and early versions of Windows 10, which ignore
+            // `dpiAwareness`.
+            xml.push_str(concat!(
+                "      <dpiAware xmlns=\"http://schemas.microsoft.com/SMI/2005/WindowsSettings\">",
+                "true/pm</dpiAware>\n",
+                "      <dpiAwareness xmlns=\"http://schemas.microsoft.com/SMI/2016/WindowsSettings\">",
+                "PerMonitorV2, PerMonitor</dpiAwareness>\n",
+            ));
+        }
+
+        if settings.utf8_code_page {
+            xml.push_str(concat!(
+                "      <activeCodePage xmlns=\"http://schemas.microsoft.com/SMI/2019/WindowsSettings\">",
+                "UTF-8</activeCodePage>\n",
+            ));
+        }
+
+        xml.push_str("    </windowsSettings>\n");
+        xml.push_str("  </application>\n");
+    }
+
+    xml.push_str("</assembly>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(common_controls_v6: bool, per_monitor_dpi_aware: bool, utf8_code_page: bool)
+        -> ManifestSettings
+    {
+        ManifestSettings {
+            common_controls_v6: common_controls_v6,
+            per_monitor_dpi_aware: per_monitor_dpi_aware,
+            utf8_code_page: utf8_code_page,
+        }
+    }
+
+    #[test]
+    fn default_declares_everything() {
+        let xml = manifest_xml(&Default::default());
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n"));
+        assert!(xml.contains("name=\"Microsoft.Windows.Common-Controls\" version=\"6.0.0.0\""));
+        assert!(xml.contains(">true/pm</dpiAware>"));
+        assert!(xml.contains(">PerMonitorV2, PerMonitor</dpiAwareness>"));
+        assert!(xml.contains(">UTF-8</activeCodePage>"));
+        assert!(xml.ends_with("</assembly>\n"));
+    }
+
+    #[test]
+    fn nothing_declared() {
+        assert_eq!(
+            manifest_xml(&settings(false, false, false)),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <assembly xmlns=\"urn:schemas-microsoft-com:asm.v1\" manifestVersion=\"1.0\">\n\
+             </assembly>\n"
+        );
+    }
+
+    #[test]
+    fn settings_are_independent() {
+        let xml = manifest_xml(&settings(true, false, false));
+        assert!(xml.contains("Common-Controls"));
+        assert!(!xml.contains("<application"));
+
+        let xml = manifest_xml(&settings(false, true, false));
+        assert!(!xml.contains("Common-Controls"));
+        assert!(xml.contains("<dpiAwareness"));
+        assert!(!xml.contains("<activeCodePage"));
+
+        let xml = manifest_xml(&settings(false, false, true));
+        assert!(!xml.contains("<dpiAware"));
+        assert!(xml.contains("<activeCodePage"));
+    }
+
+    #[test]
+    fn elements_are_balanced() {
+        let xml = manifest_xml(&Default::default());
+
+        for tag in &["assembly", "dependency", "dependentAssembly", "application",
+                     "windowsSettings", "dpiAware", "dpiAwareness", "activeCodePage"] {
+            let opened = xml.matches(&format!("<{}>", tag)).count() +
+                xml.matches(&format!("<{} ", tag)).count();
+
+            assert_eq!(opened, 1, "<{}> is opened {} times", tag, opened);
+            assert_eq!(xml.matches(&format!("</{}>", tag)).count(), 1, "</{}>", tag);
+        }
+    }
+}
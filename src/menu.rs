@@ -0,0 +1,405 @@
+//! Menu bars and context menus.
+//!
+//! A `Menu` describes its items, and is turned into a Windows menu when it's attached with
+//! `Window::set_menu()` or shown with `Window::show_context_menu()`. Choosing an item calls
+//! `Window::on_command()` with the item's `CommandId`.
+//!
+//! Checkable items toggle when they're chosen, and radio items check themselves and uncheck
+//! the radio items next to them, before the command is delivered.
+
+use {gdi32, user32};
+
+use winapi::*;
+
+use canvas::{self, Bitmap};
+use command::CommandId;
+use ffi::user::*;
+use input::Accelerator;
+use winstr::WinString;
+
+use std::{mem, ptr, slice};
+
+// Flags in `dwItemData`.
+/// The item toggles when chosen.
+const CHECKABLE: ULONG_PTR = 1;
+/// The label ends with a shortcut added by `show_shortcuts()`.
+const SHOWS_SHORTCUT: ULONG_PTR = 2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CheckKind {
+    None,
+    Checkbox,
+    Radio,
+}
+
+/// An item of a `Menu`, which either triggers a command or opens a submenu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MenuItem {
+    id: Option<CommandId>,
+    text: String,
+    submenu: Option<Menu>,
+    check: CheckKind,
+    checked: bool,
+    enabled: bool,
+    icon: Option<Bitmap>,
+}
+
+impl MenuItem {
+    /// An item triggering `id`. An `&` in `text` underlines the next character as the
+    /// item's access key, and `&&` shows an `&`.
+    pub fn new<T: AsRef<str>>(id: CommandId, text: T) -> MenuItem {
+        MenuItem {
+            id: Some(id),
+            text: text.as_ref().to_string(),
+            submenu: None,
+            check: CheckKind::None,
+            checked: false,
+            enabled: true,
+            icon: None,
+        }
+    }
+
+    /// An item opening `menu`.
+    pub fn submenu<T: AsRef<str>>(text: T, menu: Menu) -> MenuItem {
+        MenuItem { id: None, submenu: Some(menu), .. MenuItem::new(CommandId(0), text) }
+    }
+
+    /// Show a check mark that toggles each time the item is chosen.
+    pub fn checkable(mut self) -> Self {
+        self.check = CheckKind::Checkbox;
+        self
+    }
+
+    /// Show a dot when the item is chosen, taking it away from the radio items next to it.
+    /// A separator or another kind of item ends a group.
+    pub fn radio(mut self) -> Self {
+        self.check = CheckKind::Radio;
+        self
+    }
+
+    /// Whether a checkable or radio item starts out checked.
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Disabled items are grayed out and can't be chosen.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Show `icon` next to the text, in place of the check mark. Its alpha channel is used,
+    /// so it should be about 16 by 16 pixels with a transparent background.
+    pub fn icon(mut self, icon: Bitmap) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Entry {
+    Item(MenuItem),
+    Separator,
+}
+
+/// The items of a menu bar, a context menu or a submenu.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Menu {
+    entries: Vec<Entry>,
+}
+
+impl Menu {
+    pub fn new() -> Menu {
+        Menu { entries: vec![] }
+    }
+
+    pub fn item(mut self, item: MenuItem) -> Self {
+        self.entries.push(Entry::Item(item));
+        self
+    }
+
+    /// Add an item opening `menu`, like `item(MenuItem::submenu(text, menu))`.
+    pub fn submenu<T: AsRef<str>>(self, text: T, menu: Menu) -> Self {
+        self.item(MenuItem::submenu(text, menu))
+    }
+
+    pub fn separator(mut self) -> Self {
+        self.entries.push(Entry::Separator);
+        self
+    }
+}
+
+/// A Windows menu created from a `Menu`, destroyed along with the icons of its items when
+/// dropped.
+#[doc(hidden)]
+pub struct MenuHandle {
+    hmenu: HMENU,
+    icons: Vec<HBITMAP>,
+}
+
+impl MenuHandle {
+    /// Create a menu bar, which reports chosen items with `WM_MENUCOMMAND`.
+    pub fn bar(menu: &Menu) -> MenuHandle {
+        unsafe {
+            let mut handle = MenuHandle { hmenu: user32::CreateMenu(), icons: vec![] };
+            handle.append(handle.hmenu, menu);
+
+            let mut info: MENUINFO = mem::zeroed();
+            info.cbSize = mem::size_of::<MENUINFO>() as DWORD;
+            info.fMask = MIM_STYLE | MIM_APPLYTOSUBMENUS;
+            info.dwStyle = MNS_NOTIFYBYPOS;
+            SetMenuInfo(handle.hmenu, &info);
+
+            handle
+        }
+    }
+
+    /// Create a popup menu for `TrackPopupMenuEx()`.
+    pub fn popup(menu: &Menu) -> MenuHandle {
+        unsafe {
+            let mut handle = MenuHandle { hmenu: user32::CreatePopupMenu(), icons: vec![] };
+            handle.append(handle.hmenu, menu);
+            handle
+        }
+    }
+
+    pub fn hmenu(&self) -> HMENU {
+        self.hmenu
+    }
+
+    unsafe fn append(&mut self, hmenu: HMENU, menu: &Menu) {
+        for (pos, entry) in menu.entries.iter().enumerate() {
+            let mut info = item_info(0);
+            let text = match *entry {
+                Entry::Item(ref item) => WinString::from_str(&item.text),
+                Entry::Separator => WinString::empty(),
+            };
+
+            match *entry {
+                Entry::Separator => {
+                    info.fMask = MIIM_FTYPE;
+                    info.fType = MFT_SEPARATOR;
+                },
+                Entry::Item(ref item) => {
+                    info.fMask = MIIM_FTYPE | MIIM_STATE | MIIM_ID | MIIM_STRING | MIIM_DATA;
+                    info.fType = MFT_STRING;
+                    info.wID = item.id.map_or(0, |CommandId(id)| id as UINT);
+                    info.dwTypeData = text.as_ptr() as LPWSTR;
+
+                    match item.check {
+                        CheckKind::None => {},
+                        CheckKind::Checkbox => info.dwItemData = CHECKABLE,
+                        CheckKind::Radio => info.fType |= MFT_RADIOCHECK,
+                    }
+
+                    if item.checked && item.check != CheckKind::None {
+                        info.fState |= MFS_CHECKED;
+                    }
+
+                    if !item.enabled {
+                        info.fState |= MFS_DISABLED;
+                    }
+
+                    if let Some(ref submenu) = item.submenu {
+                        info.fMask |= MIIM_SUBMENU;
+                        info.hSubMenu = user32::CreatePopupMenu();
+                        self.append(info.hSubMenu, submenu);
+                    }
+
+                    if let Some(ref icon) = item.icon {
+                        let hbitmap = create_icon(icon);
+
+                        if !hbitmap.is_null() {
+                            info.fMask |= MIIM_BITMAP;
+                            info.hbmpItem = hbitmap;
+                            self.icons.push(hbitmap);
+                        }
+                    }
+                },
+            }
+
+            user32::InsertMenuItemW(hmenu, pos as UINT, TRUE, &info);
+        }
+    }
+}
+
+impl Drop for MenuHandle {
+    fn drop(&mut self) {
+        unsafe {
+            // Submenus are destroyed along with their parent.
+            user32::DestroyMenu(self.hmenu);
+
+            for &icon in &self.icons {
+                gdi32::DeleteObject(icon as HGDIOBJ);
+            }
+        }
+    }
+}
+
+/// A 32-bit DIB section with premultiplied alpha, which menus draw blended.
+unsafe fn create_icon(icon: &Bitmap) -> HBITMAP {
+    let info = canvas::bitmap_info(icon.width, icon.height);
+    let mut bits = ptr::null_mut();
+
+    let hbitmap = gdi32::CreateDIBSection(
+        ptr::null_mut(), &info, DIB_RGB_COLORS, &mut bits, ptr::null_mut(), 0
+    );
+
+    if hbitmap.is_null() {
+        return hbitmap;
+    }
+
+    let pixels = slice::from_raw_parts_mut(bits as *mut u32, icon.pixels.len());
+
+    for (dest, &pixel) in pixels.iter_mut().zip(&icon.pixels) {
+        let alpha = pixel >> 24;
+        let channel = |shift: u32| ((pixel >> shift & 0xff) * alpha + 127) / 255 << shift;
+
+        *dest = alpha << 24 | channel(16) | channel(8) | channel(0);
+    }
+
+    hbitmap
+}
+
+fn item_info(mask: UINT) -> MENUITEMINFOW {
+    let mut info: MENUITEMINFOW = unsafe { mem::zeroed() };
+    info.cbSize = mem::size_of::<MENUITEMINFOW>() as UINT;
+    info.fMask = mask;
+    info
+}
+
+unsafe fn get_item_info(hmenu: HMENU, pos: UINT, mask: UINT) -> Option<MENUITEMINFOW> {
+    let mut info = item_info(mask);
+
+    if GetMenuItemInfoW(hmenu, pos, TRUE, &mut info) != 0 {
+        Some(info)
+    } else {
+        None
+    }
+}
+
+/// Update the check marks for the item at `pos` of `hmenu` being chosen, and return its
+/// command.
+#[doc(hidden)]
+pub unsafe fn activate(hmenu: HMENU, pos: UINT) -> Option<CommandId> {
+    let mask = MIIM_ID | MIIM_FTYPE | MIIM_STATE | MIIM_DATA;
+    let info = unwrap_or_ret!(get_item_info(hmenu, pos, mask), None);
+
+    if info.fType & MFT_RADIOCHECK != 0 {
+        let is_radio = |pos| get_item_info(hmenu, pos, MIIM_FTYPE)
+            .map_or(false, |info| info.fType & MFT_RADIOCHECK != 0);
+        let count = GetMenuItemCount(hmenu) as UINT;
+
+        let mut first = pos;
+        let mut last = pos;
+
+        while first > 0 && is_radio(first - 1) {
+            first -= 1;
+        }
+
+        while last + 1 < count && is_radio(last + 1) {
+            last += 1;
+        }
+
+        user32::CheckMenuRadioItem(hmenu, first, last, pos, MF_BYPOSITION);
+    } else if info.dwItemData & CHECKABLE != 0 {
+        let check = if info.fState & MFS_CHECKED != 0 { MF_UNCHECKED } else { MF_CHECKED };
+        user32::CheckMenuItem(hmenu, pos, MF_BYPOSITION | check);
+    }
+
+    Some(CommandId(info.wID as u16))
+}
+
+/// Find the item triggering `id` in `hmenu` or its submenus, as the menu it's in and its
+/// position there.
+#[doc(hidden)]
+pub unsafe fn find(hmenu: HMENU, id: CommandId) -> Option<(HMENU, UINT)> {
+    for pos in 0..item_count(hmenu) {
+        let info = unwrap_or_ret!(get_item_info(hmenu, pos, MIIM_ID | MIIM_FTYPE | MIIM_SUBMENU), None);
+
+        if !info.hSubMenu.is_null() {
+            if let Some(found) = find(info.hSubMenu, id) {
+                return Some(found);
+            }
+        } else if info.fType & MFT_SEPARATOR == 0 && info.wID == id.0 as UINT {
+            return Some((hmenu, pos));
+        }
+    }
+
+    None
+}
+
+/// Show the shortcut `shortcut` gives for each item of `hmenu` and its submenus after a
+/// tab in its label, replacing the one shown before.
+#[doc(hidden)]
+pub unsafe fn show_shortcuts<F>(hmenu: HMENU, shortcut: &F)
+where F: Fn(CommandId) -> Option<Accelerator> {
+    for pos in 0..item_count(hmenu) {
+        let mask = MIIM_ID | MIIM_FTYPE | MIIM_SUBMENU | MIIM_DATA | MIIM_STRING;
+        let mut info = unwrap_or_ret!(get_item_info(hmenu, pos, mask), ());
+
+        if !info.hSubMenu.is_null() {
+            show_shortcuts(info.hSubMenu, shortcut);
+            continue;
+        } else if info.fType & MFT_SEPARATOR != 0 {
+            continue;
+        }
+
+        // The first call only gave the length.
+        let mut text = vec![0u16; info.cch as usize + 1];
+        info.fMask = MIIM_STRING;
+        info.dwTypeData = text.as_mut_ptr();
+        info.cch = text.len() as UINT;
+        GetMenuItemInfoW(hmenu, pos, TRUE, &mut info);
+
+        let len = text.iter().position(|&unit| unit == 0).unwrap_or(text.len());
+        let mut label = String::from_utf16_lossy(&text[..len]);
+
+        if info.dwItemData & SHOWS_SHORTCUT != 0 {
+            let end = label.rfind('\t').unwrap_or(label.len());
+            label.truncate(end);
+        }
+
+        let mut data = info.dwItemData & !SHOWS_SHORTCUT;
+
+        if let Some(accelerator) = shortcut(CommandId(info.wID as u16)) {
+            label = format!("{}\t{}", label, accelerator);
+            data |= SHOWS_SHORTCUT;
+        }
+
+        let label = WinString::from_str(label);
+        let mut info = item_info(MIIM_STRING | MIIM_DATA);
+        info.dwTypeData = label.as_ptr() as LPWSTR;
+        info.dwItemData = data;
+        SetMenuItemInfoW(hmenu, pos, TRUE, &info);
+    }
+}
+
+fn item_count(hmenu: HMENU) -> UINT {
+    let count = unsafe { GetMenuItemCount(hmenu) };
+    if count < 0 { 0 } else { count as UINT }
+}
+
+#[doc(hidden)]
+pub unsafe fn is_checked(hmenu: HMENU, id: CommandId) -> bool {
+    find(hmenu, id)
+        .and_then(|(hmenu, pos)| get_item_info(hmenu, pos, MIIM_STATE))
+        .map_or(false, |info| info.fState & MFS_CHECKED != 0)
+}
+
+#[doc(hidden)]
+pub unsafe fn set_checked(hmenu: HMENU, id: CommandId, checked: bool) {
+    if let Some((hmenu, pos)) = find(hmenu, id) {
+        let check = if checked { MF_CHECKED } else { MF_UNCHECKED };
+        user32::CheckMenuItem(hmenu, pos, MF_BYPOSITION | check);
+    }
+}
+
+#[doc(hidden)]
+pub unsafe fn set_enabled(hmenu: HMENU, id: CommandId, enabled: bool) {
+    if let Some((hmenu, pos)) = find(hmenu, id) {
+        let enable = if enabled { MF_ENABLED } else { MF_GRAYED };
+        user32::EnableMenuItem(hmenu, pos, MF_BYPOSITION | enable);
+    }
+}
@@ -0,0 +1,140 @@
+use user32;
+
+use abs_window::AbsWindow;
+use winstr::WinString;
+use ffi::{WindowHandle, OCM_BASE};
+use ffi::class::system as system_classes;
+use ffi::traits::{WindowData, WindowEvents};
+use layout::Size;
+
+use super::TextWindow;
+use super::metrics;
+
+use winapi::*;
+
+use std::cmp;
+
+/// Room for this many average characters when measuring an `Edit`.
+const MEASURE_CHARS: i32 = 20;
+/// Space taken by the client edge and the margins around the text.
+const MEASURE_PADDING: Size = Size { width: 8, height: 7 };
+
+/// A single-line text entry box.
+pub struct Edit {
+    hand: WindowHandle<Self>,
+}
+
+impl Edit {
+    pub fn new<P: AbsWindow, T: AsRef<str>>(parent: &P, text: T) -> Edit {
+        let data = EditData::new(parent.ptr(), text);
+        let hand = WindowHandle::create_instance(system_classes::EDIT, data).unwrap();
+
+        Edit {
+            hand: hand
+        }
+    }
+
+    /// Only allow digits to be typed into this box.
+    pub fn set_numeric(&mut self, numeric: bool) {
+        unsafe {
+            let style = user32::GetWindowLongPtrW(self.ptr(), GWL_STYLE) as DWORD;
+            let style = if numeric { style | ES_NUMBER } else { style & !ES_NUMBER };
+
+            user32::SetWindowLongPtrW(self.ptr(), GWL_STYLE, style as LONG_PTR);
+        }
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        unsafe {
+            user32::SendMessageW(self.ptr(), EM_SETREADONLY, read_only as WPARAM, 0);
+        }
+    }
+
+    /// Called whenever the text changes, whether typed by the user or set with `set_text()`.
+    pub fn on_text_changed<F>(&mut self, on_text_changed: F) -> &mut Self
+    where F: FnMut(&mut Edit) + 'static {
+        unsafe {
+            self.hand.data_mut().on_text_changed = Some(Box::new(on_text_changed));
+        }
+
+        self
+    }
+}
+
+impl AbsWindow for Edit {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+
+    /// Wide enough for the current text or a fixed number of characters, whichever is
+    /// wider, but no wider than `available`.
+    fn measure(&self, available: Size) -> Size {
+        let text = self.get_text();
+
+        let size = metrics::with_metrics(self, |metrics| {
+            let text = metrics::text_size(metrics, &text);
+            let width = cmp::max(text.width, metrics.average_char_width() * MEASURE_CHARS);
+
+            Size::new(width, metrics.line_height())
+        });
+
+        Size::new(
+            cmp::min(size.width + MEASURE_PADDING.width, cmp::max(available.width, 0)),
+            size.height + MEASURE_PADDING.height,
+        )
+    }
+}
+
+unsafe impl TextWindow for Edit {}
+
+impl WindowEvents for Edit {
+    type Data = EditData;
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, wparam: WPARAM, _: LPARAM) -> Option<LRESULT> {
+        if msg != OCM_BASE + WM_COMMAND || HIWORD(wparam as DWORD) != EN_CHANGE {
+            return None;
+        }
+
+        let cb = unsafe { hnd.data_mut().on_text_changed.take() };
+
+        if let Some(mut on_text_changed) = cb {
+            on_text_changed(&mut Edit { hand: hnd.clone() });
+
+            unsafe {
+                hnd.data_mut().on_text_changed = Some(on_text_changed);
+            }
+        }
+
+        Some(0)
+    }
+}
+
+pub struct EditData {
+    parent: HWND,
+    text: WinString,
+    on_text_changed: Option<Box<FnMut(&mut Edit)>>,
+}
+
+impl EditData {
+    fn new<T: AsRef<str>>(parent: HWND, text: T) -> EditData {
+        EditData {
+            parent: parent,
+            text: WinString::from_str(text),
+            on_text_changed: None,
+        }
+    }
+}
+
+impl WindowData for EditData {
+    fn name(&self) -> Option<&WinString> {
+        Some(&self.text)
+    }
+
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE | WS_TABSTOP | ES_LEFT | ES_AUTOHSCROLL
+    }
+}
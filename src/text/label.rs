@@ -1,24 +1,22 @@
-use window::Window;
+use abs_window::AbsWindow;
 use winstr::WinString;
-use winstr::consts as winstr_consts;
-use ffi::{self, WindowData, WindowEvents, WindowHandle};
+use ffi::WindowHandle;
 use ffi::class::system as system_classes;
+use ffi::traits::{WindowData, WindowEvents};
+use layout::Size;
 
 use super::TextWindow;
+use super::metrics;
 
-use user32;
 use winapi::*;
 
-use std::marker::PhantomData;
-use std::ptr;
-
 pub struct Label {
     hand: WindowHandle<Self>,
 }
 
 impl Label {
-    pub fn new<T: AsRef<str>>(text: T) -> Label {
-        let data = LabelData::new(text);
+    pub fn new<P: AbsWindow, T: AsRef<str>>(parent: &P, text: T) -> Label {
+        let data = LabelData::new(parent.ptr(), text);
         let hand = WindowHandle::create_instance(system_classes::STATIC, data).unwrap();
 
         Label {
@@ -27,17 +25,32 @@ impl Label {
     }
 }
 
+impl AbsWindow for Label {
+    fn ptr(&self) -> HWND {
+        self.hand.hwnd()
+    }
+
+    fn measure(&self, _available: Size) -> Size {
+        let text = self.get_text();
+        metrics::with_metrics(self, |metrics| metrics::text_size(metrics, &text))
+    }
+}
+
+unsafe impl TextWindow for Label {}
+
 impl WindowEvents for Label {
     type Data = LabelData;
 }
 
-struct LabelData {
+pub struct LabelData {
+    parent: HWND,
     text: WinString,
 }
 
 impl LabelData {
-    fn new<T: AsRef<str>>(text: T) -> LabelData {
+    fn new<T: AsRef<str>>(parent: HWND, text: T) -> LabelData {
         LabelData {
+            parent: parent,
             text: WinString::from_str(text)
         }
     }
@@ -47,4 +60,14 @@ impl WindowData for LabelData {
     fn name(&self) -> Option<&WinString> { 
         Some(&self.text)
     }
+
+    fn parent(&self) -> HWND {
+        self.parent
+    }
+
+    fn style(&self) -> DWORD {
+        WS_CHILD | WS_VISIBLE
+    }
+
+    fn ex_style(&self) -> DWORD { 0 }
 }
@@ -0,0 +1,527 @@
+//! Laying out text in lines, for custom controls that draw text themselves.
+
+use canvas::{Canvas, Point};
+use color::Color;
+use layout::{Rect, Size};
+
+use super::metrics::FontMetrics;
+
+use std::cmp;
+use std::ops::Range;
+
+/// How lines are placed within the width of a layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for TextAlign {
+    fn default() -> Self {
+        TextAlign::Left
+    }
+}
+
+/// How to lay out text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextFormat {
+    width: Option<i32>,
+    wrap: bool,
+    align: TextAlign,
+    tab_width: i32,
+    ellipsis: bool,
+    max_lines: Option<usize>,
+}
+
+impl TextFormat {
+    /// Lines broken only at `\n`, aligned to the left, with tab stops every 8 characters.
+    pub fn new() -> TextFormat {
+        TextFormat {
+            width: None,
+            wrap: true,
+            align: TextAlign::Left,
+            tab_width: 8,
+            ellipsis: false,
+            max_lines: None,
+        }
+    }
+
+    /// Keep lines within `width`, wrapping longer ones between words, or within words if a
+    /// word doesn't fit on a line by itself. Without a width lines are aligned within the
+    /// widest one. Negative widths count as 0.
+    pub fn width(mut self, width: i32) -> Self {
+        self.width = Some(cmp::max(width, 0));
+        self
+    }
+
+    /// Whether to wrap lines wider than the width, or cut them off instead.
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Put tab stops every `chars` average character widths.
+    pub fn tab_width(mut self, chars: i32) -> Self {
+        self.tab_width = chars;
+        self
+    }
+
+    /// Whether to show `…` where text is cut off, like `DT_END_ELLIPSIS`.
+    pub fn ellipsis(mut self, ellipsis: bool) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    /// Show at most `lines` lines, cutting off the text after them.
+    pub fn max_lines(mut self, lines: usize) -> Self {
+        self.max_lines = Some(lines);
+        self
+    }
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        TextFormat::new()
+    }
+}
+
+const ELLIPSIS: &'static str = "\u{2026}";
+
+/// Text laid out in lines with a `TextFormat`, in the units of the `FontMetrics` it was
+/// measured with, which are those of the canvas for `Canvas::text_metrics()`.
+///
+/// Positions in the text are byte indices, like for `str`.
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    text: String,
+    lines: Vec<Line>,
+    line_height: i32,
+    width: i32,
+}
+
+#[derive(Clone, Debug)]
+struct Line {
+    /// The text shown on the line, without the line break or any text cut off.
+    range: Range<usize>,
+    /// The x of the line within the layout.
+    x: i32,
+    /// The width without trailing spaces, and with the ellipsis if there is one.
+    width: i32,
+    /// Where the caret goes before each character and after the last, relative to `x`.
+    carets: Vec<(usize, i32)>,
+    /// Where the ellipsis goes, relative to `x`.
+    ellipsis: Option<i32>,
+}
+
+impl TextLayout {
+    pub fn new(metrics: &FontMetrics, text: &str, format: &TextFormat) -> TextLayout {
+        let tab = format.tab_width * metrics.average_char_width();
+        let mut lines = vec![];
+        let mut cut_off = false;
+        let mut start = 0;
+
+        for paragraph in text.split('\n') {
+            let end = start + paragraph.trim_right_matches('\r').len();
+            cut_off = !break_lines(metrics, text, start..end, tab, format, &mut lines);
+            start += paragraph.len() + 1;
+
+            if cut_off {
+                break;
+            }
+        }
+
+        if let Some(max_lines) = format.max_lines {
+            if lines.len() > max_lines {
+                lines.truncate(max_lines);
+                cut_off = true;
+            }
+        }
+
+        if cut_off && format.ellipsis {
+            if let Some(line) = lines.last_mut() {
+                add_ellipsis(metrics, text, line, format.width);
+            }
+        }
+
+        let widest = lines.iter().map(|line| line.width).max().unwrap_or(0);
+        let width = format.width.unwrap_or(widest);
+
+        for line in &mut lines {
+            line.x = match format.align {
+                TextAlign::Left => 0,
+                TextAlign::Center => (width - line.width) / 2,
+                TextAlign::Right => width - line.width,
+            };
+        }
+
+        TextLayout {
+            text: text.to_string(),
+            lines: lines,
+            // At least 1, so that hit testing can divide by it.
+            line_height: cmp::max(metrics.line_height(), 1),
+            width: width,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The width of the format, or of the widest line without one, by the height of the
+    /// lines.
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.lines.len() as i32 * self.line_height)
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The text shown on `line`, without the line break or any text cut off.
+    ///
+    /// ## Panics
+    /// If there is no such line.
+    pub fn line_text(&self, line: usize) -> &str {
+        &self.text[self.lines[line].range.clone()]
+    }
+
+    /// The position in the text nearest to `point`, relative to the top left of the layout.
+    pub fn hit_test(&self, point: Point) -> usize {
+        let last = self.lines.len() as i32 - 1;
+        let index = cmp::min(cmp::max(point.y, 0) / self.line_height, last) as usize;
+        let line = unwrap_or_ret!(self.lines.get(index), 0);
+        let x = point.x - line.x;
+
+        // The end of a wrapped line is the start of the next one, so it can't be hit here.
+        let wraps = self.lines.get(index + 1).map_or(false, |next| next.range.start == line.range.end);
+        let carets = if wraps { &line.carets[..line.carets.len() - 1] } else { &line.carets[..] };
+
+        carets.iter()
+            .min_by_key(|&&(_, caret_x)| (caret_x - x).abs())
+            .map_or(line.range.start, |&(index, _)| index)
+    }
+
+    /// Where to draw the caret before `index`: one unit wide and the height of a line. A
+    /// position where a line wraps is at the start of the next line, and one in text that's
+    /// cut off at the end of its line.
+    pub fn caret_rect(&self, index: usize) -> Rect {
+        let line = self.lines.iter().rposition(|line| line.range.start <= index).unwrap_or(0);
+        let y = line as i32 * self.line_height;
+        let line = unwrap_or_ret!(self.lines.get(line), Rect::new(0, 0, 1, self.line_height));
+
+        let &(_, x) = line.carets.iter()
+            .find(|&&(caret, _)| caret >= index)
+            .unwrap_or(&line.carets[line.carets.len() - 1]);
+
+        Rect::new(line.x + x, y, 1, self.line_height)
+    }
+
+    /// Draw the text with the top left of the layout at `origin`.
+    pub fn draw(&self, canvas: &mut Canvas, origin: Point, color: Color) {
+        for (i, line) in self.lines.iter().enumerate() {
+            let y = origin.y + i as i32 * self.line_height;
+            let mut run_start = 0;
+
+            // Tabs are drawn as the space before the next run.
+            for (i, &(index, _)) in line.carets.iter().enumerate() {
+                let at_tab = self.text[index..].starts_with('\t');
+
+                if (at_tab || i == line.carets.len() - 1) && run_start < i {
+                    let (start, start_x) = line.carets[run_start];
+                    let pos = Point::new(origin.x + line.x + start_x, y);
+                    canvas.draw_text(pos, &self.text[start..index], color);
+                }
+
+                if at_tab {
+                    run_start = i + 1;
+                }
+            }
+
+            if let Some(x) = line.ellipsis {
+                canvas.draw_text(Point::new(origin.x + line.x + x, y), ELLIPSIS, color);
+            }
+        }
+    }
+}
+
+/// Where the caret goes before each character of `text` and after the last, with tab
+/// stops every `tab` from the start.
+fn measure(metrics: &FontMetrics, text: &str, tab: i32) -> Vec<i32> {
+    let mut positions = vec![0];
+
+    for (i, run) in text.split('\t').enumerate() {
+        let start = positions[positions.len() - 1];
+
+        // The tab before the run, which ends at the next stop, or right away without stops.
+        let start = if i == 0 {
+            start
+        } else {
+            let stop = if tab > 0 { (start / tab + 1) * tab } else { start };
+            positions.push(stop);
+            stop
+        };
+
+        let units: Vec<u16> = run.encode_utf16().collect();
+        let extents = metrics.char_extents(&units);
+        let mut unit = 0;
+
+        for c in run.chars() {
+            unit += c.len_utf16();
+            positions.push(start + extents[unit - 1]);
+        }
+    }
+
+    positions
+}
+
+/// The x after the first `chars` characters of `text`, not counting trailing whitespace.
+fn visible_width(text: &[char], positions: &[i32], chars: usize) -> i32 {
+    let trailing = text[..chars].iter().rev().take_while(|c| c.is_whitespace()).count();
+    positions[chars - trailing]
+}
+
+/// Lay out the paragraph at `range` of `text` in lines added to `lines`. Returns whether
+/// the text after it should still be shown.
+fn break_lines(
+    metrics: &FontMetrics, text: &str, range: Range<usize>, tab: i32, format: &TextFormat,
+    lines: &mut Vec<Line>
+) -> bool {
+    let mut start = range.start;
+
+    loop {
+        let rest = &text[start..range.end];
+        let positions = measure(metrics, rest, tab);
+        let chars: Vec<char> = rest.chars().collect();
+        let bytes: Vec<usize> = rest.char_indices()
+            .map(|(i, _)| start + i)
+            .chain(Some(range.end))
+            .collect();
+
+        let full_width = visible_width(&chars, &positions, chars.len());
+        let max_width = format.width.unwrap_or(::std::i32::MAX);
+
+        // How many characters go on this line.
+        let count = if full_width <= max_width {
+            chars.len()
+        } else if format.wrap {
+            let between_words = (1..chars.len())
+                .filter(|&i| chars[i - 1].is_whitespace() && !chars[i].is_whitespace())
+                .filter(|&i| visible_width(&chars, &positions, i) <= max_width)
+                .last();
+            let within_word = (1..chars.len()).take_while(|&i| positions[i] <= max_width).last();
+
+            between_words.or(within_word).unwrap_or(cmp::min(1, chars.len()))
+        } else {
+            (0..chars.len() + 1).take_while(|&i| positions[i] <= max_width).last().unwrap_or(0)
+        };
+
+        lines.push(Line {
+            range: start..bytes[count],
+            x: 0,
+            width: visible_width(&chars, &positions, count),
+            carets: bytes.iter().cloned().zip(positions.iter().cloned()).take(count + 1).collect(),
+            ellipsis: None,
+        });
+
+        if format.max_lines.map_or(false, |max_lines| lines.len() > max_lines) {
+            return false;
+        }
+
+        if count == chars.len() {
+            return true;
+        } else if !format.wrap {
+            // Cut off.
+            if format.ellipsis {
+                add_ellipsis(metrics, text, lines.last_mut().unwrap(), format.width);
+            }
+
+            return true;
+        }
+
+        start = bytes[count];
+    }
+}
+
+/// End `line` of `text` with an ellipsis, leaving off characters until it fits within
+/// `width`, and any whitespace before it.
+fn add_ellipsis(metrics: &FontMetrics, text: &str, line: &mut Line, width: Option<i32>) {
+    if line.ellipsis.is_some() {
+        return;
+    }
+
+    let ellipsis: Vec<u16> = ELLIPSIS.encode_utf16().collect();
+    let ellipsis_width = metrics.line_extent(&ellipsis).width;
+    let max_x = width.map_or(::std::i32::MAX, |width| width - ellipsis_width);
+
+    while line.carets.len() > 1 && line.carets[line.carets.len() - 1].1 > max_x {
+        line.carets.pop();
+    }
+
+    while line.carets.len() > 1 {
+        let (before, _) = line.carets[line.carets.len() - 2];
+
+        if !text[before..].starts_with(char::is_whitespace) {
+            break;
+        }
+
+        line.carets.pop();
+    }
+
+    let (end, x) = line.carets[line.carets.len() - 1];
+    line.range.end = end;
+    line.ellipsis = Some(x);
+    line.width = x + ellipsis_width;
+}
+
+#[cfg(test)]
+mod tests {
+    use text::metrics::{FixedMetrics, FontMetrics, FIXED_CHAR_WIDTH as W, FIXED_LINE_HEIGHT as H};
+
+    use super::*;
+
+    fn layout(text: &str, format: TextFormat) -> TextLayout {
+        TextLayout::new(&FixedMetrics, text, &format)
+    }
+
+    fn lines(layout: &TextLayout) -> Vec<&str> {
+        (0..layout.line_count()).map(|line| layout.line_text(line)).collect()
+    }
+
+    #[test]
+    fn lines_split_at_line_breaks() {
+        let layout = layout("one\r\ntwo\n", TextFormat::new());
+
+        assert_eq!(lines(&layout), ["one", "two", ""]);
+        assert_eq!(layout.size(), Size::new(3 * W, 3 * H));
+    }
+
+    #[test]
+    fn wrap_between_words() {
+        let layout = layout("hello world", TextFormat::new().width(8 * W));
+
+        // The space stays at the end of the first line, but doesn't count towards its width.
+        assert_eq!(lines(&layout), ["hello ", "world"]);
+        assert_eq!(layout.lines[0].width, 5 * W);
+        assert_eq!(layout.size(), Size::new(8 * W, 2 * H));
+    }
+
+    #[test]
+    fn wrap_within_words() {
+        let wrapped = layout("abcdefghij", TextFormat::new().width(4 * W));
+        assert_eq!(lines(&wrapped), ["abcd", "efgh", "ij"]);
+
+        // At least one character goes on each line.
+        let narrow = layout("ab", TextFormat::new().width(W / 2));
+        assert_eq!(lines(&narrow), ["a", "b"]);
+    }
+
+    #[test]
+    fn empty_paragraph_without_width() {
+        for &width in &[0, -4] {
+            let layout = layout("a\n\nb", TextFormat::new().width(width));
+
+            assert_eq!(lines(&layout), ["a", "", "b"]);
+            assert_eq!(layout.size(), Size::new(0, 3 * H));
+        }
+    }
+
+    #[test]
+    fn cut_off_with_ellipsis() {
+        let layout = layout("hello world", TextFormat::new().width(8 * W).wrap(false).ellipsis(true));
+
+        assert_eq!(lines(&layout), ["hello w"]);
+        assert_eq!(layout.lines[0].ellipsis, Some(7 * W));
+        assert_eq!(layout.lines[0].width, 8 * W);
+    }
+
+    #[test]
+    fn max_lines() {
+        let layout = layout("a b c d e f", TextFormat::new().width(4 * W).max_lines(2).ellipsis(true));
+
+        assert_eq!(lines(&layout), ["a b ", "c d"]);
+        assert_eq!(layout.lines[1].ellipsis, Some(3 * W));
+        assert_eq!(layout.size().height, 2 * H);
+    }
+
+    #[test]
+    fn alignment() {
+        let format = TextFormat::new().width(10 * W);
+
+        assert_eq!(layout("ab", format.clone()).caret_rect(0).x, 0);
+        assert_eq!(layout("ab", format.clone().align(TextAlign::Center)).caret_rect(0).x, 4 * W);
+        assert_eq!(layout("ab", format.align(TextAlign::Right)).caret_rect(2).x, 10 * W);
+    }
+
+    #[test]
+    fn tab_stops() {
+        let layout = layout("a\tb", TextFormat::new().tab_width(4));
+
+        assert_eq!(layout.caret_rect(1).x, W);
+        assert_eq!(layout.caret_rect(2).x, 4 * W);
+        assert_eq!(layout.size().width, 5 * W);
+    }
+
+    #[test]
+    fn tabs_without_stops() {
+        let layout = layout("a\tb", TextFormat::new().tab_width(0));
+
+        assert_eq!(layout.caret_rect(2).x, W);
+        assert_eq!(layout.size().width, 2 * W);
+    }
+
+    #[test]
+    fn hit_test() {
+        let layout = layout("hello world", TextFormat::new().width(8 * W));
+
+        assert_eq!(layout.hit_test(Point::new(-5, -5)), 0);
+        assert_eq!(layout.hit_test(Point::new(W + 3, 5)), 1);
+        assert_eq!(layout.hit_test(Point::new(W + 5, 5)), 2);
+        // The end of the wrapped line is the start of the next one.
+        assert_eq!(layout.hit_test(Point::new(8 * W, 5)), 5);
+        assert_eq!(layout.hit_test(Point::new(0, H)), 6);
+        assert_eq!(layout.hit_test(Point::new(100 * W, 100 * H)), 11);
+    }
+
+    #[test]
+    fn caret_rects() {
+        let layout = layout("hello world", TextFormat::new().width(8 * W));
+
+        assert_eq!(layout.caret_rect(0), Rect::new(0, 0, 1, H));
+        assert_eq!(layout.caret_rect(6), Rect::new(0, H, 1, H));
+        assert_eq!(layout.caret_rect(11), Rect::new(5 * W, H, 1, H));
+    }
+
+    #[test]
+    fn empty_text() {
+        let layout = layout("", TextFormat::new());
+
+        assert_eq!(lines(&layout), [""]);
+        assert_eq!(layout.hit_test(Point::new(10, 10)), 0);
+        assert_eq!(layout.caret_rect(0), Rect::new(0, 0, 1, H));
+    }
+
+    #[test]
+    fn zero_line_height() {
+        struct Flat;
+
+        impl FontMetrics for Flat {
+            fn line_extent(&self, text: &[u16]) -> Size {
+                Size::new(text.len() as i32, 0)
+            }
+
+            fn line_height(&self) -> i32 { 0 }
+            fn average_char_width(&self) -> i32 { 1 }
+        }
+
+        let layout = TextLayout::new(&Flat, "ab\ncd", &TextFormat::new());
+
+        assert_eq!(layout.hit_test(Point::new(1, 1)), 4);
+        assert_eq!(layout.size(), Size::new(2, 2));
+    }
+}
@@ -0,0 +1,272 @@
+//! Measuring text in a control's font, for the preferred size of controls and for
+//! laying out text.
+
+#[cfg(windows)] use {gdi32, user32};
+
+#[cfg(windows)] use abs_window::AbsWindow;
+#[cfg(windows)] use dpi::Dpi;
+#[cfg(windows)] use ffi::gdi;
+use layout::Size;
+
+#[cfg(windows)] use winapi::*;
+
+#[cfg(windows)] use std::cell::Cell;
+use std::cmp;
+#[cfg(windows)] use std::{mem, ptr};
+
+/// Measures text in a particular font.
+pub trait FontMetrics {
+    /// The size of `text` on a single line.
+    fn line_extent(&self, text: &[u16]) -> Size;
+
+    /// The height of a line, including leading.
+    fn line_height(&self) -> i32;
+
+    /// The average width of a character, for sizing controls by a number of characters.
+    fn average_char_width(&self) -> i32;
+
+    /// The width of each prefix of `text` on a single line: element `i` is the width of
+    /// `text[..i + 1]`.
+    fn char_extents(&self, text: &[u16]) -> Vec<i32> {
+        (1..text.len() + 1).map(|end| self.line_extent(&text[..end]).width).collect()
+    }
+}
+
+/// Metrics where every character is the same size, so measurements are the same on every
+/// machine.
+#[derive(Copy, Clone, Debug)]
+pub struct FixedMetrics;
+
+/// The width of every character in `FixedMetrics`.
+pub const FIXED_CHAR_WIDTH: i32 = 8;
+/// The line height in `FixedMetrics`.
+pub const FIXED_LINE_HEIGHT: i32 = 16;
+
+impl FontMetrics for FixedMetrics {
+    fn line_extent(&self, text: &[u16]) -> Size {
+        let chars = ::std::char::decode_utf16(text.iter().cloned()).count() as i32;
+        Size::new(chars * FIXED_CHAR_WIDTH, FIXED_LINE_HEIGHT)
+    }
+
+    fn line_height(&self) -> i32 {
+        FIXED_LINE_HEIGHT
+    }
+
+    fn average_char_width(&self) -> i32 {
+        FIXED_CHAR_WIDTH
+    }
+
+    fn char_extents(&self, text: &[u16]) -> Vec<i32> {
+        let mut chars = 0;
+
+        // The second half of a surrogate pair doesn't add a character.
+        text.iter().map(|&unit| {
+            if unit < 0xdc00 || unit > 0xdfff {
+                chars += 1;
+            }
+
+            chars * FIXED_CHAR_WIDTH
+        }).collect()
+    }
+}
+
+/// Metrics of the font selected into a device context.
+#[cfg(windows)]
+#[doc(hidden)]
+pub struct DcMetrics {
+    hdc: HDC,
+    text_metrics: TEXTMETRICW,
+    /// Pixels per unit of the measurements.
+    scale: f64,
+    /// The window the DC was taken from and the font to select back, if this owns the DC.
+    owner: Option<(HWND, HGDIOBJ)>,
+}
+
+#[cfg(windows)]
+impl DcMetrics {
+    /// Measure in the font of `hwnd`, in logical units at its DPI.
+    unsafe fn of_window(hwnd: HWND) -> DcMetrics {
+        let hdc = user32::GetDC(hwnd);
+
+        // Windows without `WM_SETFONT` return null, which leaves the system font selected.
+        let font = user32::SendMessageW(hwnd, WM_GETFONT, 0, 0) as HGDIOBJ;
+        let old_font = if font.is_null() { font } else { gdi32::SelectObject(hdc, font) };
+
+        let mut metrics = DcMetrics::new(hdc, Dpi::of_hwnd(hwnd).scale_factor());
+        metrics.owner = Some((hwnd, old_font));
+        metrics
+    }
+
+    /// Measure in the font currently selected into `hdc`, in units of `scale` pixels.
+    pub unsafe fn new(hdc: HDC, scale: f64) -> DcMetrics {
+        let mut text_metrics = mem::zeroed();
+        gdi::GetTextMetricsW(hdc, &mut text_metrics);
+
+        DcMetrics {
+            hdc: hdc,
+            text_metrics: text_metrics,
+            scale: scale,
+            owner: None,
+        }
+    }
+
+    fn unscale(&self, pixels: i32) -> i32 {
+        (pixels as f64 / self.scale).round() as i32
+    }
+}
+
+#[cfg(windows)]
+impl FontMetrics for DcMetrics {
+    fn line_extent(&self, text: &[u16]) -> Size {
+        unsafe {
+            let mut size = mem::zeroed::<SIZE>();
+            gdi::GetTextExtentPoint32W(self.hdc, text.as_ptr(), text.len() as c_int, &mut size);
+            Size::new(self.unscale(size.cx), cmp::max(self.unscale(size.cy), self.line_height()))
+        }
+    }
+
+    fn line_height(&self) -> i32 {
+        self.unscale(self.text_metrics.tmHeight + self.text_metrics.tmExternalLeading)
+    }
+
+    fn average_char_width(&self) -> i32 {
+        self.unscale(self.text_metrics.tmAveCharWidth)
+    }
+
+    fn char_extents(&self, text: &[u16]) -> Vec<i32> {
+        let mut extents = vec![0; text.len()];
+
+        unsafe {
+            let mut size = mem::zeroed::<SIZE>();
+            gdi::GetTextExtentExPointW(
+                self.hdc, text.as_ptr(), text.len() as c_int, 0, ptr::null_mut(),
+                extents.as_mut_ptr(), &mut size
+            );
+        }
+
+        extents.into_iter().map(|extent| self.unscale(extent)).collect()
+    }
+}
+
+#[cfg(windows)]
+impl Drop for DcMetrics {
+    fn drop(&mut self) {
+        if let Some((hwnd, old_font)) = self.owner {
+            unsafe {
+                if !old_font.is_null() {
+                    gdi32::SelectObject(self.hdc, old_font);
+                }
+
+                user32::ReleaseDC(hwnd, self.hdc);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+thread_local!(static USE_FIXED: Cell<bool> = Cell::new(false));
+
+/// Measure all text on this thread with `FixedMetrics` instead of the real fonts, e.g. so
+/// that layouts come out the same in tests on any machine.
+#[cfg(windows)]
+pub fn use_fixed_metrics(fixed: bool) {
+    USE_FIXED.with(|use_fixed| use_fixed.set(fixed));
+}
+
+/// Call `f` with the metrics of the font `window` uses, in logical units like `FixedMetrics`.
+#[cfg(windows)]
+pub fn with_metrics<W, F, R>(window: &W, f: F) -> R
+where W: AbsWindow + ?Sized, F: FnOnce(&FontMetrics) -> R {
+    if USE_FIXED.with(Cell::get) {
+        return f(&FixedMetrics);
+    }
+
+    let metrics = unsafe { DcMetrics::of_window(window.ptr()) };
+    f(&metrics)
+}
+
+/// The size of `text`, which may have several lines separated by `\n`.
+pub fn text_size(metrics: &FontMetrics, text: &str) -> Size {
+    let line_height = metrics.line_height();
+
+    text.split('\n').fold(Size::default(), |size, line| {
+        let line: Vec<u16> = line.trim_right_matches('\r').encode_utf16().collect();
+        let extent = metrics.line_extent(&line);
+
+        Size::new(cmp::max(size.width, extent.width), size.height + line_height)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str) -> Vec<u16> {
+        text.encode_utf16().collect()
+    }
+
+    #[test]
+    fn fixed_line_extent_counts_characters() {
+        let (w, h) = (FIXED_CHAR_WIDTH, FIXED_LINE_HEIGHT);
+
+        assert_eq!(FixedMetrics.line_extent(&utf16("")), Size::new(0, h));
+        assert_eq!(FixedMetrics.line_extent(&utf16("abc")), Size::new(3 * w, h));
+
+        // A surrogate pair is one character.
+        assert_eq!(FixedMetrics.line_extent(&utf16("a\u{1f600}")), Size::new(2 * w, h));
+    }
+
+    #[test]
+    fn fixed_char_extents() {
+        let w = FIXED_CHAR_WIDTH;
+
+        assert_eq!(FixedMetrics.char_extents(&utf16("ab")), vec![w, 2 * w]);
+        assert_eq!(FixedMetrics.char_extents(&utf16("\u{1f600}b")), vec![w, w, 2 * w]);
+        assert_eq!(FixedMetrics.char_extents(&[]), vec![]);
+    }
+
+    #[test]
+    fn default_char_extents_use_line_extent() {
+        struct Wide;
+
+        impl FontMetrics for Wide {
+            fn line_extent(&self, text: &[u16]) -> Size {
+                Size::new(text.len() as i32 * 10 + 1, 12)
+            }
+
+            fn line_height(&self) -> i32 { 12 }
+            fn average_char_width(&self) -> i32 { 10 }
+        }
+
+        assert_eq!(Wide.char_extents(&utf16("abc")), vec![11, 21, 31]);
+    }
+
+    #[test]
+    fn text_size_of_lines() {
+        let (w, h) = (FIXED_CHAR_WIDTH, FIXED_LINE_HEIGHT);
+
+        assert_eq!(text_size(&FixedMetrics, "OK"), Size::new(2 * w, h));
+        assert_eq!(text_size(&FixedMetrics, "Cancel\r\nOK\n"), Size::new(6 * w, 3 * h));
+        assert_eq!(text_size(&FixedMetrics, ""), Size::new(0, h));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn fixed_metrics_replace_fonts() {
+        use abs_window::AbsWindow;
+
+        struct NoWindow;
+
+        impl AbsWindow for NoWindow {
+            fn ptr(&self) -> HWND {
+                ptr::null_mut()
+            }
+        }
+
+        use_fixed_metrics(true);
+        let size = with_metrics(&NoWindow, |metrics| text_size(metrics, "Label"));
+        use_fixed_metrics(false);
+
+        assert_eq!(size, Size::new(5 * FIXED_CHAR_WIDTH, FIXED_LINE_HEIGHT));
+    }
+}
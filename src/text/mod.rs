@@ -1,13 +1,18 @@
-use user32;
+#[cfg(windows)] use user32;
 
-use abs_window::AbsWindow;
-use winstr::WinString;
+#[cfg(windows)] use abs_window::AbsWindow;
+#[cfg(windows)] use winstr::WinString;
 
-pub mod label;
-pub mod edit;
+#[cfg(windows)] pub mod label;
+#[cfg(windows)] pub mod edit;
+pub mod layout;
+pub mod metrics;
 
-pub use self::label::Label;
+#[cfg(windows)] pub use self::edit::Edit;
+#[cfg(windows)] pub use self::label::Label;
+pub use self::layout::{TextAlign, TextFormat, TextLayout};
 
+#[cfg(windows)]
 pub unsafe trait TextWindow: AbsWindow {
     fn get_text(&self) -> String {
         let mut win_str = WinString::empty();
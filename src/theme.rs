@@ -0,0 +1,226 @@
+//! Light and dark themes.
+//!
+//! Windows follow the app mode chosen in the Windows settings unless the application sets
+//! its own theme with `set_override()`. In the dark theme top-level windows get a dark title
+//! bar, controls use the dark variant of their visual style where Windows has one, and
+//! backgrounds and text not colored with `set_background_color()` or `set_text_color()`
+//! get the colors of the theme.
+
+use {kernel32, user32};
+
+use color::{self, Color};
+use ffi::{self, advapi};
+use winstr::WinString;
+
+use winapi::*;
+
+use std::cell::Cell;
+use std::{mem, ptr};
+
+const PERSONALIZE_KEY: &'static str = "Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize";
+
+// `DWMWA_USE_IMMERSIVE_DARK_MODE`, which was 19 before Windows 10 20H1.
+const USE_IMMERSIVE_DARK_MODE: DWORD = 20;
+const USE_IMMERSIVE_DARK_MODE_OLD: DWORD = 19;
+
+/// The `lParam` of the `WM_SETTINGCHANGE` sent when the app mode changes.
+const THEME_SETTING: &'static str = "ImmersiveColorSet";
+
+lazy_static! {
+    static ref DWM_SET_WINDOW_ATTRIBUTE: usize = ffi::load_proc("dwmapi.dll", "DwmSetWindowAttribute");
+    static ref SET_WINDOW_THEME: usize = ffi::load_proc("uxtheme.dll", "SetWindowTheme");
+}
+
+thread_local! {
+    static OVERRIDE: Cell<Option<Theme>> = Cell::new(None);
+    /// `system()`, read from the registry the first time it's needed after a change.
+    static SYSTEM: Cell<Option<Theme>> = Cell::new(None);
+}
+
+/// Whether windows are drawn light on dark or dark on light.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::Light
+    }
+}
+
+impl Theme {
+    /// The background of windows and controls, or `None` to use the system colors.
+    pub fn background(self) -> Option<Color> {
+        match self {
+            Theme::Light => None,
+            Theme::Dark => Some(Color::rgb(0x20, 0x20, 0x20)),
+        }
+    }
+
+    /// The color of text in controls, or `None` to use the system colors.
+    pub fn text(self) -> Option<Color> {
+        match self {
+            Theme::Light => None,
+            Theme::Dark => Some(Color::rgb(0xff, 0xff, 0xff)),
+        }
+    }
+}
+
+/// The app mode chosen in the Windows settings. Versions of Windows without a dark mode
+/// are always light.
+pub fn system() -> Theme {
+    let key = WinString::from_str(PERSONALIZE_KEY);
+    let value = WinString::from_str("AppsUseLightTheme");
+
+    let mut light: DWORD = 1;
+    let mut size = mem::size_of::<DWORD>() as DWORD;
+
+    let res = unsafe {
+        advapi::RegGetValueW(
+            HKEY_CURRENT_USER, key.as_ptr(), value.as_ptr(), RRF_RT_REG_DWORD, ptr::null_mut(),
+            &mut light as *mut DWORD as PVOID, &mut size
+        )
+    };
+
+    if res == ERROR_SUCCESS as LONG && light == 0 { Theme::Dark } else { Theme::Light }
+}
+
+/// The theme windows on this thread are drawn with: the override if there is one,
+/// otherwise the system theme.
+pub fn current() -> Theme {
+    OVERRIDE.with(Cell::get).unwrap_or_else(cached_system)
+}
+
+fn cached_system() -> Theme {
+    SYSTEM.with(|cached| {
+        let theme = cached.get().unwrap_or_else(system);
+        cached.set(Some(theme));
+        theme
+    })
+}
+
+/// Read the system theme again the next time it's needed. Called on `WM_SETTINGCHANGE`
+/// messages about the theme.
+#[doc(hidden)]
+pub fn refresh() {
+    SYSTEM.with(|cached| cached.set(None));
+}
+
+/// Use `theme` for this thread's windows regardless of the system theme, or follow the
+/// system theme again with `None`. Existing windows are re-themed, and their
+/// `on_theme_changed()` callbacks called if it changed.
+pub fn set_override(theme: Option<Theme>) {
+    OVERRIDE.with(|over| over.set(theme));
+
+    unsafe extern "system" fn notify_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        user32::SendMessageW(hwnd, WM_SETTINGCHANGE, 0, lparam);
+        TRUE
+    }
+
+    // Go through the same path as a change of the system theme.
+    let setting = WinString::from_str(THEME_SETTING);
+
+    unsafe {
+        user32::EnumThreadWindows(kernel32::GetCurrentThreadId(), Some(notify_proc), setting.as_ptr() as LPARAM);
+    }
+}
+
+/// Whether a `WM_SETTINGCHANGE` message with `lparam` is about the theme.
+#[doc(hidden)]
+pub unsafe fn is_theme_change(lparam: LPARAM) -> bool {
+    let setting = lparam as *const u16;
+
+    if setting.is_null() {
+        return false;
+    }
+
+    let expected = WinString::from_str(THEME_SETTING);
+    let expected = expected.as_wide();
+
+    // Compare up to and including the NUL terminator, without reading past a shorter string.
+    for (i, &c) in expected.iter().enumerate() {
+        if *setting.offset(i as isize) != c {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Draw `hwnd` in the current theme: the title bar if it's a top-level window, or the
+/// visual style if it's a control.
+#[doc(hidden)]
+pub fn apply(hwnd: HWND) {
+    let theme = current();
+
+    color::set_defaults(theme.background(), theme.text());
+
+    unsafe {
+        let style = user32::GetWindowLongPtrW(hwnd, GWL_STYLE) as DWORD;
+
+        if style & WS_CHILD == 0 {
+            set_dark_title_bar(hwnd, theme == Theme::Dark);
+        } else {
+            set_control_theme(hwnd, theme);
+        }
+    }
+}
+
+/// Re-theme `hwnd` and all its descendants and redraw them.
+#[doc(hidden)]
+pub fn apply_to_children(hwnd: HWND) {
+    unsafe extern "system" fn apply_proc(child: HWND, _: LPARAM) -> BOOL {
+        apply(child);
+        TRUE
+    }
+
+    apply(hwnd);
+
+    unsafe {
+        user32::EnumChildWindows(hwnd, Some(apply_proc), 0);
+        user32::RedrawWindow(
+            hwnd, ptr::null(), ptr::null_mut(),
+            RDW_ERASE | RDW_FRAME | RDW_INVALIDATE | RDW_ALLCHILDREN
+        );
+    }
+}
+
+unsafe fn set_dark_title_bar(hwnd: HWND, dark: bool) {
+    if *DWM_SET_WINDOW_ATTRIBUTE == 0 {
+        return;
+    }
+
+    let set_attribute: unsafe extern "system" fn(HWND, DWORD, LPCVOID, DWORD) -> HRESULT =
+        mem::transmute(*DWM_SET_WINDOW_ATTRIBUTE);
+
+    let value = dark as BOOL;
+    let value_ptr = &value as *const BOOL as LPCVOID;
+    let size = mem::size_of::<BOOL>() as DWORD;
+
+    if set_attribute(hwnd, USE_IMMERSIVE_DARK_MODE, value_ptr, size) != S_OK {
+        set_attribute(hwnd, USE_IMMERSIVE_DARK_MODE_OLD, value_ptr, size);
+    }
+}
+
+unsafe fn set_control_theme(hwnd: HWND, theme: Theme) {
+    if *SET_WINDOW_THEME == 0 {
+        return;
+    }
+
+    let set_window_theme: unsafe extern "system" fn(HWND, LPCWSTR, LPCWSTR) -> HRESULT =
+        mem::transmute(*SET_WINDOW_THEME);
+
+    match theme {
+        Theme::Dark => {
+            let name = WinString::from_str("DarkMode_Explorer");
+            set_window_theme(hwnd, name.as_ptr(), ptr::null());
+        },
+        // Null goes back to the normal visual style of the class.
+        Theme::Light => {
+            set_window_theme(hwnd, ptr::null(), ptr::null());
+        },
+    }
+}
+
@@ -3,15 +3,45 @@ use {kernel32, user32};
 use winapi::*;
 
 //use builder::{Builder, Buildable};
-use ffi::WindowHandle;
-use ffi::class::{Class as WindowClass, CustomClass};
+use abs_window::AbsWindow;
+use accel;
+use canvas::{BackBuffer, Canvas, GdiCanvas, PixelSurface, Point};
+use color;
+use command::{Command, CommandId, CommandSource};
+use controls::{Button, StatusBar, ToolBar};
+use dpi::Dpi;
+use font::{self, Font};
+use input::{Accelerator, KeyEvent, KeyFlags, Modifiers, MouseEvent, MouseEventKind, TextInput};
+use input::keyboard::Utf16Chars;
+use ffi::{self, WindowHandle};
+use ffi::class::{self, Class as WindowClass, CustomClass};
+use ffi::msg::CommandMsg;
+use ffi::user::{TrackPopupMenuEx, TPM_RETURNCMD, TPM_RIGHTBUTTON, WM_MENUCOMMAND};
+use menu::{self, Menu, MenuHandle};
+use theme::{self, Theme};
+use layout::{self, Anchor, AnchorLayout, Dock, LayoutItem, Rect, Size, SizeLimits, SizingEdge};
 use ffi::traits::{BorrowHandle, WindowEvents, WindowData};
 use winstr::WinString;
 
 use std::borrow::Cow;
 use std::boxed::FnBox;
 use std::marker::PhantomData;
-use std::{mem, ptr};
+use std::{cmp, mem, ptr};
+
+// Missing from `winapi`.
+const BM_CLICK: UINT = 0x00F5;
+const BM_SETSTYLE: UINT = 0x00F4;
+const DLGC_WANTARROWS: LRESULT = 0x0001;
+const DLGC_WANTCHARS: LRESULT = 0x0080;
+// Commands `IsDialogMessageW()` sends for Enter and Escape.
+const IDOK: u16 = 1;
+const IDCANCEL: u16 = 2;
+const WA_INACTIVE: WORD = 0;
+
+lazy_static! {
+    static ref ADJUST_WINDOW_RECT_EX_FOR_DPI: usize =
+        ffi::load_proc("user32.dll", "AdjustWindowRectExForDpi");
+}
 
 #[derive(Clone)]
 pub struct Window {
@@ -28,17 +58,995 @@ impl Window {
         }
     }
 
-    pub fn add_child<W: WindowEvents, C: BorrowHandle<W>>(&mut self, child: C) -> &mut Self {
+    pub fn builder<T: AsRef<str>>(title: T) -> WindowBuilder {
+        WindowBuilder {
+            data: Data::new(title),
+            status_bar: None,
+            tool_bar: None,
+            menu: None,
+        }
+    }
+
+    /// Create a borderless window inside `parent` to group other controls.
+    pub fn new_child<P: AbsWindow>(parent: &P) -> Window {
+        let data = Data { parent: Some(parent.ptr()), .. Data::default() };
+        let hnd = WindowHandle::create_instance(Class, data).unwrap();
+
+        Window {
+            hnd: hnd
+        }
+    }
+
+    /// Reparent `child` so that it is displayed inside this window.
+    pub fn add_child<C: AbsWindow>(&mut self, child: &C) -> &mut Self {
+        let child = child.ptr();
+
+        unsafe {
+            let style = user32::GetWindowLongPtrW(child, GWL_STYLE) as DWORD;
+            let style = (style & !WS_POPUP) | WS_CHILD;
+
+            user32::SetWindowLongPtrW(child, GWL_STYLE, style as LONG_PTR);
+            user32::SetParent(child, self.hnd.hwnd());
+        }
 
         self
     }
+
+    pub fn status_bar(&mut self) -> Option<&mut StatusBar> {
+        unsafe { self.hnd.data_mut().status_bar.as_mut() }
+    }
+
+    pub fn tool_bar(&mut self) -> Option<&mut ToolBar> {
+        unsafe { self.hnd.data_mut().tool_bar.as_mut() }
+    }
+
+    /// The DPI this window is displayed at.
+    pub fn dpi(&self) -> Dpi {
+        Dpi::of_window(self)
+    }
+
+    /// The part of the client area not covered by the toolbar or status bar, in logical
+    /// units relative to the client area. This is the area available to the layout.
+    pub fn client_area(&self) -> Rect {
+        let data = unsafe { self.hnd.data_mut() };
+
+        let rect = unsafe {
+            let mut rect: RECT = mem::zeroed();
+            user32::GetClientRect(self.hnd.hwnd(), &mut rect);
+            rect
+        };
+
+        let mut top = rect.top;
+        let mut bottom = rect.bottom;
+
+        if let Some(ref tool_bar) = data.tool_bar {
+            top += tool_bar.height();
+        }
+
+        if let Some(ref status_bar) = data.status_bar {
+            bottom -= status_bar.height();
+        }
+
+        let area = Rect::new(rect.left, top, rect.right - rect.left, cmp::max(bottom - top, 0));
+        self.dpi().unscale_rect(area)
+    }
+
+    /// Use `font` for the controls in this window, including those in child windows that
+    /// don't have their own font. `None` goes back to the parent's font.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        font::set_window_font(self.hnd.hwnd(), font);
+        font::apply_to_children(self.hnd.hwnd());
+
+        // Measured sizes depend on the font.
+        self.relayout();
+    }
+
+    /// Arrange this window's children with `layout`, now and whenever the window is resized.
+    pub fn set_layout<L: LayoutItem + 'static>(&mut self, layout: L) {
+        unsafe {
+            self.hnd.data_mut().layout = Some(Box::new(layout));
+        }
+
+        self.relayout();
+    }
+
+    /// Keep `child` at a fixed distance from the given edges of the client area as the
+    /// window is resized, starting from where it is now.
+    pub fn set_anchor<C: AbsWindow>(&mut self, child: &C, anchor: Anchor) {
+        let area = self.client_area();
+
+        unsafe {
+            self.hnd.data_mut().anchors.set_widget_anchor(child, area, anchor);
+        }
+
+        self.relayout();
+    }
+
+    /// Attach `child` to an edge of the client area. Docked children are placed in the order
+    /// they were docked, and the layout set with `set_layout()` gets the space left over.
+    pub fn set_dock<C: AbsWindow>(&mut self, child: &C, dock: Dock) {
+        unsafe {
+            self.hnd.data_mut().anchors.set_widget_dock(child, dock);
+        }
+
+        self.relayout();
+    }
+
+    /// Stop docking or anchoring `child`.
+    pub fn clear_anchor<C: AbsWindow>(&mut self, child: &C) {
+        unsafe {
+            self.hnd.data_mut().anchors.remove_widget(child);
+        }
+    }
+
+    /// Rerun the layout, e.g. after the minimum size of a child changes.
+    pub fn relayout(&mut self) {
+        let area = self.client_area();
+        let data = unsafe { self.hnd.data_mut() };
+
+        let rest = data.anchors.arrange_docked(area);
+        data.layout.as_mut().map(|layout| layout.arrange(rest));
+    }
+
+    /// Keep the client area at least `size`, including any bars, in logical units.
+    ///
+    /// By default the minimum is the minimum size of the layout.
+    pub fn set_min_size(&mut self, size: Size) {
+        unsafe {
+            self.hnd.data_mut().min_size = Some(size);
+        }
+    }
+
+    /// Keep the client area at most `size`, including any bars, in logical units.
+    pub fn set_max_size(&mut self, size: Size) {
+        unsafe {
+            self.hnd.data_mut().max_size = Some(size);
+        }
+    }
+
+    /// Keep the width of the client area divided by its height at `ratio` while the user
+    /// resizes the window, or allow any shape with `None`.
+    pub fn set_aspect_ratio(&mut self, ratio: Option<f64>) {
+        unsafe {
+            self.hnd.data_mut().aspect_ratio = ratio;
+        }
+    }
+
+    /// The limits on the size of the client area, in pixels.
+    fn size_limits(&self) -> SizeLimits {
+        let data = unsafe { self.hnd.data_mut() };
+        let dpi = self.dpi();
+
+        let min = match data.min_size {
+            Some(min) => dpi.scale_size(min),
+            None => {
+                let layout = data.layout.as_ref().map_or(Size::default(), |layout| layout.min_size());
+                let anchors = data.anchors.min_size();
+
+                let content = dpi.scale_size(Size::new(
+                    cmp::max(layout.width, anchors.width), cmp::max(layout.height, anchors.height)
+                ));
+
+                // The layout goes in the space the bars leave.
+                let bars = data.tool_bar.as_ref().map_or(0, ToolBar::height) +
+                    data.status_bar.as_ref().map_or(0, StatusBar::height);
+
+                Size::new(content.width, content.height + bars)
+            },
+        };
+
+        SizeLimits {
+            min: min,
+            max: data.max_size.map(|max| dpi.scale_size(max)),
+            aspect_ratio: data.aspect_ratio,
+        }
+    }
+
+    /// How much larger the window is than its client area.
+    fn frame_size(&self) -> Size {
+        unsafe {
+            let hwnd = self.hnd.hwnd();
+
+            let style = user32::GetWindowLongPtrW(hwnd, GWL_STYLE) as DWORD;
+            let ex_style = user32::GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as DWORD;
+            let has_menu = !user32::GetMenu(hwnd).is_null();
+
+            let mut rect: RECT = mem::zeroed();
+
+            // `AdjustWindowRectEx()` always uses the system DPI, which is wrong for windows on
+            // other monitors when per-monitor aware.
+            if *ADJUST_WINDOW_RECT_EX_FOR_DPI != 0 {
+                let adjust_for_dpi: unsafe extern "system" fn(
+                    LPRECT, DWORD, BOOL, DWORD, UINT
+                ) -> BOOL = mem::transmute(*ADJUST_WINDOW_RECT_EX_FOR_DPI);
+
+                adjust_for_dpi(&mut rect, style, has_menu as BOOL, ex_style, self.dpi().0);
+            } else {
+                user32::AdjustWindowRectEx(&mut rect, style, has_menu as BOOL, ex_style);
+            }
+
+            Size::new(rect.right - rect.left, rect.bottom - rect.top)
+        }
+    }
+
+    fn handle_min_max_info(&mut self, info: &mut MINMAXINFO) {
+        let limits = self.size_limits();
+        let frame = self.frame_size();
+
+        info.ptMinTrackSize.x = cmp::max(info.ptMinTrackSize.x, limits.min.width + frame.width);
+        info.ptMinTrackSize.y = cmp::max(info.ptMinTrackSize.y, limits.min.height + frame.height);
+
+        if let Some(max) = limits.max {
+            info.ptMaxTrackSize.x = max.width + frame.width;
+            info.ptMaxTrackSize.y = max.height + frame.height;
+        }
+    }
+
+    fn handle_sizing(&mut self, wparam: WPARAM, rect: &mut RECT) {
+        let edge = match wparam as UINT {
+            WMSZ_LEFT => SizingEdge::Left,
+            WMSZ_RIGHT => SizingEdge::Right,
+            WMSZ_TOP => SizingEdge::Top,
+            WMSZ_BOTTOM => SizingEdge::Bottom,
+            WMSZ_TOPLEFT => SizingEdge::TopLeft,
+            WMSZ_TOPRIGHT => SizingEdge::TopRight,
+            WMSZ_BOTTOMLEFT => SizingEdge::BottomLeft,
+            WMSZ_BOTTOMRIGHT => SizingEdge::BottomRight,
+            _ => return,
+        };
+
+        let proposed = Rect::new(rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top);
+        let adjusted = layout::adjust_sizing_rect(proposed, edge, &self.size_limits(), self.frame_size());
+
+        rect.left = adjusted.x;
+        rect.top = adjusted.y;
+        rect.right = adjusted.right();
+        rect.bottom = adjusted.bottom();
+    }
+
+    /// Called with the new DPI after the window moves to a monitor with a different DPI,
+    /// once it has been resized and its fonts rescaled.
+    pub fn on_dpi_changed<F>(&mut self, on_dpi_changed: F) -> &mut Self
+    where F: FnMut(&mut Window, Dpi) + 'static {
+        unsafe {
+            self.hnd.data_mut().on_dpi_changed = Some(Box::new(on_dpi_changed));
+        }
+
+        self
+    }
+
+    fn handle_dpi_changed(&mut self, wparam: WPARAM, suggested: &RECT) {
+        let dpi = Dpi(LOWORD(wparam as DWORD) as u32);
+        let data = unsafe { self.hnd.data_mut() };
+
+        // Fonts are cached per DPI, so reapplying them picks up the new size.
+        font::apply_to_children(self.hnd.hwnd());
+
+        unsafe {
+            user32::SetWindowPos(
+                self.hnd.hwnd(), ptr::null_mut(), suggested.left, suggested.top,
+                suggested.right - suggested.left, suggested.bottom - suggested.top,
+                SWP_NOZORDER | SWP_NOACTIVATE
+            );
+        }
+
+        // Resizing relays out the window, but not if the size in pixels didn't change.
+        self.relayout();
+
+        if let Some(mut on_dpi_changed) = data.on_dpi_changed.take() {
+            on_dpi_changed(self, dpi);
+            data.on_dpi_changed = Some(on_dpi_changed);
+        }
+    }
+
+    /// The theme this window was last drawn with.
+    pub fn theme(&self) -> Theme {
+        unsafe { self.hnd.data_mut().theme }
+    }
+
+    /// Called with the new theme after the system theme or the override set with
+    /// `theme::set_override()` changes, once the window has been redrawn in it. Only
+    /// top-level windows are notified.
+    pub fn on_theme_changed<F>(&mut self, on_theme_changed: F) -> &mut Self
+    where F: FnMut(&mut Window, Theme) + 'static {
+        unsafe {
+            self.hnd.data_mut().on_theme_changed = Some(Box::new(on_theme_changed));
+        }
+
+        self
+    }
+
+    fn handle_theme_changed(&mut self) {
+        let data = unsafe { self.hnd.data_mut() };
+
+        // Re-theme even if the theme is the same, as the override may have just been
+        // cleared without changing it.
+        theme::apply_to_children(self.hnd.hwnd());
+
+        let theme = theme::current();
+
+        if theme == data.theme {
+            return;
+        }
+
+        data.theme = theme;
+
+        if let Some(mut on_theme_changed) = data.on_theme_changed.take() {
+            on_theme_changed(self, theme);
+            data.on_theme_changed = Some(on_theme_changed);
+        }
+    }
+
+    /// Draw the client area with `on_paint` whenever part of it needs to be repainted.
+    /// Drawing is clipped to the invalid part.
+    pub fn on_paint<F>(&mut self, on_paint: F) -> &mut Self
+    where F: FnMut(&mut Window, &mut Canvas) + 'static {
+        unsafe {
+            self.hnd.data_mut().on_paint = Some(Box::new(on_paint));
+            user32::InvalidateRect(self.hnd.hwnd(), ptr::null(), TRUE);
+        }
+
+        self
+    }
+
+    /// Paint into an off-screen bitmap and copy it to the window when `on_paint` is done,
+    /// so that the window doesn't flicker while it's being redrawn.
+    pub fn set_double_buffered(&mut self, double_buffered: bool) {
+        unsafe {
+            self.hnd.data_mut().double_buffered = double_buffered;
+        }
+    }
+
+    /// Have `rect`, in logical units relative to the client area, repainted.
+    pub fn invalidate(&mut self, rect: Rect) {
+        let rect = self.dpi().scale_rect(rect);
+        let rect = RECT { left: rect.x, top: rect.y, right: rect.right(), bottom: rect.bottom() };
+
+        unsafe {
+            // Double-buffered windows erase their background while painting.
+            let erase = !self.hnd.data_mut().double_buffered;
+            user32::InvalidateRect(self.hnd.hwnd(), &rect, erase as BOOL);
+        }
+    }
+
+    /// Show `surface` in the client area instead of painting it with `on_paint`, or stop
+    /// showing a surface with `None`.
+    pub fn set_pixel_surface(&mut self, surface: Option<PixelSurface>) {
+        let hwnd = self.hnd.hwnd();
+
+        unsafe {
+            let data = self.hnd.data_mut();
+            data.pixel_surface = surface;
+            data.pixel_surface.as_mut().map(|surface| surface.attach(hwnd));
+
+            user32::InvalidateRect(hwnd, ptr::null(), TRUE);
+        }
+    }
+
+    pub fn pixel_surface(&mut self) -> Option<&mut PixelSurface> {
+        unsafe { self.hnd.data_mut().pixel_surface.as_mut() }
+    }
+
+    fn handle_paint(&mut self) {
+        let data = unsafe { self.hnd.data_mut() };
+        let hwnd = self.hnd.hwnd();
+
+        unsafe {
+            let mut paint: PAINTSTRUCT = mem::zeroed();
+            let hdc = user32::BeginPaint(hwnd, &mut paint);
+
+            if let Some(ref surface) = data.pixel_surface {
+                surface.paint(hdc);
+            } else if let Some(mut on_paint) = data.on_paint.take() {
+                if data.double_buffered {
+                    let buffer = BackBuffer::new(hdc, paint.rcPaint);
+
+                    // The `WM_ERASEBKGND` sent by `BeginPaint()` was skipped, erase here
+                    // instead.
+                    if !color::erase_background(hwnd, buffer.hdc()) {
+                        user32::DefWindowProcW(hwnd, WM_ERASEBKGND, buffer.hdc() as WPARAM, 0);
+                    }
+
+                    {
+                        let mut canvas = GdiCanvas::new(buffer.hdc(), hwnd);
+                        on_paint(self, &mut canvas);
+                    }
+
+                    buffer.copy_to(hdc);
+                } else {
+                    let mut canvas = GdiCanvas::new(hdc, hwnd);
+                    on_paint(self, &mut canvas);
+                }
+
+                data.on_paint = Some(on_paint);
+            }
+
+            user32::EndPaint(hwnd, &paint);
+        }
+    }
+
+    fn paints_itself(&self) -> bool {
+        let data = unsafe { self.hnd.data_mut() };
+        data.on_paint.is_some() || data.pixel_surface.is_some()
+    }
+
+    /// Whether the background is erased by `handle_paint()` rather than `WM_ERASEBKGND`.
+    /// Pixel surfaces cover the whole client area, so they don't need it erased at all.
+    fn erases_while_painting(&self) -> bool {
+        let data = unsafe { self.hnd.data_mut() };
+        (data.double_buffered && data.on_paint.is_some()) || data.pixel_surface.is_some()
+    }
+
+    fn resize_pixel_surface(&mut self, lparam: LPARAM) {
+        let data = unsafe { self.hnd.data_mut() };
+
+        if let Some(ref mut surface) = data.pixel_surface {
+            let size = Size::new(LOWORD(lparam as DWORD) as i32, HIWORD(lparam as DWORD) as i32);
+            surface.set_client_size(size);
+
+            // Where the frame goes depends on the whole client area.
+            unsafe {
+                user32::InvalidateRect(self.hnd.hwnd(), ptr::null(), FALSE);
+            }
+        }
+    }
+
+    /// Called for mouse events in the client area, except `DoubleClick`. Positions are in
+    /// logical units.
+    pub fn on_mouse<F>(&mut self, on_mouse: F) -> &mut Self
+    where F: FnMut(&mut Window, MouseEvent) + 'static {
+        unsafe {
+            self.hnd.data_mut().on_mouse = Some(Box::new(on_mouse));
+        }
+
+        self
+    }
+
+    /// Called with `DoubleClick` events, after the `Down` event for the same press.
+    ///
+    /// Double clicks are only detected for window classes with `CS_DBLCLKS`, which this
+    /// adds to the class of all `Window`s. In windows without this handler, the second press
+    /// of a double click still looks like any other.
+    pub fn on_double_click<F>(&mut self, on_double_click: F) -> &mut Self
+    where F: FnMut(&mut Window, MouseEvent) + 'static {
+        unsafe {
+            class::add_class_style(self.hnd.hwnd(), CS_DBLCLKS);
+            self.hnd.data_mut().on_double_click = Some(Box::new(on_double_click));
+        }
+
+        self
+    }
+
+    /// Keep getting mouse events while the mouse is outside of the window, until
+    /// `release_mouse()` is called or another window takes the capture.
+    pub fn capture_mouse(&mut self) {
+        unsafe {
+            user32::SetCapture(self.hnd.hwnd());
+        }
+    }
+
+    /// Stop capturing the mouse, if this window has captured it.
+    pub fn release_mouse(&mut self) {
+        if self.has_mouse_capture() {
+            unsafe {
+                user32::ReleaseCapture();
+            }
+        }
+    }
+
+    pub fn has_mouse_capture(&self) -> bool {
+        unsafe { user32::GetCapture() == self.hnd.hwnd() }
+    }
+
+    fn handle_mouse(&mut self, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let data = unsafe { self.hnd.data_mut() };
+        let hwnd = self.hnd.hwnd();
+
+        if data.on_mouse.is_none() && data.on_double_click.is_none() {
+            return None;
+        }
+
+        let mut event = unwrap_or_ret!(MouseEvent::decode(msg, wparam, lparam), None);
+
+        unsafe {
+            let mut pos = POINT { x: event.pos.x, y: event.pos.y };
+
+            match event.kind {
+                MouseEventKind::Wheel(_) | MouseEventKind::HWheel(_) => {
+                    user32::ScreenToClient(hwnd, &mut pos);
+                },
+                MouseEventKind::Leave => {
+                    user32::GetCursorPos(&mut pos);
+                    user32::ScreenToClient(hwnd, &mut pos);
+                },
+                _ => {},
+            }
+
+            let dpi = Dpi::of_hwnd(hwnd);
+            event.pos = Point::new(dpi.unscale(pos.x), dpi.unscale(pos.y));
+            event.modifiers.alt = user32::GetKeyState(VK_MENU) < 0;
+        }
+
+        match event.kind {
+            MouseEventKind::Move if !data.hovering => {
+                let mut track = TRACKMOUSEEVENT {
+                    cbSize: mem::size_of::<TRACKMOUSEEVENT>() as DWORD,
+                    dwFlags: TME_LEAVE,
+                    hwndTrack: hwnd,
+                    dwHoverTime: HOVER_DEFAULT,
+                };
+
+                data.hovering = unsafe { user32::TrackMouseEvent(&mut track) != 0 };
+                self.fire_mouse(MouseEvent { kind: MouseEventKind::Enter, click_count: 0, .. event });
+                self.fire_mouse(event);
+            },
+            MouseEventKind::Leave => {
+                data.hovering = false;
+                self.fire_mouse(event);
+            },
+            MouseEventKind::DoubleClick(button) => {
+                let click_count = if data.on_double_click.is_some() { 2 } else { 1 };
+                let down = MouseEvent { kind: MouseEventKind::Down(button), click_count: click_count, .. event };
+                self.fire_mouse(down);
+
+                if let Some(mut on_double_click) = data.on_double_click.take() {
+                    on_double_click(self, event);
+                    data.on_double_click = Some(on_double_click);
+                }
+            },
+            _ => self.fire_mouse(event),
+        }
+
+        match msg {
+            WM_XBUTTONDOWN | WM_XBUTTONUP | WM_XBUTTONDBLCLK => Some(TRUE as LRESULT),
+            _ => Some(0),
+        }
+    }
+
+    fn fire_mouse(&mut self, event: MouseEvent) {
+        let data = unsafe { self.hnd.data_mut() };
+
+        if let Some(mut on_mouse) = data.on_mouse.take() {
+            on_mouse(self, event);
+            data.on_mouse = Some(on_mouse);
+        }
+    }
+
+    /// Called when a key goes down or up while the window has the keyboard focus. System
+    /// keys are still handled by Windows afterwards, so that e.g. Alt+F4 keeps working.
+    ///
+    /// Child windows taking keyboard input become tab stops.
+    pub fn on_key<F>(&mut self, on_key: F) -> &mut Self
+    where F: FnMut(&mut Window, KeyEvent) + 'static {
+        unsafe {
+            self.hnd.data_mut().on_key = Some(Box::new(on_key));
+        }
+
+        self.make_tab_stop();
+        self
+    }
+
+    /// Called for each character typed while the window has the keyboard focus. Control
+    /// characters, like those of Backspace, Enter and Ctrl+letter, are left out; handle
+    /// those keys with `on_key`.
+    pub fn on_text_input<F>(&mut self, on_text_input: F) -> &mut Self
+    where F: FnMut(&mut Window, TextInput) + 'static {
+        unsafe {
+            self.hnd.data_mut().on_text_input = Some(Box::new(on_text_input));
+        }
+
+        self.make_tab_stop();
+        self
+    }
+
+    fn make_tab_stop(&mut self) {
+        unsafe {
+            let style = user32::GetWindowLongPtrW(self.hnd.hwnd(), GWL_STYLE) as DWORD;
+
+            if style & WS_CHILD != 0 {
+                user32::SetWindowLongPtrW(self.hnd.hwnd(), GWL_STYLE, (style | WS_TABSTOP) as LONG_PTR);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let data = unsafe { self.hnd.data_mut() };
+        let mut on_key = unwrap_or_ret!(data.on_key.take(), None);
+        let event = KeyEvent::decode(msg, wparam, lparam, Modifiers::current());
+
+        event.map(|event| on_key(self, event));
+        data.on_key = Some(on_key);
+
+        match msg {
+            WM_SYSKEYDOWN | WM_SYSKEYUP => None,
+            _ => Some(0),
+        }
+    }
+
+    fn handle_char(&mut self, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let data = unsafe { self.hnd.data_mut() };
+        let mut on_text_input = unwrap_or_ret!(data.on_text_input.take(), None);
+
+        if let Some(ch) = data.chars.push(wparam as u16) {
+            if !ch.is_control() {
+                let repeat_count = KeyFlags::from_lparam(lparam).repeat_count;
+                on_text_input(self, TextInput { ch: ch, repeat_count: repeat_count });
+            }
+        }
+
+        data.on_text_input = Some(on_text_input);
+        Some(0)
+    }
+
+    /// Click `button` when Enter is pressed anywhere in the window, except in controls that
+    /// use Enter themselves. It's drawn with a heavier border. Only works on top-level
+    /// windows.
+    pub fn set_default_button(&mut self, button: Option<&Button>) {
+        let data = unsafe { self.hnd.data_mut() };
+        let set_style = |hwnd: HWND, style: DWORD| unsafe {
+            user32::SendMessageW(hwnd, BM_SETSTYLE, style as WPARAM, TRUE as LPARAM);
+        };
+
+        data.default_button.map(|old| set_style(old, BS_PUSHBUTTON));
+        data.default_button = button.map(|button| button.ptr());
+        data.default_button.map(|new| set_style(new, BS_DEFPUSHBUTTON));
+    }
+
+    /// Click `button` when Escape is pressed anywhere in the window. Only works on
+    /// top-level windows.
+    pub fn set_cancel_button(&mut self, button: Option<&Button>) {
+        unsafe {
+            self.hnd.data_mut().cancel_button = button.map(|button| button.ptr());
+        }
+    }
+
+    /// The keys for `IsDialogMessageW()` to leave to the window when it has the focus.
+    fn dialog_code(&self) -> Option<LRESULT> {
+        let data = unsafe { self.hnd.data_mut() };
+
+        // Tab, Enter and Escape still navigate.
+        if data.on_key.is_some() || data.on_text_input.is_some() {
+            Some(DLGC_WANTARROWS | DLGC_WANTCHARS)
+        } else {
+            None
+        }
+    }
+
+    /// Keep track of the focused child while the window is inactive, as dialogs do.
+    fn handle_activate(&mut self, wparam: WPARAM) -> Option<LRESULT> {
+        let data = unsafe { self.hnd.data_mut() };
+        let hwnd = self.hnd.hwnd();
+
+        unsafe {
+            if LOWORD(wparam as DWORD) == WA_INACTIVE {
+                let focus = user32::GetFocus();
+                let is_child = !focus.is_null() && user32::IsChild(hwnd, focus) != 0;
+                data.last_focus = if is_child { Some(focus) } else { None };
+                None
+            } else {
+                match data.last_focus.take() {
+                    // The child may have been destroyed since.
+                    Some(focus) if user32::IsChild(hwnd, focus) != 0 => {
+                        user32::SetFocus(focus);
+                        Some(0)
+                    },
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Show `menu` as the menu bar, or remove the menu bar with `None`. Only works on
+    /// top-level windows.
+    pub fn set_menu(&mut self, menu: Option<&Menu>) {
+        let handle = menu.map(MenuHandle::bar);
+        let hmenu = handle.as_ref().map_or(ptr::null_mut(), MenuHandle::hmenu);
+
+        self.show_shortcuts(hmenu);
+
+        unsafe {
+            user32::SetMenu(self.hnd.hwnd(), hmenu);
+
+            // The old menu is destroyed now that it's detached.
+            self.hnd.data_mut().menu = handle;
+        }
+    }
+
+    /// Show `menu` at `pos`, relative to the client area, and wait until an item is chosen
+    /// or the menu is dismissed. Items with `CommandId(0)` can't be told apart from
+    /// dismissing the menu, so they don't trigger commands.
+    pub fn show_context_menu(&mut self, menu: &Menu, pos: Point) {
+        let handle = MenuHandle::popup(menu);
+        self.show_shortcuts(handle.hmenu());
+
+        let dpi = self.dpi();
+        let mut point = POINT { x: dpi.scale(pos.x), y: dpi.scale(pos.y) };
+
+        let id = unsafe {
+            user32::ClientToScreen(self.hnd.hwnd(), &mut point);
+
+            TrackPopupMenuEx(
+                handle.hmenu(), TPM_RETURNCMD | TPM_RIGHTBUTTON, point.x, point.y,
+                self.hnd.hwnd(), ptr::null_mut()
+            )
+        };
+
+        if id == 0 {
+            return;
+        }
+
+        let id = unsafe {
+            match menu::find(handle.hmenu(), CommandId(id as u16)) {
+                Some((hmenu, pos)) => menu::activate(hmenu, pos),
+                None => None,
+            }
+        };
+
+        if let Some(id) = id {
+            self.fire_command(Command { id: id, source: CommandSource::ContextMenu });
+        }
+    }
+
+    /// Whether the item triggering `id` in the menu bar is checked.
+    pub fn is_menu_item_checked(&self, id: CommandId) -> bool {
+        let hmenu = unsafe { user32::GetMenu(self.hnd.hwnd()) };
+        !hmenu.is_null() && unsafe { menu::is_checked(hmenu, id) }
+    }
+
+    /// Check or uncheck the item triggering `id` in the menu bar.
+    pub fn set_menu_item_checked(&mut self, id: CommandId, checked: bool) {
+        unsafe {
+            let hmenu = user32::GetMenu(self.hnd.hwnd());
+
+            if !hmenu.is_null() {
+                menu::set_checked(hmenu, id, checked);
+            }
+        }
+    }
+
+    /// Enable or gray out the item triggering `id` in the menu bar.
+    pub fn set_menu_item_enabled(&mut self, id: CommandId, enabled: bool) {
+        unsafe {
+            let hmenu = user32::GetMenu(self.hnd.hwnd());
+
+            if !hmenu.is_null() {
+                menu::set_enabled(hmenu, id, enabled);
+            }
+        }
+    }
+
+    /// Trigger `id` when `accelerator` is pressed while the focus is in this window,
+    /// replacing the command it triggered before. The shortcut is shown in the labels of
+    /// the items triggering `id` in the menu bar and context menus.
+    pub fn add_accelerator(&mut self, accelerator: Accelerator, id: CommandId) {
+        accel::bind(self.hnd.hwnd(), accelerator, id);
+        self.update_menu_shortcuts();
+    }
+
+    pub fn remove_accelerator(&mut self, accelerator: Accelerator) {
+        accel::unbind(self.hnd.hwnd(), accelerator);
+        self.update_menu_shortcuts();
+    }
+
+    fn update_menu_shortcuts(&self) {
+        unsafe {
+            let hmenu = user32::GetMenu(self.hnd.hwnd());
+
+            if !hmenu.is_null() {
+                self.show_shortcuts(hmenu);
+                user32::DrawMenuBar(self.hnd.hwnd());
+            }
+        }
+    }
+
+    /// Show the accelerators bound on this window in the labels of `hmenu`.
+    fn show_shortcuts(&self, hmenu: HMENU) {
+        let hwnd = self.hnd.hwnd();
+
+        if !hmenu.is_null() {
+            unsafe {
+                menu::show_shortcuts(hmenu, &|id| accel::shortcut(hwnd, id));
+            }
+        }
+    }
+
+    fn handle_menu_command(&mut self, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let id = unsafe { menu::activate(lparam as HMENU, wparam as UINT) };
+
+        if let Some(id) = id {
+            self.fire_command(Command { id: id, source: CommandSource::Menu });
+        }
+
+        Some(0)
+    }
+
+    /// Called when a toolbar button or menu item is clicked, or a shortcut is pressed.
+    pub fn on_command<F>(&mut self, on_command: F) -> &mut Self
+    where F: FnMut(&mut Window, Command) + 'static {
+        unsafe {
+            self.hnd.data_mut().on_command = Some(Box::new(on_command));
+        }
+
+        self
+    }
+
+    fn update_bars(&mut self) {
+        let data = unsafe { self.hnd.data_mut() };
+
+        data.tool_bar.as_mut().map(ToolBar::parent_resized);
+        data.status_bar.as_mut().map(StatusBar::parent_resized);
+    }
+
+    fn handle_command(&mut self, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let data = unsafe { self.hnd.data_mut() };
+
+        let command = match CommandMsg::from_params(wparam, lparam) {
+            CommandMsg::Menu(id) if id == IDOK || id == IDCANCEL => {
+                let button = if id == IDOK { data.default_button } else { data.cancel_button };
+
+                if let Some(button) = button {
+                    unsafe {
+                        if user32::IsWindowEnabled(button) != 0 {
+                            user32::SendMessageW(button, BM_CLICK, 0, 0);
+                        }
+                    }
+                }
+
+                return Some(0);
+            },
+            CommandMsg::Accelerator(id) => {
+                // Keep the menu bar in step, as if the item was chosen there.
+                unsafe {
+                    let hmenu = user32::GetMenu(self.hnd.hwnd());
+
+                    if let Some((hmenu, pos)) = menu::find(hmenu, CommandId(id)) {
+                        menu::activate(hmenu, pos);
+                    }
+                }
+
+                Command { id: CommandId(id), source: CommandSource::Accelerator }
+            },
+            CommandMsg::Control { id, hwnd, .. } 
+                if data.tool_bar.as_ref().map_or(false, |tool_bar| tool_bar.ptr() == hwnd) =>
+                Command { id: CommandId(id), source: CommandSource::ToolBar },
+            _ => return None,
+        };
+
+        self.fire_command(command);
+        Some(0)
+    }
+
+    fn fire_command(&mut self, command: Command) {
+        let data = unsafe { self.hnd.data_mut() };
+
+        if let Some(mut on_command) = data.on_command.take() {
+            on_command(self, command);
+            data.on_command = Some(on_command);
+        }
+    }
+}
+
+/// Creates a `Window` with optional attached bars.
+pub struct WindowBuilder {
+    data: Data,
+    status_bar: Option<Box<FnBox(&mut StatusBar)>>,
+    tool_bar: Option<Box<FnBox(&mut ToolBar)>>,
+    menu: Option<Menu>,
+}
+
+impl WindowBuilder {
+    /// Called once the window is created, before any bars are attached.
+    pub fn on_create<F>(mut self, on_create: F) -> Self where F: FnMut(&mut Window) + 'static {
+        self.data.on_create = Some(Box::new(on_create));
+        self
+    }
+
+    /// Paint the window off-screen first, see `Window::set_double_buffered()`.
+    pub fn double_buffered(mut self, double_buffered: bool) -> Self {
+        self.data.double_buffered = double_buffered;
+        self
+    }
+
+    /// Attach a status bar to the bottom of the window, calling `init` to set it up.
+    pub fn status_bar<F>(mut self, init: F) -> Self where F: FnOnce(&mut StatusBar) + 'static {
+        self.status_bar = Some(Box::new(init));
+        self
+    }
+
+    /// Attach a toolbar to the top of the window, calling `init` to add its buttons.
+    pub fn tool_bar<F>(mut self, init: F) -> Self where F: FnOnce(&mut ToolBar) + 'static {
+        self.tool_bar = Some(Box::new(init));
+        self
+    }
+
+    /// Show `menu` as the menu bar.
+    pub fn menu(mut self, menu: Menu) -> Self {
+        self.menu = Some(menu);
+        self
+    }
+
+    pub fn build(self) -> Window {
+        let hnd = WindowHandle::create_instance(Class, self.data).unwrap();
+        let mut window = Window { hnd: hnd };
+
+        if let Some(ref menu) = self.menu {
+            window.set_menu(Some(menu));
+        }
+
+        if let Some(init) = self.tool_bar {
+            let mut tool_bar = ToolBar::new(&window);
+            init(&mut tool_bar);
+
+            unsafe {
+                window.hnd.data_mut().tool_bar = Some(tool_bar);
+            }
+        }
+
+        if let Some(init) = self.status_bar {
+            let mut status_bar = StatusBar::new(&window);
+            init(&mut status_bar);
+
+            unsafe {
+                window.hnd.data_mut().status_bar = Some(status_bar);
+            }
+        }
+
+        window.update_bars();
+        window
+    }
+}
+
+impl AbsWindow for Window {
+    fn ptr(&self) -> HWND {
+        self.hnd.hwnd()
+    }
+}
+
+/// Stop docking or anchoring `hwnd` in its parent, if that's a `Window`. Called when `hwnd`
+/// is destroyed, so the layout doesn't keep positioning it.
+#[doc(hidden)]
+pub fn forget_child(hwnd: HWND) {
+    unsafe {
+        let parent = user32::GetParent(hwnd);
+
+        if let Some(parent) = WindowHandle::<Class>::downcast(parent) {
+            parent.data_mut().anchors.forget(hwnd);
+        }
+    }
 }
 
 #[derive(Default)]
 struct Data {
     title: WinString,
+    parent: Option<HWND>,
+    status_bar: Option<StatusBar>,
+    tool_bar: Option<ToolBar>,
+    menu: Option<MenuHandle>,
+    layout: Option<Box<LayoutItem>>,
+    anchors: AnchorLayout,
+    min_size: Option<Size>,
+    max_size: Option<Size>,
+    aspect_ratio: Option<f64>,
     on_create: Option<Box<FnMut(&mut Window)>>,
     on_show: Option<Box<FnMut(&mut Window)>>,
+    on_command: Option<Box<FnMut(&mut Window, Command)>>,
+    on_dpi_changed: Option<Box<FnMut(&mut Window, Dpi)>>,
+    theme: Theme,
+    on_theme_changed: Option<Box<FnMut(&mut Window, Theme)>>,
+    on_paint: Option<Box<FnMut(&mut Window, &mut Canvas)>>,
+    double_buffered: bool,
+    pixel_surface: Option<PixelSurface>,
+    on_mouse: Option<Box<FnMut(&mut Window, MouseEvent)>>,
+    on_double_click: Option<Box<FnMut(&mut Window, MouseEvent)>>,
+    /// Whether `TrackMouseEvent()` is waiting for the mouse to leave.
+    hovering: bool,
+    on_key: Option<Box<FnMut(&mut Window, KeyEvent)>>,
+    on_text_input: Option<Box<FnMut(&mut Window, TextInput)>>,
+    chars: Utf16Chars,
+    default_button: Option<HWND>,
+    cancel_button: Option<HWND>,
+    /// The child that had the focus when the window was last deactivated.
+    last_focus: Option<HWND>,
 }
 
 impl Data {
@@ -51,6 +1059,24 @@ impl WindowData for Data {
     fn name(&self) -> Option<&WinString> {
         Some(&self.title)
     }
+
+    fn parent(&self) -> HWND {
+        self.parent.unwrap_or(ptr::null_mut())
+    }
+
+    fn style(&self) -> DWORD {
+        match self.parent {
+            Some(_) => WS_CHILD | WS_VISIBLE | WS_CLIPCHILDREN | WS_CLIPSIBLINGS,
+            None => WS_OVERLAPPEDWINDOW | WS_CLIPCHILDREN,
+        }
+    }
+
+    fn ex_style(&self) -> DWORD {
+        match self.parent {
+            Some(_) => WS_EX_CONTROLPARENT,
+            None => WS_EX_CLIENTEDGE | WS_EX_CONTROLPARENT,
+        }
+    }
 }
 
 struct Class;
@@ -60,6 +1086,7 @@ impl WindowEvents for Class {
 
     fn on_create(hnd: &WindowHandle<Self>) {
         let cb = unsafe {
+            hnd.data_mut().theme = theme::current();
             hnd.data_mut().on_create.take()
         };
         let mut wnd = Window { hnd: hnd.clone() };
@@ -76,6 +1103,63 @@ impl WindowEvents for Class {
         
         cb.map(|on_show| (on_show)(&mut wnd));
     }
+
+    fn on_destroy(hnd: &WindowHandle<Self>) {
+        font::set_window_font(hnd.hwnd(), None);
+
+        // Windows would destroy the menu bar with the window, but it belongs to the data.
+        unsafe {
+            user32::SetMenu(hnd.hwnd(), ptr::null_mut());
+        }
+    }
+
+    fn handle_msg(hnd: &WindowHandle<Self>, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        let mut wnd = Window { hnd: hnd.clone() };
+
+        match msg {
+            WM_SIZE => {
+                wnd.update_bars();
+                wnd.relayout();
+                wnd.resize_pixel_surface(lparam);
+                None
+            },
+            WM_COMMAND => wnd.handle_command(wparam, lparam),
+            WM_MENUCOMMAND => wnd.handle_menu_command(wparam, lparam),
+            WM_PAINT if wnd.paints_itself() => {
+                wnd.handle_paint();
+                Some(0)
+            },
+            // Erasing in `handle_paint()` instead avoids showing the bare background.
+            WM_ERASEBKGND if wnd.erases_while_painting() => Some(TRUE as LRESULT),
+            WM_GETMINMAXINFO => {
+                wnd.handle_min_max_info(unsafe { &mut *(lparam as *mut MINMAXINFO) });
+                Some(0)
+            },
+            WM_DPICHANGED => {
+                wnd.handle_dpi_changed(wparam, unsafe { &*(lparam as *const RECT) });
+                Some(0)
+            },
+            WM_SETTINGCHANGE if unsafe { theme::is_theme_change(lparam) } => {
+                theme::refresh();
+                wnd.handle_theme_changed();
+                None
+            },
+            WM_MOUSEMOVE | WM_MOUSELEAVE | WM_MOUSEWHEEL | WM_MOUSEHWHEEL |
+            WM_LBUTTONDOWN | WM_LBUTTONUP | WM_LBUTTONDBLCLK |
+            WM_RBUTTONDOWN | WM_RBUTTONUP | WM_RBUTTONDBLCLK |
+            WM_MBUTTONDOWN | WM_MBUTTONUP | WM_MBUTTONDBLCLK |
+            WM_XBUTTONDOWN | WM_XBUTTONUP | WM_XBUTTONDBLCLK => wnd.handle_mouse(msg, wparam, lparam),
+            WM_KEYDOWN | WM_KEYUP | WM_SYSKEYDOWN | WM_SYSKEYUP => wnd.handle_key(msg, wparam, lparam),
+            WM_CHAR => wnd.handle_char(wparam, lparam),
+            WM_GETDLGCODE => wnd.dialog_code(),
+            WM_ACTIVATE => wnd.handle_activate(wparam),
+            WM_SIZING => {
+                wnd.handle_sizing(wparam, unsafe { &mut *(lparam as *mut RECT) });
+                Some(TRUE as LRESULT)
+            },
+            _ => None,
+        }
+    }
 }
 
 impl CustomClass for Class {
@@ -29,6 +29,11 @@ impl WinString {
         self.data.push(0);
     } 
 
+    /// Get the UTF-16 data, including the NUL terminator.
+    pub fn as_wide(&self) -> &[u16] {
+        &self.data
+    }
+
     pub fn as_ptr(&self) -> *const u16 {
         self.data.as_ptr()
     }
@@ -49,7 +54,8 @@ impl WinString {
     }
 
     pub fn to_string(&self) -> String {
-        String::from_utf16_lossy(&self.data)
+        let len = self.data.iter().position(|&c| c == 0).unwrap_or(self.data.len());
+        String::from_utf16_lossy(&self.data[..len])
     }
 }
 